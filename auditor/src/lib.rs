@@ -0,0 +1,666 @@
+//! A Rust crate for auditing an Open Vote Network election independently of the *voting* contract.
+//!
+//! The auditor replays the election transcript: the messages every voter sent to the contract, in the order they registered.
+//! It re-verifies every ZKP with the same `voting::crypto` functions the contract uses, recomputes each reconstructed key,
+//! checks every commitment against the vote and recomputes the tally, such that nobody needs to trust the contract run.
+//!
+//! The transcript is a JSON file of the form:
+//!
+//! ```json
+//! {
+//!   "contract_address": { "index": 5124, "subindex": 0 },
+//!   "election_id": 0,
+//!   "config": <the voteconfig.json the contract was initialized with>,
+//!   "voters": [
+//!     { "account": "<base58 address>", "register": "<hex>", "commit": "<hex>", "vote": "<hex>", "recovery": "<hex>" }
+//!   ],
+//!   "result": [[3, 2]]
+//! }
+//! ```
+//!
+//! where each message is the hex encoded binary parameter the voter sent (as created by the voter CLI), and a voter
+//! who did not send a message has it left out or null. The result is the stored result to check, with the counts of each question, and can be left out.
+//! Every check of a voter is done on each question of the ballot, and fails if it fails on any of them.
+
+use base58check::*;
+use concordium_std::*;
+use group::GroupEncoding;
+use k256::ProjectivePoint;
+use serde_json::Value;
+use util::{OneInKZKP, OneInTwoZKP, ProofContext};
+
+#[cfg(test)]
+mod tests;
+use voting::{
+    crypto, types, CommitMessage, RecoveryMessage, RegisterMessage, VoteMessage, VoteZKP,
+};
+
+/// The messages a voter sent to the contract
+pub struct VoterTranscript {
+    // Base58 address of the voter's account
+    pub address: String,
+    pub account: AccountAddress,
+    pub register: RegisterMessage,
+    pub commit: Option<CommitMessage>,
+    pub vote: Option<VoteMessage>,
+    pub recovery: Option<RecoveryMessage>,
+}
+
+impl VoterTranscript {
+    /// Whether every message the voter sent is for the given election of the contract instance
+    fn sent_to(&self, election_id: u64) -> bool {
+        self.register.election_id == election_id
+            && self.commit.as_ref().is_none_or(|m| m.election_id == election_id)
+            && self.vote.as_ref().is_none_or(|m| m.election_id == election_id)
+            && self.recovery.as_ref().is_none_or(|m| m.election_id == election_id)
+    }
+}
+
+/// The full transcript of an election, with the voters in the order they registered
+pub struct ElectionTranscript {
+    pub contract_address: ContractAddress,
+    pub election_id: u64,
+    pub merkle_root: String,
+    pub merkle_leaf_count: i32,
+    // Sum of the weights of the eligible voters
+    pub total_weight: i32,
+    // 0 for a yes/no vote, otherwise the number of candidates
+    pub number_of_options: usize,
+    pub ballot_type: types::BallotType,
+    pub number_of_questions: usize,
+    pub voters: Vec<VoterTranscript>,
+    // The result of each question stored by the contract, if it is to be checked
+    pub stored_result: Option<Vec<Vec<i32>>>,
+}
+
+impl ElectionTranscript {
+    /// Bounds (max_score, budget) of an approval or score ballot, the same as the contract's. None for single votes and veto elections
+    fn score_range(&self) -> Option<(u64, u64)> {
+        match &self.ballot_type {
+            types::BallotType::Approval(max_approvals) => Some((1, *max_approvals as u64)),
+            types::BallotType::Score(rule) => Some((rule.max_score as u64, rule.budget as u64)),
+            types::BallotType::Single | types::BallotType::Veto => None,
+        }
+    }
+
+    fn is_veto(&self) -> bool {
+        self.ballot_type == types::BallotType::Veto
+    }
+
+    /// Base m of the packed tally, the same as the contract's: larger than the weighted count or score sum any candidate can get
+    fn tally_base(&self) -> u64 {
+        let max_score = self.score_range().map_or(1, |(max_score, _)| max_score);
+        self.total_weight as u64 * max_score + 1
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CheckResult {
+    Passed,
+    Failed,
+    // The voter did not send the message the check is on
+    NotSent,
+}
+
+impl CheckResult {
+    /// The result of a check over several questions: failed if it failed on any, and not sent if the message was not sent
+    fn all(results: impl Iterator<Item = CheckResult>) -> CheckResult {
+        results.fold(CheckResult::Passed, |acc, result| {
+            match (acc, result) {
+                (CheckResult::Failed, _) | (_, CheckResult::Failed) => CheckResult::Failed,
+                (CheckResult::NotSent, _) | (_, CheckResult::NotSent) => CheckResult::NotSent,
+                _ => CheckResult::Passed,
+            }
+        })
+    }
+}
+
+/// The checks of a single voter's messages
+pub struct VoterReport {
+    pub index: i32,
+    pub address: String,
+    // Merkle proof that the account is eligible
+    pub eligibility: CheckResult,
+    // Schnorr ZKP of the voting key
+    pub voting_key_zkp: CheckResult,
+    // Reconstructed key sent at commit equals the one recomputed from the voting keys
+    pub reconstructed_key: CheckResult,
+    // Commitment sent at commit matches the vote
+    pub commitment: CheckResult,
+    // One-in-two (one-in-k, range or veto) ZKP of the vote, against the recomputed reconstructed key
+    pub vote_zkp: CheckResult,
+    // Chaum-Pedersen ZKP of the recovery key, if the voter had to take part in the recovery round
+    pub recovery_zkp: CheckResult,
+}
+
+impl VoterReport {
+    /// The checks as (name, result) pairs, in the order of the election
+    pub fn checks(&self) -> [(&'static str, CheckResult); 6] {
+        [
+            ("eligibility", self.eligibility),
+            ("voting key ZKP", self.voting_key_zkp),
+            ("reconstructed key", self.reconstructed_key),
+            ("commitment", self.commitment),
+            ("vote ZKP", self.vote_zkp),
+            ("recovery ZKP", self.recovery_zkp),
+        ]
+    }
+
+    /// A voter passes if none of the messages they sent failed a check
+    pub fn passed(&self) -> bool {
+        self.checks()
+            .iter()
+            .all(|(_, result)| *result != CheckResult::Failed)
+    }
+}
+
+pub struct AuditReport {
+    pub voters: Vec<VoterReport>,
+    // The recomputed result of each question, None if the votes of any question do not tally. Veto elections have no counts
+    pub tally: Option<Vec<Vec<i32>>>,
+    // Whether anyone vetoed each question of a veto election, None for other elections or if the votes are not valid points
+    pub vetoed: Option<Vec<bool>>,
+    // Whether the stored result equals the recomputed one, None if there is no stored result to check
+    pub result_matches: Option<bool>,
+}
+
+impl AuditReport {
+    /// The election passes if every voter passed, the votes tally and the stored result (if any) matches the tally
+    pub fn passed(&self) -> bool {
+        self.voters.iter().all(|v| v.passed())
+            && self.tally.is_some()
+            && self.result_matches != Some(false)
+    }
+}
+
+#[derive(Debug)]
+pub enum AuditError {
+    // Failed reading the transcript file
+    Io(std::io::Error),
+    // The transcript is malformed, with a description of where
+    InvalidTranscript(String),
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::Io(e) => write!(f, "transcript file: {}", e),
+            AuditError::InvalidTranscript(e) => write!(f, "invalid transcript: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<std::io::Error> for AuditError {
+    fn from(e: std::io::Error) -> Self {
+        AuditError::Io(e)
+    }
+}
+
+impl From<AuditError> for std::io::Error {
+    fn from(e: AuditError) -> Self {
+        match e {
+            AuditError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Replay the transcript and check every voter's messages, the tally and the stored result
+pub fn audit(transcript: &ElectionTranscript) -> AuditReport {
+    let questions = 0..transcript.number_of_questions;
+
+    // Voting keys of each question in the order of registration. If any is invalid, no reconstructed key of the question can be recomputed
+    let voting_keys: Vec<Option<Vec<ProjectivePoint>>> = questions
+        .clone()
+        .map(|question| {
+            let keys: Option<Vec<ProjectivePoint>> = transcript
+                .voters
+                .iter()
+                .map(|v| point(v.register.voting_keys.get(question)))
+                .collect();
+            keys.filter(|keys| keys.len() > 1)
+        })
+        .collect();
+
+    let candidate_encodings = if transcript.number_of_options == 0 {
+        None
+    } else {
+        util::candidate_encodings(transcript.number_of_options, transcript.tally_base())
+    };
+
+    // Indices of the voters who did not vote, which the voters who did vote recover the keys of
+    let missing_voters: Vec<i32> = transcript
+        .voters
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| v.vote.is_none())
+        .map(|(i, _)| i as i32)
+        .collect();
+
+    let voters = transcript
+        .voters
+        .iter()
+        .enumerate()
+        .map(|(i, voter)| {
+            let index = i as i32;
+            // The weight proven in the merkle proof, which the vote of the voter counts with
+            let weight = voter.register.merkle_proof.weight as u64;
+            let context = ProofContext {
+                contract_address: transcript.contract_address,
+                election_id: transcript.election_id,
+                sender: voter.account,
+            };
+
+            let eligibility = check(crypto::verify_merkle_proof(
+                &transcript.merkle_root,
+                transcript.merkle_leaf_count,
+                &voter.register.merkle_proof,
+                &voter.account,
+            ) == Ok(true));
+
+            // Every message must hold one entry for each question
+            let one_per_question = |lengths: &[usize]| {
+                lengths
+                    .iter()
+                    .all(|length| *length == transcript.number_of_questions)
+            };
+            let register_complete = one_per_question(&[
+                voter.register.voting_keys.len(),
+                voter.register.voting_key_zkps.len(),
+            ]);
+            let commit_complete = voter.commit.as_ref().is_none_or(|commit| {
+                one_per_question(&[commit.reconstructed_keys.len(), commit.commitments.len()])
+            });
+            let vote_complete = voter.vote.as_ref().is_none_or(|vote| {
+                one_per_question(&[vote.votes.len(), vote.vote_zkps.len()])
+            });
+            let recovery_complete = voter.recovery.as_ref().is_none_or(|recovery| {
+                one_per_question(&[recovery.recovery_keys.len(), recovery.recovery_zkps.len()])
+            });
+
+            let checks: Vec<[CheckResult; 5]> = questions
+                .clone()
+                .map(|question| {
+                    let g_x = point(voter.register.voting_keys.get(question));
+                    let g_y = voting_keys[question]
+                        .as_ref()
+                        .map(|keys| util::compute_reconstructed_key(keys, index));
+                    let vote = voter
+                        .vote
+                        .as_ref()
+                        .map(|v| point(v.votes.get(question)));
+
+                    let voting_key_zkp = check(
+                        register_complete
+                            && g_x.is_some_and(|g_x| {
+                                crypto::verify_schnorr_zkp(
+                                    g_x,
+                                    voter.register.voting_key_zkps[question].clone(),
+                                    &context,
+                                ) == Ok(true)
+                            }),
+                    );
+
+                    let reconstructed_key = match (&voter.commit, g_y) {
+                        (None, _) => CheckResult::NotSent,
+                        (Some(commit), g_y) => check(
+                            commit_complete
+                                && g_y.map(|g_y| g_y.to_bytes().to_vec())
+                                    == Some(commit.reconstructed_keys[question].clone()),
+                        ),
+                    };
+
+                    let commitment = match (&voter.commit, vote) {
+                        (_, None) => CheckResult::NotSent,
+                        (Some(commit), Some(Some(vote))) if commit_complete => check(
+                            crypto::check_commitment(vote, commit.commitments[question].clone()),
+                        ),
+                        _ => CheckResult::Failed,
+                    };
+
+                    let vote_zkp = match (&voter.vote, vote, g_y) {
+                        (None, _, _) => CheckResult::NotSent,
+                        (Some(message), Some(Some(vote)), Some(g_y)) if vote_complete => {
+                            check(match (
+                                &message.vote_zkps[question],
+                                &candidate_encodings,
+                                transcript.score_range(),
+                            ) {
+                                // The proofs must be about the registered voting key, like the range proof
+                                (VoteZKP::OneInTwo(zkp), None, None) if !transcript.is_veto() => {
+                                    OneInTwoZKP::extract_points(zkp)
+                                        .map(|points| (Some(points.0), points.1))
+                                        == Ok((g_x, vote))
+                                        && crypto::verify_one_in_two_zkp(
+                                            zkp.clone(),
+                                            g_y,
+                                            weight,
+                                            &context,
+                                        ) == Ok(true)
+                                }
+                                (VoteZKP::OneInK(zkp), Some(encodings), None) => {
+                                    OneInKZKP::extract_points(zkp)
+                                        .map(|points| (Some(points.0), points.1))
+                                        == Ok((g_x, vote))
+                                        && crypto::verify_one_in_k_zkp(
+                                            zkp.clone(),
+                                            g_y,
+                                            &util::weighted_encodings(encodings, weight),
+                                            &context,
+                                        ) == Ok(true)
+                                }
+                                (
+                                    VoteZKP::Range(zkp),
+                                    Some(encodings),
+                                    Some((max_score, budget)),
+                                ) => g_x.is_some_and(|g_x| {
+                                    crypto::verify_range_zkp(
+                                        zkp.clone(),
+                                        g_x,
+                                        g_y,
+                                        vote,
+                                        max_score,
+                                        budget,
+                                        encodings,
+                                        weight,
+                                        &context,
+                                    ) == Ok(true)
+                                }),
+                                (VoteZKP::Veto(zkp), None, None) if transcript.is_veto() => {
+                                    crypto::verify_veto_zkp(g_y, vote, zkp.clone(), &context)
+                                        == Ok(true)
+                                }
+                                _ => false,
+                            })
+                        }
+                        _ => CheckResult::Failed,
+                    };
+
+                    let recovery_zkp = match &voter.recovery {
+                        None => CheckResult::NotSent,
+                        // Only voters who voted take part in the recovery, and only if someone did not vote
+                        Some(_) if vote.is_none() || missing_voters.is_empty() => {
+                            CheckResult::Failed
+                        }
+                        Some(recovery) => check(
+                            recovery_complete
+                                && match (
+                                    g_x,
+                                    &voting_keys[question],
+                                    point(recovery.recovery_keys.get(question)),
+                                ) {
+                                    (Some(g_x), Some(keys), Some(h_x)) => {
+                                        let h = util::compute_recovery_base(
+                                            keys,
+                                            index,
+                                            &missing_voters,
+                                        );
+                                        crypto::verify_chaum_pedersen_zkp(
+                                            g_x,
+                                            h,
+                                            h_x,
+                                            recovery.recovery_zkps[question].clone(),
+                                            &context,
+                                        ) == Ok(true)
+                                    }
+                                    _ => false,
+                                },
+                        ),
+                    };
+
+                    [voting_key_zkp, reconstructed_key, commitment, vote_zkp, recovery_zkp]
+                })
+                .collect();
+            let combined = |check: usize| CheckResult::all(checks.iter().map(|c| c[check]));
+
+            VoterReport {
+                index,
+                address: voter.address.clone(),
+                eligibility,
+                voting_key_zkp: combined(0),
+                reconstructed_key: combined(1),
+                commitment: combined(2),
+                vote_zkp: combined(3),
+                recovery_zkp: combined(4),
+            }
+        })
+        .collect();
+
+    let tally = questions
+        .clone()
+        .map(|question| compute_tally(transcript, question, &candidate_encodings))
+        .collect::<Option<Vec<_>>>();
+    let vetoed = match transcript.is_veto() {
+        true => questions
+            .map(|question| {
+                vote_aggregate(transcript, question)
+                    .map(|(aggregate, _)| aggregate != ProjectivePoint::IDENTITY)
+            })
+            .collect::<Option<Vec<_>>>(),
+        false => None,
+    };
+    let result_matches = transcript
+        .stored_result
+        .as_ref()
+        .map(|stored| Some(stored) == tally.as_ref());
+
+    AuditReport {
+        voters,
+        tally,
+        vetoed,
+        result_matches,
+    }
+}
+
+/// Sum the votes of a question, removing the recovery keys. Returns the sum and the weight that voted, or None if any is not a valid point
+fn vote_aggregate(transcript: &ElectionTranscript, question: usize) -> Option<(ProjectivePoint, u64)> {
+    let mut vote_aggregate = ProjectivePoint::IDENTITY;
+    let mut voted_weight: u64 = 0;
+    for voter in transcript.voters.iter() {
+        if let Some(vote) = &voter.vote {
+            vote_aggregate += point(vote.votes.get(question))?;
+            voted_weight += voter.register.merkle_proof.weight as u64;
+        }
+        if let Some(recovery) = &voter.recovery {
+            vote_aggregate -= point(recovery.recovery_keys.get(question))?;
+        }
+    }
+    Some((vote_aggregate, voted_weight))
+}
+
+/// Recompute the result of a question from the votes and recovery keys in the transcript, the same way the contract does.
+/// Returns the weighted (yes, no) for yes/no votes, the count or score sum of each candidate for votes between candidates and no counts for veto elections
+fn compute_tally(
+    transcript: &ElectionTranscript,
+    question: usize,
+    candidate_encodings: &Option<Vec<u64>>,
+) -> Option<Vec<i32>> {
+    let (vote_aggregate, voted_weight) = vote_aggregate(transcript, question)?;
+    if transcript.is_veto() {
+        return Some(Vec::new());
+    }
+
+    match candidate_encodings {
+        Some(encodings) => {
+            let bound = match transcript.score_range() {
+                Some((max_score, _)) => voted_weight * max_score * encodings.iter().sum::<u64>(),
+                None => voted_weight * encodings.last()?,
+            };
+            let tally = crypto::compute_tally(vote_aggregate, bound)?;
+            Some(util::decode_candidate_tally(
+                tally,
+                encodings.len(),
+                transcript.tally_base(),
+            ))
+        }
+        None => {
+            let yes_votes = crypto::compute_tally(vote_aggregate, voted_weight)?;
+            Some(vec![
+                yes_votes as i32,
+                (voted_weight - yes_votes) as i32,
+            ])
+        }
+    }
+}
+
+/// The point of a question's entry in a message, None if it is missing or not a valid point
+fn point(bytes: Option<&Vec<u8>>) -> Option<ProjectivePoint> {
+    util::try_convert_vec_to_point(bytes?).ok()
+}
+
+fn check(passed: bool) -> CheckResult {
+    if passed {
+        CheckResult::Passed
+    } else {
+        CheckResult::Failed
+    }
+}
+
+/// Parse the transcript from its JSON form (see the crate documentation)
+pub fn parse_transcript(json: &Value) -> Result<ElectionTranscript, AuditError> {
+    let contract_address = &json["contract_address"];
+    let config = &json["config"];
+
+    let voters = json["voters"]
+        .as_array()
+        .ok_or_else(|| invalid("expected a list of voters"))?
+        .iter()
+        .enumerate()
+        .map(|(i, voter)| parse_voter(voter).map_err(|e| invalid(&format!("voter {}: {}", i, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let stored_result = match &json["result"] {
+        Value::Null => None,
+        result => Some(
+            result
+                .as_array()
+                .and_then(|questions| {
+                    questions
+                        .iter()
+                        .map(|counts| {
+                            counts
+                                .as_array()?
+                                .iter()
+                                .map(|c| c.as_i64().map(|c| c as i32))
+                                .collect::<Option<Vec<_>>>()
+                        })
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or_else(|| invalid("expected the result as a list of counts for each question"))?,
+        ),
+    };
+
+    match (
+        contract_address["index"].as_u64(),
+        contract_address["subindex"].as_u64(),
+        json["election_id"].as_u64(),
+        config["merkle_root"].as_str(),
+        config["merkle_leaf_count"].as_i64(),
+        config["total_weight"].as_i64(),
+        config["voting_options"].as_array(),
+        config["voting_questions"].as_array(),
+        parse_ballot_type(&config["ballot_type"]),
+    ) {
+        (
+            Some(index),
+            Some(subindex),
+            Some(election_id),
+            Some(merkle_root),
+            Some(merkle_leaf_count),
+            Some(total_weight),
+            Some(voting_options),
+            Some(voting_questions),
+            Some(ballot_type),
+        ) if merkle_leaf_count > 0
+            && total_weight >= merkle_leaf_count
+            && total_weight <= i32::MAX as i64
+            && !voting_questions.is_empty() =>
+        {
+            // A contract instance hosts many elections, and the messages must all be for the audited one
+            if let Some(i) = voters.iter().position(|voter| !voter.sent_to(election_id)) {
+                return Err(invalid(&format!("voter {}: message of another election", i)));
+            }
+
+            Ok(ElectionTranscript {
+                contract_address: ContractAddress { index, subindex },
+                election_id,
+                merkle_root: merkle_root.to_string(),
+                merkle_leaf_count: merkle_leaf_count as i32,
+                total_weight: total_weight as i32,
+                number_of_options: voting_options.len(),
+                ballot_type,
+                number_of_questions: voting_questions.len(),
+                voters,
+                stored_result,
+            })
+        }
+        _ => Err(invalid(
+            "expected the contract address, election id and config (merkle root, leaf count, total weight, voting options, ballot type and questions)",
+        )),
+    }
+}
+
+/// Parse the ballot type of the config, in the JSON form concordium-client takes (e.g. {"Approval": [2]})
+fn parse_ballot_type(json: &Value) -> Option<types::BallotType> {
+    let bound = |value: &Value| value.as_u64().filter(|b| *b <= u32::MAX as u64).map(|b| b as u32);
+    let ballot_type = json.as_object()?;
+    if ballot_type.contains_key("Single") {
+        return Some(types::BallotType::Single);
+    }
+    if ballot_type.contains_key("Veto") {
+        return Some(types::BallotType::Veto);
+    }
+    if let Some(max_approvals) = ballot_type.get("Approval") {
+        return Some(types::BallotType::Approval(bound(&max_approvals[0])?));
+    }
+    let rule = &ballot_type.get("Score")?[0];
+    Some(types::BallotType::Score(types::ScoreRule {
+        max_score: bound(&rule["max_score"])?,
+        budget: bound(&rule["budget"])?,
+    }))
+}
+
+fn parse_voter(json: &Value) -> Result<VoterTranscript, String> {
+    let address = json["account"]
+        .as_str()
+        .ok_or("expected the account address")?;
+
+    Ok(VoterTranscript {
+        address: address.to_string(),
+        account: parse_account(address).ok_or("invalid account address")?,
+        register: parse_message(&json["register"])?.ok_or("expected the register message")?,
+        commit: parse_message(&json["commit"])?,
+        vote: parse_message(&json["vote"])?,
+        recovery: parse_message(&json["recovery"])?,
+    })
+}
+
+/// Parse a hex encoded binary parameter, None if it was not sent
+fn parse_message<T: Deserial>(json: &Value) -> Result<Option<T>, String> {
+    match json {
+        Value::Null => Ok(None),
+        Value::String(s) => hex::decode(s)
+            .ok()
+            .and_then(|bytes| from_bytes(&bytes).ok())
+            .map(Some)
+            .ok_or_else(|| "invalid message".to_string()),
+        _ => Err("expected a message as a hex string".to_string()),
+    }
+}
+
+/// Parse a base58check encoded account address
+fn parse_account(address: &str) -> Option<AccountAddress> {
+    let (version, body) = address.from_base58check().ok()?;
+    if version != 1 || body.len() != ACCOUNT_ADDRESS_SIZE {
+        return None;
+    }
+    let mut buf = [0u8; ACCOUNT_ADDRESS_SIZE];
+    buf.copy_from_slice(&body);
+    Some(AccountAddress(buf))
+}
+
+fn invalid(msg: &str) -> AuditError {
+    AuditError::InvalidTranscript(msg.to_string())
+}
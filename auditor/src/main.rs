@@ -0,0 +1,63 @@
+//! Rust binary entry point of the election auditor, printing a pass/fail report for each voter of an election transcript.
+//!
+//! Usage: `cargo run -- <transcript.json>`, with the transcript format described in the *auditor* crate documentation.
+//! Exits with an error if any voter failed a check, the votes do not tally or the stored result does not match the tally.
+
+use auditor::CheckResult;
+use clap::{value_parser, Arg, Command};
+use std::io::Error;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Error> {
+    let matches = Command::new("auditor")
+        .about("Audit an Open Vote Network election by replaying its transcript")
+        .arg(
+            Arg::new("transcript")
+                .help("JSON file with the election transcript")
+                .required(true)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .get_matches();
+
+    let transcript_file = matches.get_one::<PathBuf>("transcript").unwrap();
+    let json = serde_json::from_str(&std::fs::read_to_string(transcript_file)?)?;
+    let transcript = auditor::parse_transcript(&json)?;
+
+    let report = auditor::audit(&transcript);
+
+    for voter in report.voters.iter() {
+        println!(
+            "Voter {} ({}): {}",
+            voter.index,
+            voter.address,
+            if voter.passed() { "PASS" } else { "FAIL" }
+        );
+        for (name, result) in voter.checks().iter() {
+            let result = match result {
+                CheckResult::Passed => "passed",
+                CheckResult::Failed => "FAILED",
+                CheckResult::NotSent => "not sent",
+            };
+            println!("    {}: {}", name, result);
+        }
+    }
+
+    match &report.tally {
+        Some(tally) => println!("Tally: {:?}", tally),
+        None => println!("Tally: the votes do not tally"),
+    }
+    if let Some(vetoed) = &report.vetoed {
+        println!("Vetoed: {:?}", vetoed);
+    }
+    match (&transcript.stored_result, report.result_matches) {
+        (Some(stored), Some(true)) => println!("Stored result {:?} matches the tally", stored),
+        (Some(stored), _) => println!("Stored result {:?} does NOT match the tally", stored),
+        (None, _) => println!("No stored result to check"),
+    }
+
+    if !report.passed() {
+        return Err(Error::other("audit FAILED"));
+    }
+    println!("Audit passed");
+    Ok(())
+}
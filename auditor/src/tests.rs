@@ -0,0 +1,116 @@
+//! Unit tests of the auditor, over the recorded transcript of an honest election in which four of five voters voted
+//! (yes, yes, no, yes) and recovered the key of the fifth.
+
+use crate::*;
+
+/// The recorded transcript as JSON, to tamper with a message before parsing it
+fn transcript_json() -> Value {
+    serde_json::from_str(include_str!("tests/transcript.json")).unwrap()
+}
+
+fn transcript() -> ElectionTranscript {
+    parse_transcript(&transcript_json()).unwrap()
+}
+
+#[test]
+fn test_honest_election() {
+    let report = audit(&transcript());
+
+    assert!(report.passed());
+    assert_eq!(report.voters.len(), 5);
+    assert!(report.voters.iter().all(|v| v.passed()));
+    assert_eq!(report.voters[4].vote_zkp, CheckResult::NotSent);
+    assert_eq!(report.tally, Some(vec![vec![3, 1]]));
+    assert_eq!(report.result_matches, Some(true));
+}
+
+#[test]
+fn test_tampered_vote_zkp() {
+    let mut json = transcript_json();
+    // Flip a bit of the last byte of the vote message, which is in the last point of the one-in-two ZKP
+    let vote = json["voters"][0]["vote"].as_str().unwrap().to_string();
+    let last = u8::from_str_radix(&vote[vote.len() - 2..], 16).unwrap() ^ 1;
+    json["voters"][0]["vote"] = Value::from(format!("{}{:02x}", &vote[..vote.len() - 2], last));
+
+    let report = audit(&parse_transcript(&json).unwrap());
+
+    assert_eq!(report.voters[0].vote_zkp, CheckResult::Failed);
+    assert_eq!(report.voters[0].commitment, CheckResult::Passed);
+    assert!(report.voters[1..].iter().all(|v| v.passed()));
+    assert!(!report.passed());
+}
+
+#[test]
+fn test_vote_not_matching_commitment() {
+    let mut transcript = transcript();
+    // Voter 0 commits to the vote of voter 1
+    let commitments = transcript.voters[1].commit.as_ref().unwrap().commitments.clone();
+    transcript.voters[0].commit.as_mut().unwrap().commitments = commitments;
+
+    let report = audit(&transcript);
+
+    assert_eq!(report.voters[0].commitment, CheckResult::Failed);
+    assert_eq!(report.voters[0].vote_zkp, CheckResult::Passed);
+    assert!(!report.passed());
+}
+
+#[test]
+fn test_wrong_stored_result() {
+    let mut transcript = transcript();
+    transcript.stored_result = Some(vec![vec![2, 2]]);
+
+    let report = audit(&transcript);
+
+    assert!(report.voters.iter().all(|v| v.passed()));
+    assert_eq!(report.tally, Some(vec![vec![3, 1]]));
+    assert_eq!(report.result_matches, Some(false));
+    assert!(!report.passed());
+}
+
+#[test]
+fn test_invalid_recovery_zkp() {
+    let mut transcript = transcript();
+    // Voter 0 sends its own recovery key with the Chaum-Pedersen ZKP of voter 1
+    let recovery_zkps = transcript.voters[1].recovery.as_ref().unwrap().recovery_zkps.clone();
+    transcript.voters[0].recovery.as_mut().unwrap().recovery_zkps = recovery_zkps;
+
+    let report = audit(&transcript);
+
+    assert_eq!(report.voters[0].recovery_zkp, CheckResult::Failed);
+    assert_eq!(report.voters[1].recovery_zkp, CheckResult::Passed);
+    assert!(!report.passed());
+}
+
+#[test]
+fn test_vote_with_other_key() {
+    let mut transcript = transcript();
+    let voter = &transcript.voters[0];
+    let context = ProofContext {
+        contract_address: transcript.contract_address,
+        election_id: transcript.election_id,
+        sender: voter.account,
+    };
+
+    // Voter 0 votes yes with a key x' it did not register, with a valid proof and a matching commitment
+    let voting_keys: Vec<ProjectivePoint> = transcript
+        .voters
+        .iter()
+        .map(|v| point(v.register.voting_keys.first()).unwrap())
+        .collect();
+    let g_y = util::compute_reconstructed_key(&voting_keys, 0);
+    let (x, g_x) = off_chain::create_votingkey_pair();
+    let vote = g_y * x + ProjectivePoint::GENERATOR;
+    let zkp = off_chain::create_one_in_two_zkp_yes(g_x, g_y, x, 1, &context);
+
+    transcript.voters[0].commit.as_mut().unwrap().commitments =
+        vec![off_chain::commit_to_vote(&x, &g_y, ProjectivePoint::GENERATOR)];
+    let message = transcript.voters[0].vote.as_mut().unwrap();
+    message.votes = vec![vote.to_bytes().to_vec()];
+    message.vote_zkps = vec![VoteZKP::OneInTwo(zkp)];
+
+    let report = audit(&transcript);
+
+    assert_eq!(report.voters[0].commitment, CheckResult::Passed);
+    assert_eq!(report.voters[0].vote_zkp, CheckResult::Failed);
+    assert!(!report.passed());
+}
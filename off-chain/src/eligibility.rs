@@ -0,0 +1,234 @@
+//! Eligibility proof bundles, such that voters don't need the full list of eligible voters to build their Merkle proof.
+//!
+//! The organizer exports one bundle per eligible account, holding the account's Merkle proof and weight along with the Merkle root,
+//! the leaf count and the contract address and id of the election. A bundle is either JSON (`.json`) or the binary serialization of
+//! [`EligibilityBundle`] (any other extension). The voter imports their bundle and validates it before registering.
+
+use concordium_std::*;
+use rs_merkle::algorithms::Sha256 as merkle_sha256;
+use rs_merkle::*;
+use serde_json::{json, Value};
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use util::MerkleProof;
+
+/// A voter's proof of eligibility for a single election
+#[derive(Serialize, PartialEq)]
+pub struct EligibilityBundle {
+    pub contract_address: ContractAddress,
+    // Id of the election in the contract instance
+    pub election_id: u64,
+    // Hex encoded Merkle root, as stored in the voteconfig
+    pub merkle_root: String,
+    pub merkle_leaf_count: i32,
+    pub merkle_proof: MerkleProof,
+}
+
+#[derive(Debug)]
+pub enum EligibilityError {
+    // Failed reading or writing the bundle file
+    Io(std::io::Error),
+    // The file is not a valid bundle
+    InvalidFormat,
+    // The bundle belongs to another contract instance or election
+    OtherElection,
+    // The bundle's leaf is not the given account with the bundle's weight
+    OtherAccount,
+    // The Merkle proof does not prove the leaf is in the tree with the bundle's root
+    InvalidProof,
+}
+
+impl std::fmt::Display for EligibilityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EligibilityError::Io(e) => write!(f, "eligibility bundle file: {}", e),
+            EligibilityError::InvalidFormat => write!(f, "not a valid eligibility bundle"),
+            EligibilityError::OtherElection => {
+                write!(f, "eligibility bundle belongs to another election")
+            }
+            EligibilityError::OtherAccount => {
+                write!(f, "eligibility bundle belongs to another account")
+            }
+            EligibilityError::InvalidProof => {
+                write!(f, "eligibility bundle does not prove membership of the Merkle tree")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EligibilityError {}
+
+impl From<std::io::Error> for EligibilityError {
+    fn from(e: std::io::Error) -> Self {
+        EligibilityError::Io(e)
+    }
+}
+
+impl From<EligibilityError> for std::io::Error {
+    fn from(e: EligibilityError) -> Self {
+        match e {
+            EligibilityError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+impl EligibilityBundle {
+    /// Check the bundle proves the eligibility of the account in the given election of the contract instance
+    pub fn validate(
+        &self,
+        account: &AccountAddress,
+        contract_address: ContractAddress,
+        election_id: u64,
+    ) -> Result<(), EligibilityError> {
+        if self.contract_address != contract_address || self.election_id != election_id {
+            return Err(EligibilityError::OtherElection);
+        }
+        if self.merkle_proof.index < 0 || self.merkle_proof.index >= self.merkle_leaf_count {
+            return Err(EligibilityError::InvalidFormat);
+        }
+        if self.merkle_proof.leaf != merkle_sha256::hash(&to_bytes(&(*account, self.merkle_proof.weight))) {
+            return Err(EligibilityError::OtherAccount);
+        }
+
+        // Same check as the contract does at registration
+        let mut root = [0u8; 32];
+        hex::decode_to_slice(&self.merkle_root, &mut root)
+            .map_err(|_| EligibilityError::InvalidFormat)?;
+        let proof = rs_merkle::MerkleProof::<merkle_sha256>::from_bytes(&self.merkle_proof.proof)
+            .map_err(|_| EligibilityError::InvalidFormat)?;
+
+        if !proof.verify(
+            root,
+            &[self.merkle_proof.index as usize],
+            &[self.merkle_proof.leaf],
+            self.merkle_leaf_count as usize,
+        ) {
+            return Err(EligibilityError::InvalidProof);
+        }
+        Ok(())
+    }
+
+    /// The bundle as json, with the proof, leaf and root hex encoded
+    pub fn to_json(&self) -> Value {
+        json!({
+            "contract_address": {
+                "index": self.contract_address.index,
+                "subindex": self.contract_address.subindex,
+            },
+            "election_id": self.election_id,
+            "merkle_root": self.merkle_root,
+            "merkle_leaf_count": self.merkle_leaf_count,
+            "merkle_proof": {
+                "proof": hex::encode(&self.merkle_proof.proof),
+                "leaf": hex::encode(self.merkle_proof.leaf),
+                "index": self.merkle_proof.index,
+                "weight": self.merkle_proof.weight,
+            },
+        })
+    }
+
+    /// Parse a bundle from the json created by `to_json`
+    pub fn from_json(json: &Value) -> Result<Self, EligibilityError> {
+        let contract_address = &json["contract_address"];
+        let merkle_proof = &json["merkle_proof"];
+        let leaf = parse_hex(&merkle_proof["leaf"])?
+            .try_into()
+            .map_err(|_| EligibilityError::InvalidFormat)?;
+
+        match (
+            contract_address["index"].as_u64(),
+            contract_address["subindex"].as_u64(),
+            json["election_id"].as_u64(),
+            json["merkle_root"].as_str(),
+            json["merkle_leaf_count"].as_i64(),
+            merkle_proof["index"].as_i64(),
+            merkle_proof["weight"].as_u64(),
+        ) {
+            (
+                Some(index),
+                Some(subindex),
+                Some(election_id),
+                Some(merkle_root),
+                Some(leaf_count),
+                Some(leaf_index),
+                Some(weight),
+            ) => {
+                Ok(EligibilityBundle {
+                    contract_address: ContractAddress { index, subindex },
+                    election_id,
+                    merkle_root: merkle_root.to_string(),
+                    merkle_leaf_count: leaf_count
+                        .try_into()
+                        .map_err(|_| EligibilityError::InvalidFormat)?,
+                    merkle_proof: MerkleProof {
+                        proof: parse_hex(&merkle_proof["proof"])?,
+                        leaf,
+                        index: leaf_index
+                            .try_into()
+                            .map_err(|_| EligibilityError::InvalidFormat)?,
+                        weight: weight
+                            .try_into()
+                            .map_err(|_| EligibilityError::InvalidFormat)?,
+                    },
+                })
+            }
+            _ => Err(EligibilityError::InvalidFormat),
+        }
+    }
+}
+
+/// Create the bundle of every eligible account and its weight for the election, in the order of the Merkle leaves
+pub fn create_bundles(
+    leaves: &[(AccountAddress, u32)],
+    contract_address: ContractAddress,
+    election_id: u64,
+) -> Result<Vec<EligibilityBundle>, EligibilityError> {
+    let merkle_tree: MerkleTree<merkle_sha256> = crate::create_merkle_tree(leaves);
+    let merkle_root = merkle_tree.root_hex().ok_or(EligibilityError::InvalidFormat)?;
+
+    Ok(leaves
+        .iter()
+        .map(|(account, weight)| EligibilityBundle {
+            contract_address,
+            election_id,
+            merkle_root: merkle_root.clone(),
+            merkle_leaf_count: leaves.len() as i32,
+            merkle_proof: crate::create_merkle_proof(*account, *weight, &merkle_tree),
+        })
+        .collect())
+}
+
+/// Write the bundle to a file, as json if the file has the json extension and binary otherwise
+pub fn export(path: &Path, bundle: &EligibilityBundle) -> Result<(), EligibilityError> {
+    if is_json(path) {
+        let json = serde_json::to_string_pretty(&bundle.to_json())
+            .map_err(|_| EligibilityError::InvalidFormat)?;
+        fs::write(path, json)?;
+    } else {
+        fs::write(path, to_bytes(bundle))?;
+    }
+    Ok(())
+}
+
+/// Read a bundle written by `export`. It should be validated for the voter's account and election before use
+pub fn import(path: &Path) -> Result<EligibilityBundle, EligibilityError> {
+    if is_json(path) {
+        let json: Value = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|_| EligibilityError::InvalidFormat)?;
+        EligibilityBundle::from_json(&json)
+    } else {
+        from_bytes(&fs::read(path)?).map_err(|_| EligibilityError::InvalidFormat)
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+fn parse_hex(json: &Value) -> Result<Vec<u8>, EligibilityError> {
+    json.as_str()
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or(EligibilityError::InvalidFormat)
+}
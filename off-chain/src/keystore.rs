@@ -0,0 +1,354 @@
+//! Encrypted keystore for the voter's secret key x (and their choices once committed to), such that a voter can register, commit and vote on different days.
+//!
+//! The key is derived from a password with scrypt and the secrets are encrypted with ChaCha20-Poly1305.
+//! The contract address, election id and voter index are authenticated along with the ciphertext,
+//! so a keystore can not be used for another election or voter, even if its plaintext fields are edited.
+
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use concordium_std::*;
+use group::GroupEncoding;
+use k256::{ProjectivePoint, Scalar};
+use rand::{thread_rng, RngCore};
+use serde_json::{json, Value};
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroize;
+
+/// Version of the keystore file format
+const KEYSTORE_VERSION: u64 = 1;
+
+/// scrypt cost parameters: N = 2^15, r = 8, p = 1
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// The election and voter a keystore is bound to
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct KeystoreBinding {
+    pub contract_address: ContractAddress,
+    pub election_id: u64,
+    // Index of the voter's account in the published list of eligible voters (the Merkle leaves)
+    pub voter_index: i32,
+}
+
+/// The vote committed to on a question
+#[derive(Clone, PartialEq, Debug)]
+pub enum Choice {
+    // Yes (true) or no, which is consent (true) or a veto in a veto election
+    YesNo(bool),
+    // Index of the chosen candidate of a single vote
+    Candidate(usize),
+    // Score of each candidate of an approval (0 or 1) or score ballot
+    Scores(Vec<u64>),
+}
+
+impl Zeroize for Choice {
+    fn zeroize(&mut self) {
+        match self {
+            Choice::YesNo(choice) => choice.zeroize(),
+            Choice::Candidate(candidate) => candidate.zeroize(),
+            Choice::Scores(scores) => scores.zeroize(),
+        }
+    }
+}
+
+/// The secrets stored in the keystore. They are zeroized when dropped
+pub struct VoterSecrets {
+    pub x: Scalar,
+    // The vote committed to on each question, empty until the commit step
+    pub choices: Vec<Choice>,
+}
+
+impl Drop for VoterSecrets {
+    fn drop(&mut self) {
+        self.x.zeroize();
+        self.choices.zeroize();
+    }
+}
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    // Failed reading or writing the keystore file
+    Io(std::io::Error),
+    // The file is not a keystore of a supported version
+    InvalidFormat,
+    // The keystore belongs to another contract instance or election
+    OtherElection,
+    // The keystore belongs to another voter
+    OtherVoter,
+    // Wrong password, or the keystore has been tampered with
+    DecryptionFailed,
+    // Failed encrypting the secrets when saving
+    EncryptionFailed,
+}
+
+impl std::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KeystoreError::Io(e) => write!(f, "keystore file: {}", e),
+            KeystoreError::InvalidFormat => write!(f, "not a valid keystore file"),
+            KeystoreError::OtherElection => write!(f, "keystore belongs to another election"),
+            KeystoreError::OtherVoter => write!(f, "keystore belongs to another voter"),
+            KeystoreError::DecryptionFailed => {
+                write!(f, "wrong password or the keystore has been tampered with")
+            }
+            KeystoreError::EncryptionFailed => write!(f, "failed encrypting the keystore"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+impl From<KeystoreError> for std::io::Error {
+    fn from(e: KeystoreError) -> Self {
+        match e {
+            KeystoreError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Encrypt the secrets with the password and save them to the keystore file, bound to the given election and voter
+pub fn save(
+    path: &Path,
+    password: &[u8],
+    secrets: &VoterSecrets,
+    binding: &KeystoreBinding,
+) -> Result<(), KeystoreError> {
+    let mut rng = thread_rng();
+    let mut salt = [0u8; 32];
+    rng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut nonce);
+
+    // Plaintext is x followed by the choice of each question, or by a single 0 if there are no choices yet.
+    // A choice is 1 for no, 2 for yes, 3 and the u32 candidate index, or 4 and the u32 number of scores followed by the u64 scores
+    let mut plaintext = secrets.x.to_bytes().to_vec();
+    if secrets.choices.is_empty() {
+        plaintext.push(0);
+    }
+    for choice in secrets.choices.iter() {
+        match choice {
+            Choice::YesNo(false) => plaintext.push(1),
+            Choice::YesNo(true) => plaintext.push(2),
+            Choice::Candidate(candidate) => {
+                plaintext.push(3);
+                plaintext.extend((*candidate as u32).to_be_bytes());
+            }
+            Choice::Scores(scores) => {
+                plaintext.push(4);
+                plaintext.extend((scores.len() as u32).to_be_bytes());
+                for score in scores {
+                    plaintext.extend(score.to_be_bytes());
+                }
+            }
+        }
+    }
+
+    let mut key = derive_key(password, &salt)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key)).encrypt(
+        Nonce::from_slice(&nonce),
+        Payload {
+            msg: &plaintext,
+            aad: &associated_data(binding),
+        },
+    );
+    key.zeroize();
+    plaintext.zeroize();
+    let ciphertext = ciphertext.map_err(|_| KeystoreError::EncryptionFailed)?;
+
+    let json = json!({
+        "version": KEYSTORE_VERSION,
+        "contract_address": {
+            "index": binding.contract_address.index,
+            "subindex": binding.contract_address.subindex,
+        },
+        "election_id": binding.election_id,
+        "voter_index": binding.voter_index,
+        "voting_key": hex::encode((ProjectivePoint::GENERATOR * secrets.x).to_bytes()),
+        "kdf": {
+            "name": "scrypt",
+            "log_n": SCRYPT_LOG_N,
+            "r": SCRYPT_R,
+            "p": SCRYPT_P,
+            "salt": hex::encode(salt),
+        },
+        "cipher": {
+            "name": "chacha20poly1305",
+            "nonce": hex::encode(nonce),
+            "ciphertext": hex::encode(ciphertext),
+        },
+    });
+
+    fs::write(
+        path,
+        serde_json::to_string_pretty(&json).map_err(|_| KeystoreError::InvalidFormat)?,
+    )?;
+    Ok(())
+}
+
+/// Read the election and voter a keystore is bound to, without decrypting it
+pub fn read_binding(path: &Path) -> Result<KeystoreBinding, KeystoreError> {
+    let json = read_keystore(path)?;
+    parse_binding(&json)
+}
+
+/// Decrypt the secrets of the keystore with the password.
+/// Refuses keystores of another contract instance or election than the given one
+pub fn load(
+    path: &Path,
+    password: &[u8],
+    contract_address: ContractAddress,
+    election_id: u64,
+) -> Result<(VoterSecrets, KeystoreBinding), KeystoreError> {
+    let json = read_keystore(path)?;
+    let binding = parse_binding(&json)?;
+    if binding.contract_address != contract_address || binding.election_id != election_id {
+        return Err(KeystoreError::OtherElection);
+    }
+
+    let kdf = &json["kdf"];
+    if kdf["name"] != "scrypt"
+        || kdf["log_n"] != SCRYPT_LOG_N
+        || kdf["r"] != SCRYPT_R
+        || kdf["p"] != SCRYPT_P
+        || json["cipher"]["name"] != "chacha20poly1305"
+    {
+        return Err(KeystoreError::InvalidFormat);
+    }
+    let salt = parse_hex(&kdf["salt"])?;
+    let nonce = parse_hex(&json["cipher"]["nonce"])?;
+    let ciphertext = parse_hex(&json["cipher"]["ciphertext"])?;
+    if nonce.len() != 12 {
+        return Err(KeystoreError::InvalidFormat);
+    }
+
+    let mut key = derive_key(password, &salt)?;
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(&key)).decrypt(
+        Nonce::from_slice(&nonce),
+        Payload {
+            msg: &ciphertext,
+            aad: &associated_data(&binding),
+        },
+    );
+    key.zeroize();
+    let mut plaintext = plaintext.map_err(|_| KeystoreError::DecryptionFailed)?;
+
+    let secrets = match (plaintext.get(..32), plaintext.get(32..)) {
+        (Some(x), Some([0])) => {
+            util::try_convert_vec_to_scalar(x).map(|x| VoterSecrets {
+                x,
+                choices: Vec::new(),
+            })
+        }
+        (Some(x), Some(choices)) if !choices.is_empty() => match parse_choices(choices) {
+            Some(choices) => util::try_convert_vec_to_scalar(x).map(|x| VoterSecrets { x, choices }),
+            None => Err(util::UtilError::InvalidScalar),
+        },
+        _ => Err(util::UtilError::InvalidScalar),
+    };
+    plaintext.zeroize();
+
+    Ok((secrets.map_err(|_| KeystoreError::InvalidFormat)?, binding))
+}
+
+/// Parse the choices of the plaintext, as written by save
+fn parse_choices(mut bytes: &[u8]) -> Option<Vec<Choice>> {
+    let mut choices = Vec::new();
+    while let Some((tag, rest)) = bytes.split_first() {
+        bytes = rest;
+        let choice = match tag {
+            1 => Choice::YesNo(false),
+            2 => Choice::YesNo(true),
+            3 => Choice::Candidate(take_u32(&mut bytes)? as usize),
+            4 => {
+                let number_of_scores = take_u32(&mut bytes)? as usize;
+                let scores = bytes.get(..number_of_scores.checked_mul(8)?)?;
+                bytes = &bytes[scores.len()..];
+                Choice::Scores(
+                    scores
+                        .chunks(8)
+                        .map(|score| u64::from_be_bytes(score.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+            _ => return None,
+        };
+        choices.push(choice);
+    }
+    Some(choices)
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let value = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?);
+    *bytes = &bytes[4..];
+    Some(value)
+}
+
+/// Check the keystore belongs to the voter with the given index
+pub fn ensure_voter(binding: &KeystoreBinding, voter_index: i32) -> Result<(), KeystoreError> {
+    if binding.voter_index != voter_index {
+        return Err(KeystoreError::OtherVoter);
+    }
+    Ok(())
+}
+
+/// Derive the 256 bit encryption key from the password with scrypt
+fn derive_key(password: &[u8], salt: &[u8]) -> Result<[u8; 32], KeystoreError> {
+    let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, 32)
+        .map_err(|_| KeystoreError::InvalidFormat)?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password, salt, &params, &mut key).map_err(|_| KeystoreError::InvalidFormat)?;
+    Ok(key)
+}
+
+/// The binding is authenticated along with the ciphertext, such that it can not be changed without failing decryption
+fn associated_data(binding: &KeystoreBinding) -> Vec<u8> {
+    let mut aad = b"open-vote-network keystore".to_vec();
+    aad.extend(to_bytes(&binding.contract_address));
+    aad.extend(to_bytes(&binding.election_id));
+    aad.extend(to_bytes(&binding.voter_index));
+    aad
+}
+
+fn read_keystore(path: &Path) -> Result<Value, KeystoreError> {
+    let json: Value =
+        serde_json::from_str(&fs::read_to_string(path)?).map_err(|_| KeystoreError::InvalidFormat)?;
+    if json["version"] != KEYSTORE_VERSION {
+        return Err(KeystoreError::InvalidFormat);
+    }
+    Ok(json)
+}
+
+fn parse_binding(json: &Value) -> Result<KeystoreBinding, KeystoreError> {
+    let contract_address = &json["contract_address"];
+    match (
+        contract_address["index"].as_u64(),
+        contract_address["subindex"].as_u64(),
+        json["election_id"].as_u64(),
+        json["voter_index"].as_i64(),
+    ) {
+        (Some(index), Some(subindex), Some(election_id), Some(voter_index)) => {
+            Ok(KeystoreBinding {
+                contract_address: ContractAddress { index, subindex },
+                election_id,
+                voter_index: voter_index as i32,
+            })
+        }
+        _ => Err(KeystoreError::InvalidFormat),
+    }
+}
+
+fn parse_hex(json: &Value) -> Result<Vec<u8>, KeystoreError> {
+    json.as_str()
+        .and_then(|s| hex::decode(s).ok())
+        .ok_or(KeystoreError::InvalidFormat)
+}
@@ -12,7 +12,16 @@ use rand::thread_rng;
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
 use rs_merkle::*;
 use sha2::{Digest, Sha256};
-use util::{hash_to_scalar, OneInTwoZKP, SchnorrProof};
+use util::{
+    convert_vec_to_point, ChaumPedersenProof, OneInKZKP, OneInTwoZKP, ProofContext, RangeZKP,
+    SchnorrProof, Transcript,
+};
+
+pub mod eligibility;
+pub mod keystore;
+
+#[cfg(test)]
+mod tests;
 
 /// Create a voting key (pk, sk) pair of g^x and x
 pub fn create_votingkey_pair() -> (Scalar, ProjectivePoint) {
@@ -22,27 +31,50 @@ pub fn create_votingkey_pair() -> (Scalar, ProjectivePoint) {
     (x, g_x)
 }
 
+/// Derive the voting key pair of each question of a ballot from the voter's secret key x, which is the key of the first question.
+/// Every question needs its own key, as two votes encrypted with the same key reveal g^(v - v') to anyone dividing them
+pub fn derive_question_keys(x: &Scalar, number_of_questions: usize) -> Vec<(Scalar, ProjectivePoint)> {
+    (0..number_of_questions)
+        .map(|question| {
+            let x_q = if question == 0 {
+                *x
+            } else {
+                let mut bytes = b"open-vote-network question key".to_vec();
+                bytes.extend(x.to_bytes());
+                bytes.extend((question as u64).to_le_bytes());
+                util::hash_to_scalar(bytes)
+            };
+            (x_q, ProjectivePoint::GENERATOR * x_q)
+        })
+        .collect()
+}
+
 /// Create a discrete log Schnorr ZKP (g^w, r = w - xz)
-pub fn create_schnorr_zkp(g_x: ProjectivePoint, x: Scalar) -> SchnorrProof {
+pub fn create_schnorr_zkp(g_x: ProjectivePoint, x: Scalar, context: &ProofContext) -> SchnorrProof {
     let rng = thread_rng();
 
     let w = Scalar::random(rng);
     let g_w = ProjectivePoint::GENERATOR * w;
 
-    // Create hash z = H(g, g^w, g^x)
-    let value_to_hash = ProjectivePoint::GENERATOR + g_w + g_x;
-    let z = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // Create hash z = H(context, g, g^w, g^x)
+    let mut transcript = Transcript::new("schnorr", context);
+    transcript.append_point(&ProjectivePoint::GENERATOR);
+    transcript.append_point(&g_w);
+    transcript.append_point(&g_x);
+    let z = transcript.challenge();
 
     let r = w - x * z;
 
     SchnorrProof::new(g_w, r)
 }
 
-/// Create one-in-two ZKP "yes" instance
+/// Create one-in-two ZKP "yes" instance, for the vote v = w of a voter of weight w (1 if unweighted)
 pub fn create_one_in_two_zkp_yes(
     g_x: ProjectivePoint,
     g_y: ProjectivePoint,
     x: Scalar,
+    weight: u64,
+    context: &ProofContext,
 ) -> OneInTwoZKP {
     let rng = thread_rng();
 
@@ -52,27 +84,33 @@ pub fn create_one_in_two_zkp_yes(
     let d1 = Scalar::random(rng);
 
     // Create the rest of the neccessary variables for the proof
-    let y = (g_y.clone() * x.clone()) + ProjectivePoint::GENERATOR;
-    let a1 = (ProjectivePoint::GENERATOR * r1.clone()) + (g_x.clone() * d1.clone());
-    let b1 = (g_y.clone() * r1.clone()) + (y.clone() * d1.clone());
-    let a2 = ProjectivePoint::GENERATOR * w.clone();
-    let b2 = g_y * w.clone();
+    let y = (g_y * x) + (ProjectivePoint::GENERATOR * Scalar::from(weight));
+    let a1 = (ProjectivePoint::GENERATOR * r1) + (g_x * d1);
+    let b1 = (g_y * r1) + (y * d1);
+    let a2 = ProjectivePoint::GENERATOR * w;
+    let b2 = g_y * w;
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = g_x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(context, w, g^y, g^x, y, a1, b1, a2, b2)
+    let mut transcript = Transcript::new("one-in-two", context);
+    transcript.append_u64(weight);
+    for point in [g_y, g_x, y, a1, b1, a2, b2].iter() {
+        transcript.append_point(point);
+    }
+    let c = transcript.challenge();
 
-    let d2: Scalar = c - d1.clone();
-    let r2 = w - (x * d2.clone());
+    let d2: Scalar = c - d1;
+    let r2 = w - (x * d2);
 
     OneInTwoZKP::new(r1, r2, d1, d2, g_x, y, a1, b1, a2, b2)
 }
 
-/// Create one-in-two ZKP "no" instance
+/// Create one-in-two ZKP "no" instance, for the vote v = 0 of a voter of weight w (1 if unweighted)
 pub fn create_one_in_two_zkp_no(
     g_x: ProjectivePoint,
     g_y: ProjectivePoint,
     x: Scalar,
+    weight: u64,
+    context: &ProofContext,
 ) -> OneInTwoZKP {
     let rng = thread_rng();
 
@@ -82,22 +120,222 @@ pub fn create_one_in_two_zkp_no(
     let d2 = Scalar::random(rng.clone());
 
     // Create the rest of the neccessary variables for the proof
-    let y = g_y.clone() * x.clone();
-    let a1 = ProjectivePoint::GENERATOR * w.clone();
-    let b1 = g_y.clone() * w.clone();
-    let a2 = (ProjectivePoint::GENERATOR * r2.clone()) + (g_x.clone() * d2.clone());
-    let b2 = (g_y.clone() * r2.clone()) + ((y.clone() - ProjectivePoint::GENERATOR) * d2.clone());
+    let y = g_y * x;
+    let a1 = ProjectivePoint::GENERATOR * w;
+    let b1 = g_y * w;
+    let a2 = (ProjectivePoint::GENERATOR * r2) + (g_x * d2);
+    let b2 = (g_y * r2)
+        + ((y - (ProjectivePoint::GENERATOR * Scalar::from(weight))) * d2);
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = g_x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(context, w, g^y, g^x, y, a1, b1, a2, b2)
+    let mut transcript = Transcript::new("one-in-two", context);
+    transcript.append_u64(weight);
+    for point in [g_y, g_x, y, a1, b1, a2, b2].iter() {
+        transcript.append_point(point);
+    }
+    let c = transcript.challenge();
 
-    let d1 = c - d2.clone();
-    let r1 = w - (x * d1.clone());
+    let d1 = c - d2;
+    let r1 = w - (x * d1);
 
     OneInTwoZKP::new(r1, r2, d1, d2, g_x, y, a1, b1, a2, b2)
 }
 
+/// Create the vote g^v = g^(m^j) for candidate j, given the encodings m^0, ..., m^(k-1) of the k candidates
+pub fn encode_candidate(choice: usize, encodings: &[u64]) -> ProjectivePoint {
+    ProjectivePoint::GENERATOR * Scalar::from(encodings[choice])
+}
+
+/// Create one-in-k ZKP for a vote for candidate `choice`, given the encodings m^0, ..., m^(k-1) of the k candidates
+pub fn create_one_in_k_zkp(
+    g_x: ProjectivePoint,
+    g_y: ProjectivePoint,
+    x: Scalar,
+    choice: usize,
+    encodings: &[u64],
+    context: &ProofContext,
+) -> OneInKZKP {
+    let mut rng = thread_rng();
+
+    let y = (g_y * x) + encode_candidate(choice, encodings);
+
+    // Simulate the proof for every candidate that was not chosen, and commit to a random w for the chosen one
+    let w = Scalar::random(&mut rng);
+    let mut r = Vec::new();
+    let mut d = Vec::new();
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    for i in 0..encodings.len() {
+        if i == choice {
+            r.push(Scalar::ZERO);
+            d.push(Scalar::ZERO);
+            a.push(ProjectivePoint::GENERATOR * w);
+            b.push(g_y * w);
+        } else {
+            let r_i = Scalar::random(&mut rng);
+            let d_i = Scalar::random(&mut rng);
+            r.push(r_i);
+            d.push(d_i);
+            a.push((ProjectivePoint::GENERATOR * r_i) + (g_x * d_i));
+            b.push((g_y * r_i) + ((y - encode_candidate(i, encodings)) * d_i));
+        }
+    }
+
+    // c = H(context, m^0, ..., m^(k-1), g^y, g^x, y, a1, b1, ..., ak, bk)
+    let mut transcript = Transcript::new("one-in-k", context);
+    for encoding in encodings {
+        transcript.append_u64(*encoding);
+    }
+    transcript.append_point(&g_y);
+    transcript.append_point(&g_x);
+    transcript.append_point(&y);
+    for (a_i, b_i) in a.iter().zip(b.iter()) {
+        transcript.append_point(a_i);
+        transcript.append_point(b_i);
+    }
+    let c = transcript.challenge();
+
+    // The challenge of the chosen candidate is what remains of c, and its response is computed with x
+    d[choice] = d.iter().fold(c, |acc, d_i| acc - d_i);
+    r[choice] = w - (x * d[choice]);
+
+    OneInKZKP::new(r, d, g_x, y, a, b)
+}
+
+/// Create the vote g^v = g^(sum of s_j*m^j) of an approval or score ballot giving each candidate j the score s_j,
+/// given the encodings m^0, ..., m^(k-1) of the k candidates. An approval is a score of 1
+pub fn encode_scores(scores: &[u64], encodings: &[u64]) -> ProjectivePoint {
+    scores
+        .iter()
+        .zip(encodings.iter())
+        .fold(ProjectivePoint::IDENTITY, |acc, (score, encoding)| {
+            acc + ProjectivePoint::GENERATOR * (Scalar::from(*score) * Scalar::from(*encoding))
+        })
+}
+
+/// Create range ZKP for an approval or score ballot, for the vote g^xy*g^v with v from `encode_scores` given the weighted encodings.
+/// The key x is split into option keys r_j with sum of r_j*m^j = x, and each option vote g^(y*r_j) g^(w*s_j) gets a one-in-k proof
+/// that s_j is in 0..=max_score. The sums of the option keys and votes get a one-in-k proof that the total score is in 0..=budget.
+/// Panics if a score is above max_score or the total is above budget, as there is no such proof
+#[allow(clippy::too_many_arguments)]
+pub fn create_range_zkp(
+    g_y: ProjectivePoint,
+    x: Scalar,
+    scores: &[u64],
+    max_score: u64,
+    budget: u64,
+    encodings: &[u64],
+    weight: u64,
+    context: &ProofContext,
+) -> RangeZKP {
+    let mut rng = thread_rng();
+
+    // Random option keys, except the first which makes them add up to x (its encoding m^0 is 1)
+    let mut r: Vec<Scalar> = encodings.iter().map(|_| Scalar::random(&mut rng)).collect();
+    r[0] = r
+        .iter()
+        .zip(encodings.iter())
+        .skip(1)
+        .fold(x, |acc, (r_j, encoding)| acc - (r_j * &Scalar::from(*encoding)));
+
+    let score_encodings = util::weighted_encodings(&util::score_encodings(max_score), weight);
+    let options = r
+        .iter()
+        .zip(scores.iter())
+        .map(|(r_j, score)| {
+            create_one_in_k_zkp(
+                ProjectivePoint::GENERATOR * r_j,
+                g_y,
+                *r_j,
+                *score as usize,
+                &score_encodings,
+                context,
+            )
+        })
+        .collect();
+
+    let total_key = r.iter().fold(Scalar::ZERO, |acc, r_j| acc + r_j);
+    let total_score: u64 = scores.iter().sum();
+    let total = create_one_in_k_zkp(
+        ProjectivePoint::GENERATOR * total_key,
+        g_y,
+        total_key,
+        total_score as usize,
+        &util::weighted_encodings(&util::score_encodings(budget), weight),
+        context,
+    );
+
+    RangeZKP::new(options, total)
+}
+
+/// Create the vote of a veto election: g^(x*y) to consent, or g^(r*y) to veto. Returns the exponent c (x or r) along with the vote g^(c*y).
+/// The r of a veto is derived from x and g^y, such that the same vote can be created again for the commitment and the vote
+pub fn create_veto_vote(x: &Scalar, g_y: &ProjectivePoint, veto: bool) -> (Scalar, ProjectivePoint) {
+    let c = if veto {
+        let mut bytes = b"open-vote-network veto".to_vec();
+        bytes.extend(x.to_bytes());
+        bytes.extend(g_y.to_bytes());
+        util::hash_to_scalar(bytes)
+    } else {
+        *x
+    };
+    (c, g_y * &c)
+}
+
+/// Create veto ZKP, a Schnorr ZKP in base g^y of knowing c such that the vote is g^(c*y): ((g^y)^w, r = w - cz)
+pub fn create_veto_zkp(
+    g_y: ProjectivePoint,
+    vote: ProjectivePoint,
+    c: Scalar,
+    context: &ProofContext,
+) -> SchnorrProof {
+    let rng = thread_rng();
+
+    let w = Scalar::random(rng);
+    let g_y_w = g_y * w;
+
+    // Create hash z = H(context, g^y, (g^y)^w, vote)
+    let mut transcript = Transcript::new("veto", context);
+    transcript.append_point(&g_y);
+    transcript.append_point(&g_y_w);
+    transcript.append_point(&vote);
+    let z = transcript.challenge();
+
+    let r = w - c * z;
+
+    SchnorrProof::new(g_y_w, r)
+}
+
+/// Create the recovery key h^x for the recovery round, where h is the recovery base from `util::compute_recovery_base`
+pub fn create_recovery_key(x: &Scalar, h: &ProjectivePoint) -> ProjectivePoint {
+    h * x
+}
+
+/// Create a Chaum-Pedersen ZKP that g^x and h^x share the same x: (g^w, h^w, r = w - xc)
+pub fn create_chaum_pedersen_zkp(
+    g_x: ProjectivePoint,
+    h: ProjectivePoint,
+    x: Scalar,
+    context: &ProofContext,
+) -> ChaumPedersenProof {
+    let rng = thread_rng();
+
+    let w = Scalar::random(rng);
+    let g_w = ProjectivePoint::GENERATOR * w;
+    let h_w = h * w;
+    let h_x = h * x;
+
+    // Create hash c = H(context, g, h, g^x, h^x, g^w, h^w)
+    let mut transcript = Transcript::new("chaum-pedersen", context);
+    for point in [ProjectivePoint::GENERATOR, h, g_x, h_x, g_w, h_w].iter() {
+        transcript.append_point(point);
+    }
+    let c = transcript.challenge();
+
+    let r = w - x * c;
+
+    ChaumPedersenProof::new(g_w, h_w, r)
+}
 
 /// Create a commitment to a vote: H(g^xy g^v)
 pub fn commit_to_vote(x: &Scalar, g_y: &ProjectivePoint, g_v: ProjectivePoint) -> Vec<u8> {
@@ -105,8 +343,83 @@ pub fn commit_to_vote(x: &Scalar, g_y: &ProjectivePoint, g_v: ProjectivePoint) -
     Sha256::digest(&g_xy_g_v.to_bytes().to_vec()).to_vec()
 }
 
-/// Create a merkle tree for storing its root in the contract via the voteconfig
-pub fn create_merkle_tree(leaf_values: &Vec<AccountAddress>) -> MerkleTree<merkle_sha256> {
+/// Sum the cast votes, removing the recovery keys for the voters who did not vote (if any)
+fn sum_votes(votes: &[Vec<u8>], recovery_keys: &[Vec<u8>]) -> ProjectivePoint {
+    let mut sum = ProjectivePoint::IDENTITY;
+    for (vote, recovery_key) in votes.iter().zip(recovery_keys.iter()) {
+        if vote.is_empty() {
+            continue;
+        }
+        sum += convert_vec_to_point(vote);
+        if !recovery_key.is_empty() {
+            sum -= convert_vec_to_point(recovery_key);
+        }
+    }
+    sum
+}
+
+/// Sum the weights of the voters who cast a vote
+fn voted_weight(votes: &[Vec<u8>], weights: &[u32]) -> u64 {
+    votes
+        .iter()
+        .zip(weights.iter())
+        .filter(|(vote, _)| !vote.is_empty())
+        .map(|(_, weight)| *weight as u64)
+        .sum()
+}
+
+/// Compute the result to pass to the contract's `result` function from the published votes, recovery keys (empty if not set) and weights
+/// of the voters, and the total weight of the election. Use 0 options for yes/no votes.
+/// Returns the weighted (yes, no) or count of each candidate, or None if the votes do not tally
+pub fn compute_result(
+    votes: &[Vec<u8>],
+    recovery_keys: &[Vec<u8>],
+    weights: &[u32],
+    number_of_options: usize,
+    total_weight: i32,
+) -> Option<Vec<i32>> {
+    let sum = sum_votes(votes, recovery_keys);
+    let voted_weight = voted_weight(votes, weights);
+
+    if number_of_options == 0 {
+        let yes_votes = util::discrete_log(sum, voted_weight)?;
+        return Some(vec![yes_votes as i32, (voted_weight - yes_votes) as i32]);
+    }
+
+    let base = total_weight as u64 + 1;
+    let encodings = util::candidate_encodings(number_of_options, base)?;
+    let tally = util::discrete_log(sum, voted_weight * encodings.last()?)?;
+    Some(util::decode_candidate_tally(tally, number_of_options, base))
+}
+
+/// Compute the result of an approval or score ballot like `compute_result`, with a max_score of 1 for approval ballots.
+/// Returns the weighted score sum (or number of approvals) of each candidate, or None if the votes do not tally
+pub fn compute_score_result(
+    votes: &[Vec<u8>],
+    recovery_keys: &[Vec<u8>],
+    weights: &[u32],
+    number_of_options: usize,
+    max_score: u64,
+    total_weight: i32,
+) -> Option<Vec<i32>> {
+    let sum = sum_votes(votes, recovery_keys);
+    let voted_weight = voted_weight(votes, weights);
+
+    let base = total_weight as u64 * max_score + 1;
+    let encodings = util::candidate_encodings(number_of_options, base)?;
+    let bound = voted_weight * max_score * encodings.iter().sum::<u64>();
+    let tally = util::discrete_log(sum, bound)?;
+    Some(util::decode_candidate_tally(tally, number_of_options, base))
+}
+
+/// Compute whether anyone vetoed in a veto election, from the published votes and recovery keys like `compute_result`
+pub fn compute_veto_result(votes: &[Vec<u8>], recovery_keys: &[Vec<u8>]) -> bool {
+    let sum = sum_votes(votes, recovery_keys);
+    sum != ProjectivePoint::IDENTITY
+}
+
+/// Create a merkle tree of the eligible accounts and their weights, for storing its root in the contract via the voteconfig
+pub fn create_merkle_tree(leaf_values: &[(AccountAddress, u32)]) -> MerkleTree<merkle_sha256> {
     let mut leaves: Vec<[u8; 32]> = Vec::new();
     leaves.extend(
         leaf_values
@@ -119,15 +432,16 @@ pub fn create_merkle_tree(leaf_values: &Vec<AccountAddress>) -> MerkleTree<merkl
     merkle_tree
 }
 
-/// Create a merkle proof-of-membership via your AccountAddress and the tree itself
+/// Create a merkle proof-of-membership via your AccountAddress, your weight and the tree itself
 pub fn create_merkle_proof(
     account: AccountAddress,
+    weight: u32,
     merkle_tree: &MerkleTree<merkle_sha256>,
 ) -> util::MerkleProof {
     let leaves = merkle_tree.leaves().unwrap();
     let index_to_prove = leaves
         .iter()
-        .position(|&l| l == merkle_sha256::hash(&to_bytes(&account)))
+        .position(|&l| l == merkle_sha256::hash(&to_bytes(&(account, weight))))
         .ok_or("Can't get index to prove. AccountAddress not in MerkleTree")
         .unwrap();
 
@@ -141,5 +455,6 @@ pub fn create_merkle_proof(
         proof: merkle_proof.to_bytes(),
         leaf: *leaf_to_prove,
         index: index_to_prove as i32,
+        weight,
     }
 }
@@ -1,40 +1,829 @@
-//! Rust binary entry point for locally creating binary files of voter messages, for the purpose of testing a full election on-chain.
+//! Rust binary entry point of the voter CLI, creating the binary parameter files voters send to the *voting* contract with concordium-client.
 //!
-//! In order to make an actual vote, and not just run this test, this program needs to be modified in 3 ways:
+//! An organizer creates the election with `voteconfig`, which reads the eligible account addresses and their weights from a CSV or JSON file
+//! and writes the `voteconfig.json` parameter of `create_election` along with the Merkle leaf list that voters need to build their proofs.
+//! A contract instance can host many elections, so the voter steps take the id `create_election` returned with `--election` (0 by default).
+//! Once the election is created, `export-proofs` creates an eligibility bundle per voter, such that a voter can register
+//! with `--proof <bundle>` instead of rebuilding the Merkle tree from the full leaf list.
 //!
-//! 1. Needs to take an argument for the vote.
-//! 2. Every voter needs a way to get eachothers reconstructed keys (g_y).
-//!    This could be done by modifying the program such that it only creates one message at a time (through some argument)
-//!    and adding a getter (view function) to the smart contract to retrieve reconstructed keys.
-//! 3. A way of publishing the merkle tree to all voters.
+//! A voter goes through the election one step at a time, each step writing a single parameter file:
+//!
+//! 1. `keygen` creates the voting key pair (x, g^x) and saves it to a key file.
+//! 2. `register` creates the register message from the key file, the voter's account address and the published Merkle leaf list.
+//!    A ballot with several questions needs a voting key per question, which are derived from x (`--questions`).
+//! 3. `commit --choice yes|no` creates the commit message from the on-chain voting keys (retrieved with the `view_voting_keys` view function).
+//!    `--choice` is repeated for each question of the ballot, in order. A voter with a weight other than 1 passes it with `--weight`.
+//!    In a vote between candidates, the choice is `--candidate <index>` for a single vote, `--approve <indices>` (like 0,2) for an approval ballot
+//!    or `--score <scores>` (a score per candidate, like 3,0,2) for a score ballot. These take the ballot from the voteconfig with `--number-of-options`,
+//!    `--total-weight` and, for approval and score ballots, `--budget` (the max approvals or the score budget) and `--max-score` (score ballots only).
+//! 4. `vote` creates the vote message for the choices committed to, with the same `--weight` and ballot arguments.
+//! 5. `recover` creates the recovery message, if the vote moved on to the recovery phase because some voters did not vote.
+//!    It takes the voters returned by `view_voters`, to find the voters who did not vote, along with the same `--voting-keys`.
+//! 6. `verify-result` recomputes the result of each question from the votes published on-chain (retrieved with the `view_voters` view function) and checks it against the stored result.
+//!    For an approval or score ballot, `--max-score` is the highest score a candidate can get from a voter (1 for approval ballots).
+//!
+//! In a veto election, `commit`, `vote` and `verify-result` take `--veto`, and the choice no is a veto while yes consents.
+//! As a veto election stores no counts, `verify-result` then checks the outcome returned by `view_outcome` instead, passed with `--outcome`.
+//!
+//! The `test-election` subcommand creates all messages for a test election with hardcoded accounts all voting "yes", for the purpose of testing a full election on-chain.
 //!
 //! Ideally, a simple decentralized app would provide an interface to the above, such that voter's wouldn't need to download and run this code and call the contract directly themselves.
 
 use base58check::*;
+use clap::{value_parser, Arg, ArgAction, ArgGroup, ArgMatches, Command};
 use concordium_std::*;
 use group::GroupEncoding;
 use k256::{ProjectivePoint, Scalar};
+use off_chain::eligibility;
+use off_chain::keystore::{self, Choice, KeystoreBinding, VoterSecrets};
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
 use rs_merkle::*;
-use serde_json::json;
+use serde_json::{json, Value};
 use std::fs;
 use std::fs::File;
-use std::io::{Error, Write};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use voting::*;
+use zeroize::Zeroizing;
+
+/// Address of the contract instance the test election messages are created for, as the ZKPs are bound to it
+const TEST_CONTRACT_ADDRESS: ContractAddress = ContractAddress {
+    index: 5124,
+    subindex: 0,
+};
 
-pub mod lib;
+/// Id of the test election in the contract instance, the first election created in it
+const TEST_ELECTION_ID: u64 = 0;
 
-/// Entry point taking an argument of the number of voter's to create messages for (cargo run)
+/// Entry point parsing the subcommand and its arguments (cargo run -- <subcommand>)
 fn main() -> Result<(), Error> {
+    let matches = cli().get_matches();
+
+    match matches.subcommand() {
+        Some(("voteconfig", args)) => voteconfig(args),
+        Some(("export-proofs", args)) => export_proofs(args),
+        Some(("keygen", args)) => keygen(args),
+        Some(("register", args)) => register(args),
+        Some(("commit", args)) => commit(args),
+        Some(("vote", args)) => vote(args),
+        Some(("recover", args)) => recover(args),
+        Some(("verify-result", args)) => verify_result(args),
+        Some(("test-election", _)) => test_election(),
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+/// The subcommands of the CLI and their arguments
+fn cli() -> Command {
+    let key = Arg::new("key")
+        .long("key")
+        .help("Encrypted keystore with the voter's secret key (password from VOTER_KEYSTORE_PASSWORD or prompted for)")
+        .default_value("voter_keystore.json")
+        .value_parser(value_parser!(PathBuf));
+    let account = Arg::new("account")
+        .long("account")
+        .help("Base58 address of the voter's account")
+        .required(true);
+    let contract = Arg::new("contract")
+        .long("contract")
+        .help("Index of the voting contract instance")
+        .required(true)
+        .value_parser(value_parser!(u64));
+    let election = Arg::new("election")
+        .long("election")
+        .help("Id of the election in the contract instance, as returned by create_election")
+        .default_value("0")
+        .value_parser(value_parser!(u64));
+    let leaves = Arg::new("leaves")
+        .long("leaves")
+        .help("JSON file with the published list of eligible account addresses and their weights (the Merkle leaves)")
+        .required(true)
+        .value_parser(value_parser!(PathBuf));
+    let leaves_or_proof = leaves
+        .clone()
+        .required(false)
+        .required_unless_present("proof")
+        .conflicts_with("proof");
+    let proof = Arg::new("proof")
+        .long("proof")
+        .help("Eligibility bundle of the voter's account exported by the organizer, instead of --leaves")
+        .value_parser(value_parser!(PathBuf));
+    let voting_keys = Arg::new("voting-keys")
+        .long("voting-keys")
+        .help("JSON file with the voting keys returned by view_voting_keys")
+        .required(true)
+        .value_parser(value_parser!(PathBuf));
+    let voters = Arg::new("voters")
+        .long("voters")
+        .help("JSON file with the voters returned by view_voters")
+        .required(true)
+        .value_parser(value_parser!(PathBuf));
+    let veto = Arg::new("veto")
+        .long("veto")
+        .help("The election is a veto election (ballot type veto), where the choice no is a veto and yes consents")
+        .action(ArgAction::SetTrue);
+    let number_of_options = Arg::new("number-of-options")
+        .long("number-of-options")
+        .help("Number of voting options, from the voteconfig (0 for a yes/no vote)")
+        .default_value("0")
+        .value_parser(value_parser!(usize));
+    let total_weight = Arg::new("total-weight")
+        .long("total-weight")
+        .help("Sum of the weights of the eligible voters, from the voteconfig")
+        .value_parser(value_parser!(i32));
+    let max_score = Arg::new("max-score")
+        .long("max-score")
+        .help("Highest score a voter can give a candidate on an approval (1) or score ballot, from the voteconfig (none for a single vote)")
+        .value_parser(value_parser!(u64));
+    let budget = Arg::new("budget")
+        .long("budget")
+        .help("Highest number of approvals or total score of a voter on an approval or score ballot, from the voteconfig")
+        .value_parser(value_parser!(u64));
+    let weight = Arg::new("weight")
+        .long("weight")
+        .help("The voter's weight, as in the Merkle leaf list or eligibility bundle")
+        .default_value("1")
+        .value_parser(value_parser!(u32).range(1..));
+    let out = |default: &'static str| {
+        Arg::new("out")
+            .long("out")
+            .help("File to write the parameter to")
+            .default_value(default)
+            .value_parser(value_parser!(PathBuf))
+    };
+
+    let timeout = |name: &'static str, help: &'static str| {
+        Arg::new(name)
+            .long(name)
+            .help(help)
+            .required(true)
+    };
+
+    Command::new("off-chain")
+        .about("Create the parameter files for taking part in an Open Vote Network election")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("voteconfig")
+                .about("Create the voteconfig of an election and the Merkle leaf list from a list of eligible account addresses")
+                .arg(
+                    Arg::new("addresses")
+                        .long("addresses")
+                        .help("JSON list of base58 account addresses or {\"address\", \"weight\"} objects, or CSV file with an address and optionally a weight in the first columns of each row (weight 1 if not given)")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("question")
+                        .long("question")
+                        .help("A voting question, repeated for each question of the ballot")
+                        .required(true)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("option")
+                        .long("option")
+                        .help("A candidate of a vote between candidates, repeated for each candidate (none for a yes/no vote)")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("ballot")
+                        .long("ballot")
+                        .help("How the candidates are voted for: single (one candidate), approval:<max approvals> or score:<max score per candidate>:<budget>. Or veto, for an anonymous veto election without candidates")
+                        .default_value("single"),
+                )
+                .arg(
+                    Arg::new("deposit")
+                        .long("deposit")
+                        .help("Deposit each voter pays at registration, in microCCD")
+                        .required(true)
+                        .value_parser(value_parser!(u64)),
+                )
+                .arg(timeout("registration-timeout", "End of the registration phase (RFC 3339, e.g. 2022-06-08T21:00:01Z)"))
+                .arg(timeout("commit-timeout", "End of the commit phase (RFC 3339)"))
+                .arg(timeout("vote-timeout", "End of the vote phase (RFC 3339)"))
+                .arg(timeout("recovery-timeout", "End of the recovery phase (RFC 3339)"))
+                .arg(
+                    Arg::new("min-registrants")
+                        .long("min-registrants")
+                        .help("Number of registrations needed to go on with the vote at the registration timeout (at least 3)")
+                        .default_value("3")
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    Arg::new("quorum")
+                        .long("quorum")
+                        .help("Fraction of the eligible voters that must vote for the result to count, e.g. 1/2")
+                        .default_value("0/1"),
+                )
+                .arg(
+                    Arg::new("pass-rule")
+                        .long("pass-rule")
+                        .help("Share of the votes needed to pass: majority, unanimity or a supermajority fraction like 2/3")
+                        .default_value("majority"),
+                )
+                .arg(
+                    Arg::new("slashing")
+                        .long("slashing")
+                        .help("What happens to the deposits of stalling voters: burn:<sink account address>, split (among the honest voters) or reporter:<share of the caller who reports the stall, like 1/2>")
+                        .default_value("reporter:1/1"),
+                )
+                .arg(
+                    Arg::new("out")
+                        .long("out")
+                        .help("File to write the voteconfig to")
+                        .default_value("voteconfig.json")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("leaves-out")
+                        .long("leaves-out")
+                        .help("File to write the Merkle leaf list to, which voters pass to keygen and register with --leaves")
+                        .default_value("leaves.json")
+                        .value_parser(value_parser!(PathBuf)),
+                ),
+        )
+        .subcommand(
+            Command::new("export-proofs")
+                .about("Create the eligibility bundle (Merkle proof, root, leaf count, contract address and election id) of every eligible account")
+                .arg(leaves)
+                .arg(contract.clone())
+                .arg(election.clone())
+                .arg(
+                    Arg::new("out-dir")
+                        .long("out-dir")
+                        .help("Directory to write the bundles to, one <account address>.<format> file per account")
+                        .default_value("eligibility_proofs")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .help("Format of the bundles")
+                        .default_value("json")
+                        .value_parser(["json", "bin"]),
+                ),
+        )
+        .subcommand(
+            Command::new("keygen")
+                .about("Create a voting key pair (x, g^x) and save x to a keystore bound to the election and voter")
+                .arg(key.clone())
+                .arg(account.clone())
+                .arg(contract.clone())
+                .arg(election.clone())
+                .arg(leaves_or_proof.clone())
+                .arg(proof.clone()),
+        )
+        .subcommand(
+            Command::new("register")
+                .about("Create the register message with the voting key of each question, their ZKPs and the voter's Merkle proof")
+                .arg(key.clone())
+                .arg(account.clone())
+                .arg(contract.clone())
+                .arg(election.clone())
+                .arg(leaves_or_proof)
+                .arg(proof)
+                .arg(
+                    Arg::new("questions")
+                        .long("questions")
+                        .help("Number of questions of the ballot, from the voteconfig")
+                        .default_value("1")
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(out("register_msg.bin")),
+        )
+        .subcommand(
+            Command::new("commit")
+                .about("Create the commit message with the reconstructed key and the commitment to the choice of each question")
+                .arg(key.clone())
+                .arg(contract.clone())
+                .arg(election.clone())
+                .arg(voting_keys.clone())
+                .arg(
+                    Arg::new("choice")
+                        .long("choice")
+                        .help("The vote, repeated for each question of the ballot. Saved to the keystore for the vote step")
+                        .action(ArgAction::Append)
+                        .value_parser(["yes", "no"]),
+                )
+                .arg(
+                    Arg::new("candidate")
+                        .long("candidate")
+                        .help("Index of the candidate voted for on a single vote, instead of --choice")
+                        .action(ArgAction::Append)
+                        .value_parser(value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("approve")
+                        .long("approve")
+                        .help("Comma separated indices of the candidates approved of on an approval ballot (empty for none), instead of --choice")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("score")
+                        .long("score")
+                        .help("Comma separated score of each candidate on a score ballot, instead of --choice")
+                        .action(ArgAction::Append),
+                )
+                .group(
+                    ArgGroup::new("vote")
+                        .args(["choice", "candidate", "approve", "score"])
+                        .required(true),
+                )
+                .arg(veto.clone())
+                .arg(weight.clone())
+                .arg(number_of_options.clone())
+                .arg(total_weight.clone())
+                .arg(max_score.clone())
+                .arg(budget.clone())
+                .arg(out("commit_msg.bin")),
+        )
+        .subcommand(
+            Command::new("vote")
+                .about("Create the vote message for the committed choices, with their one-in-two (one-in-k or range) ZKPs")
+                .arg(key.clone())
+                .arg(account.clone())
+                .arg(contract.clone())
+                .arg(election.clone())
+                .arg(voting_keys.clone())
+                .arg(veto.clone())
+                .arg(weight)
+                .arg(number_of_options.clone())
+                .arg(total_weight.clone())
+                .arg(max_score.clone())
+                .arg(budget)
+                .arg(out("vote_msg.bin")),
+        )
+        .subcommand(
+            Command::new("recover")
+                .about("Create the recovery message with the recovery key of each question for the voters who did not vote, with their Chaum-Pedersen ZKPs")
+                .arg(key)
+                .arg(account)
+                .arg(contract)
+                .arg(election)
+                .arg(voting_keys)
+                .arg(voters.clone())
+                .arg(out("recovery_msg.bin")),
+        )
+        .subcommand(
+            Command::new("verify-result")
+                .about("Recompute the result of each question from the published votes and check the stored result")
+                .arg(voters)
+                .arg(
+                    Arg::new("result")
+                        .long("result")
+                        .help("JSON file with the result returned by view_result")
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    Arg::new("outcome")
+                        .long("outcome")
+                        .help("JSON file with the outcome returned by view_outcome, to check the outcome of a veto election")
+                        .required_if_eq("veto", "true")
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(total_weight.required(true))
+                .arg(number_of_options)
+                .arg(max_score)
+                .arg(veto),
+        )
+        .subcommand(
+            Command::new("test-election")
+                .about("Create all messages for a test election with hardcoded accounts in ../data/parameters"),
+        )
+}
+
+/// Create the voteconfig from the list of eligible account addresses and their weights, and export the Merkle leaf list
+fn voteconfig(args: &ArgMatches) -> std::io::Result<()> {
+    let addresses = read_addresses(args.get_one::<PathBuf>("addresses").unwrap())?;
+    let voter_accounts: Vec<(AccountAddress, u32)> = addresses
+        .iter()
+        .map(|(_, account, weight)| (*account, *weight))
+        .collect();
+    let merkle_tree = off_chain::create_merkle_tree(&voter_accounts);
+    let total_weight: u64 = voter_accounts.iter().map(|(_, weight)| *weight as u64).sum();
+    if total_weight > i32::MAX as u64 {
+        return Err(invalid_input("the weights of the eligible voters add up to more than the contract can count"));
+    }
+
+    let voting_options: Vec<&str> = args
+        .get_many::<String>("option")
+        .map(|options| options.map(String::as_str).collect())
+        .unwrap_or_default();
+    if voting_options.len() == 1 {
+        return Err(invalid_input("a vote between candidates needs at least two options"));
+    }
+    let ballot_type = args.get_one::<String>("ballot").unwrap();
+    match ballot_type.as_str() {
+        "single" => (),
+        "veto" if !voting_options.is_empty() => {
+            return Err(invalid_input("a veto election has no candidates"));
+        }
+        "veto" => (),
+        _ if voting_options.is_empty() => {
+            return Err(invalid_input("approval and score ballots need candidates"));
+        }
+        _ => (),
+    }
+
+    let min_registrants = *args.get_one::<i32>("min-registrants").unwrap();
+    if min_registrants < 3 || min_registrants as usize > voter_accounts.len() {
+        return Err(invalid_input(
+            "the minimum number of registrants must be between 3 and the number of eligible voters",
+        ));
+    }
+
+    let voting_questions: Vec<&str> = args
+        .get_many::<String>("question")
+        .unwrap()
+        .map(String::as_str)
+        .collect();
+
+    let json = voteconfig_json(
+        &merkle_tree,
+        total_weight,
+        &voting_questions,
+        &voting_options,
+        ballot_type,
+        *args.get_one::<u64>("deposit").unwrap(),
+        [
+            args.get_one::<String>("registration-timeout").unwrap(),
+            args.get_one::<String>("commit-timeout").unwrap(),
+            args.get_one::<String>("vote-timeout").unwrap(),
+            args.get_one::<String>("recovery-timeout").unwrap(),
+        ],
+        min_registrants,
+        parse_fraction(args.get_one::<String>("quorum").unwrap())?,
+        args.get_one::<String>("pass-rule").unwrap(),
+        args.get_one::<String>("slashing").unwrap(),
+    )?;
+    let out = args.get_one::<PathBuf>("out").unwrap();
+    fs::write(out, serde_json::to_string_pretty(&json)?)?;
+    println!("Wrote {}", out.display());
+
+    // The leaves in the order of the Merkle tree, as read by keygen and register
+    let leaves: Vec<Value> = addresses
+        .iter()
+        .map(|(address, _, weight)| json!({ "address": address, "weight": weight }))
+        .collect();
+    let leaves_out = args.get_one::<PathBuf>("leaves-out").unwrap();
+    fs::write(leaves_out, serde_json::to_string_pretty(&leaves)?)?;
+    println!("Wrote {}", leaves_out.display());
+
+    println!(
+        "Eligible voters: {}, total weight: {}, Merkle root: {}",
+        merkle_tree.leaves_len(),
+        total_weight,
+        json["merkle_root"].as_str().unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Create the eligibility bundle of every eligible account, for the voters to register with
+fn export_proofs(args: &ArgMatches) -> std::io::Result<()> {
+    let leaves = read_leaves(args.get_one::<PathBuf>("leaves").unwrap())?;
+    let bundles = eligibility::create_bundles(&leaves, contract_address(args), election_id(args))?;
+
+    let out_dir = args.get_one::<PathBuf>("out-dir").unwrap();
+    let format = args.get_one::<String>("format").unwrap();
+    fs::create_dir_all(out_dir)?;
+    for ((account, _), bundle) in leaves.iter().zip(bundles.iter()) {
+        let file_name = format!("{}.{}", account.0.to_base58check(1), format);
+        eligibility::export(&out_dir.join(file_name), bundle)?;
+    }
+
+    println!("Wrote {} bundles to {}", bundles.len(), out_dir.display());
+    Ok(())
+}
+
+/// Create a voting key pair and save x to the keystore, bound to the election and the voter's index in the list of eligible voters
+fn keygen(args: &ArgMatches) -> std::io::Result<()> {
+    let key_file = args.get_one::<PathBuf>("key").unwrap();
+    let account = parse_account(args.get_one::<String>("account").unwrap())?;
+
+    let binding = KeystoreBinding {
+        contract_address: contract_address(args),
+        election_id: election_id(args),
+        voter_index: merkle_proof(args, &account)?.index,
+    };
+
+    let (x, g_x) = off_chain::create_votingkey_pair();
+    let secrets = VoterSecrets {
+        x,
+        choices: Vec::new(),
+    };
+    keystore::save(key_file, &read_password()?, &secrets, &binding)?;
+
+    println!("Voting key (g^x): {}", hex::encode(g_x.to_bytes()));
+    Ok(())
+}
+
+/// Create the register message of the voter, with a voting key for each question
+fn register(args: &ArgMatches) -> std::io::Result<()> {
+    let account = parse_account(args.get_one::<String>("account").unwrap())?;
+    let context = proof_context(contract_address(args), election_id(args), account);
+
+    let merkle_proof = merkle_proof(args, &account)?;
+    let (secrets, binding) = load_keystore(args)?;
+    keystore::ensure_voter(&binding, merkle_proof.index)?;
+
+    let number_of_questions = *args.get_one::<usize>("questions").unwrap();
+    if number_of_questions == 0 {
+        return Err(invalid_input("a ballot has at least one question"));
+    }
+    let keys = off_chain::derive_question_keys(&secrets.x, number_of_questions);
+
+    let register_msg = RegisterMessage {
+        election_id: election_id(args),
+        voting_keys: keys.iter().map(|(_, g_x)| g_x.to_bytes().to_vec()).collect(),
+        voting_key_zkps: keys
+            .iter()
+            .map(|(x, g_x)| off_chain::create_schnorr_zkp(*g_x, *x, &context))
+            .collect(),
+        merkle_proof,
+    };
+
+    write_parameter(
+        args.get_one::<PathBuf>("out").unwrap(),
+        &to_bytes(&register_msg),
+    )
+}
+
+/// Create the commit message of the voter, and save the choices to the keystore for the vote step
+fn commit(args: &ArgMatches) -> std::io::Result<()> {
+    let key_file = args.get_one::<PathBuf>("key").unwrap();
+    let password = read_password()?;
+    let (mut secrets, binding) = keystore::load(
+        key_file,
+        &password,
+        contract_address(args),
+        election_id(args),
+    )?;
+    let choices = parse_choices(args)?;
+    let keys = off_chain::derive_question_keys(&secrets.x, choices.len());
+    let weight = *args.get_one::<u32>("weight").unwrap();
+    let encodings = ballot_encodings(args, &choices)?;
+
+    let g_ys = reconstructed_keys(args.get_one::<PathBuf>("voting-keys").unwrap(), &keys)?;
+
+    let commit_msg = CommitMessage {
+        election_id: election_id(args),
+        reconstructed_keys: g_ys.iter().map(|g_y| g_y.to_bytes().to_vec()).collect(),
+        commitments: keys
+            .iter()
+            .zip(g_ys.iter())
+            .zip(choices.iter())
+            .map(|(((x, _), g_y), choice)| {
+                if args.get_flag("veto") {
+                    // Commit to g^(c*y), the vote with the exponent c of the choice
+                    let (c, _) = off_chain::create_veto_vote(x, g_y, *choice == Choice::YesNo(false));
+                    off_chain::commit_to_vote(&c, g_y, ProjectivePoint::IDENTITY)
+                } else {
+                    off_chain::commit_to_vote(x, g_y, choice_to_point(choice, weight, &encodings))
+                }
+            })
+            .collect(),
+    };
+
+    secrets.choices = choices;
+    keystore::save(key_file, &password, &secrets, &binding)?;
+    write_parameter(
+        args.get_one::<PathBuf>("out").unwrap(),
+        &to_bytes(&commit_msg),
+    )
+}
+
+/// Create the vote message of the voter for the choices saved in the commit step
+fn vote(args: &ArgMatches) -> std::io::Result<()> {
+    let account = parse_account(args.get_one::<String>("account").unwrap())?;
+    let context = proof_context(contract_address(args), election_id(args), account);
+
+    let (secrets, _) = load_keystore(args)?;
+    if secrets.choices.is_empty() {
+        return Err(invalid_input("no choices saved, run the commit step first"));
+    }
+    let keys = off_chain::derive_question_keys(&secrets.x, secrets.choices.len());
+    let weight = *args.get_one::<u32>("weight").unwrap();
+    let encodings = ballot_encodings(args, &secrets.choices)?;
+
+    let g_ys = reconstructed_keys(args.get_one::<PathBuf>("voting-keys").unwrap(), &keys)?;
+
+    let mut votes = Vec::new();
+    let mut vote_zkps = Vec::new();
+    for (((x, g_x), g_y), choice) in keys.iter().zip(g_ys.iter()).zip(secrets.choices.iter()) {
+        if args.get_flag("veto") {
+            let (c, vote) = off_chain::create_veto_vote(x, g_y, *choice == Choice::YesNo(false));
+            votes.push(vote.to_bytes().to_vec());
+            vote_zkps.push(VoteZKP::Veto(off_chain::create_veto_zkp(*g_y, vote, c, &context)));
+            continue;
+        }
+        let vote_zkp = match choice {
+            Choice::YesNo(true) => VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_yes(
+                *g_x,
+                *g_y,
+                *x,
+                weight as u64,
+                &context,
+            )),
+            Choice::YesNo(false) => VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_no(
+                *g_x,
+                *g_y,
+                *x,
+                weight as u64,
+                &context,
+            )),
+            Choice::Candidate(candidate) => VoteZKP::OneInK(off_chain::create_one_in_k_zkp(
+                *g_x,
+                *g_y,
+                *x,
+                *candidate,
+                &util::weighted_encodings(&encodings, weight as u64),
+                &context,
+            )),
+            Choice::Scores(scores) => VoteZKP::Range(off_chain::create_range_zkp(
+                *g_y,
+                *x,
+                scores,
+                max_score(args),
+                *args.get_one::<u64>("budget").unwrap(),
+                &encodings,
+                weight as u64,
+                &context,
+            )),
+        };
+        votes.push(((g_y * x) + choice_to_point(choice, weight, &encodings)).to_bytes().to_vec());
+        vote_zkps.push(vote_zkp);
+    }
+    let vote_msg = VoteMessage {
+        election_id: election_id(args),
+        votes,
+        vote_zkps,
+    };
+
+    write_parameter(
+        args.get_one::<PathBuf>("out").unwrap(),
+        &to_bytes(&vote_msg),
+    )
+}
+
+/// Create the recovery message of the voter, with the recovery key h^x of each question for the voters who did not vote
+fn recover(args: &ArgMatches) -> std::io::Result<()> {
+    let account = parse_account(args.get_one::<String>("account").unwrap())?;
+    let context = proof_context(contract_address(args), election_id(args), account);
+
+    let (secrets, _) = load_keystore(args)?;
+    let voting_keys_file = args.get_one::<PathBuf>("voting-keys").unwrap();
+    let number_of_questions = as_array(&read_json(voting_keys_file)?)?.len();
+    let keys = off_chain::derive_question_keys(&secrets.x, number_of_questions);
+
+    let missing_voters = missing_voters(args.get_one::<PathBuf>("voters").unwrap())?;
+    if missing_voters.is_empty() {
+        return Err(invalid_input("every voter voted, there are no keys to recover"));
+    }
+
+    let mut recovery_keys = Vec::new();
+    let mut recovery_zkps = Vec::new();
+    for ((x, g_x), (voting_keys, index)) in keys.iter().zip(question_voting_keys(voting_keys_file, &keys)?) {
+        if missing_voters.iter().any(|missing| *missing as usize >= voting_keys.len()) {
+            return Err(invalid_data("a voter who did not vote is not in the voting keys"));
+        }
+        let h = util::compute_recovery_base(&voting_keys, index as i32, &missing_voters);
+        recovery_keys.push(off_chain::create_recovery_key(x, &h).to_bytes().to_vec());
+        recovery_zkps.push(off_chain::create_chaum_pedersen_zkp(*g_x, h, *x, &context));
+    }
+    let recovery_msg = RecoveryMessage {
+        election_id: election_id(args),
+        recovery_keys,
+        recovery_zkps,
+    };
+
+    write_parameter(
+        args.get_one::<PathBuf>("out").unwrap(),
+        &to_bytes(&recovery_msg),
+    )
+}
+
+/// Recompute the result of each question from the published votes and recovery keys, and compare it to the stored result
+fn verify_result(args: &ArgMatches) -> std::io::Result<()> {
+    let voters = read_json(args.get_one::<PathBuf>("voters").unwrap())?;
+    let stored_result = read_json(args.get_one::<PathBuf>("result").unwrap())?;
+
+    // view_result returns the list of counts of each question
+    let stored_result: Vec<Vec<i32>> = as_array(&stored_result)?
+        .iter()
+        .map(|counts| {
+            as_array(counts)?
+                .iter()
+                .map(|count| {
+                    count
+                        .as_i64()
+                        .map(|c| c as i32)
+                        .ok_or_else(|| invalid_data("expected a list of counts for each question"))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<_, _>>()?;
+
+    // view_outcome returns the outcome of each question, of which a veto election only stores whether anyone vetoed
+    let stored_outcome: Vec<String> = match args.get_one::<PathBuf>("outcome") {
+        Some(file) => as_array(&read_json(file)?)?
+            .iter()
+            .map(|outcome| {
+                outcome
+                    .as_object()
+                    .and_then(|variant| variant.keys().next().cloned())
+                    .ok_or_else(|| invalid_data("expected an outcome for each question"))
+            })
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+    if args.get_flag("veto") && stored_outcome.len() != stored_result.len() {
+        return Err(invalid_data("expected an outcome for each question"));
+    }
+
+    // view_voters returns a list of (account, voter) pairs, with a vote and recovery key for each question (empty lists if not sent) and the weight
+    let mut computed_result = Vec::new();
+    for question in 0..stored_result.len() {
+        let mut votes = Vec::new();
+        let mut recovery_keys = Vec::new();
+        let mut weights = Vec::new();
+        for entry in as_array(&voters)? {
+            let voter = entry
+                .get(1)
+                .ok_or_else(|| invalid_data("expected (account, voter) pairs"))?;
+            votes.push(question_bytes(&voter["votes"], question)?);
+            recovery_keys.push(question_bytes(&voter["recovery_keys"], question)?);
+            weights.push(
+                voter["weight"]
+                    .as_u64()
+                    .filter(|weight| *weight <= u32::MAX as u64)
+                    .map(|weight| weight as u32)
+                    .ok_or_else(|| invalid_data("expected the weight of each voter"))?,
+            );
+        }
+
+        // A veto election stores no counts, only the outcome of whether anyone vetoed.
+        // If the quorum was not met the outcome does not tell whether anyone vetoed, so there is nothing to compare
+        if args.get_flag("veto") {
+            let vetoed = off_chain::compute_veto_result(&votes, &recovery_keys);
+            println!("Question {}: {}", question, if vetoed { "vetoed" } else { "nobody vetoed" });
+            let outcome = stored_outcome.get(question).map(String::as_str).unwrap_or_default();
+            if outcome != "QuorumNotMet" && vetoed != (outcome == "Vetoed") {
+                return Err(invalid_data(&format!(
+                    "stored outcome {} of question {} does not match the published votes",
+                    outcome, question
+                )));
+            }
+            computed_result.push(Vec::new());
+            continue;
+        }
+
+        let number_of_options = *args.get_one::<usize>("number-of-options").unwrap();
+        let total_weight = *args.get_one::<i32>("total-weight").unwrap();
+        let result = match args.get_one::<u64>("max-score") {
+            Some(max_score) => off_chain::compute_score_result(
+                &votes,
+                &recovery_keys,
+                &weights,
+                number_of_options,
+                *max_score,
+                total_weight,
+            ),
+            None => off_chain::compute_result(
+                &votes,
+                &recovery_keys,
+                &weights,
+                number_of_options,
+                total_weight,
+            ),
+        };
+        computed_result.push(result.ok_or_else(|| invalid_data("the published votes do not tally"))?);
+    }
+
+    println!("Computed result: {:?}", computed_result);
+    if computed_result != stored_result {
+        return Err(invalid_data(&format!(
+            "stored result {:?} does not match the published votes",
+            stored_result
+        )));
+    }
+    println!("Stored result matches the published votes");
+    Ok(())
+}
+
+/// Creates all messages of a test election with hardcoded accounts all voting "yes"
+fn test_election() -> std::io::Result<()> {
     let (merkle_tree, voter_accounts) = make_voteconfig_json()?;
 
-    let (list_of_scalar, list_of_voting_keys) = make_register_msg(merkle_tree, voter_accounts)?;
+    let (list_of_scalar, list_of_voting_keys) =
+        make_register_msg(merkle_tree, voter_accounts.clone())?;
 
     let list_of_reconstructed_keys =
         make_commit_msg(list_of_scalar.clone(), list_of_voting_keys.clone())?;
 
     make_vote_msg(
+        voter_accounts,
         list_of_scalar,
         list_of_voting_keys,
         list_of_reconstructed_keys,
@@ -43,6 +832,389 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
+/// The ZKP context of a voter sending messages for an election of the contract instance
+fn proof_context(
+    contract_address: ContractAddress,
+    election_id: u64,
+    sender: AccountAddress,
+) -> util::ProofContext {
+    util::ProofContext {
+        contract_address,
+        election_id,
+        sender,
+    }
+}
+
+/// The address of the contract instance given by the contract argument
+fn contract_address(args: &ArgMatches) -> ContractAddress {
+    ContractAddress {
+        index: *args.get_one::<u64>("contract").unwrap(),
+        subindex: 0,
+    }
+}
+
+/// The id of the election given by the election argument
+fn election_id(args: &ArgMatches) -> u64 {
+    *args.get_one::<u64>("election").unwrap()
+}
+
+/// Decrypt the voter's keystore, refusing keystores of other elections
+fn load_keystore(args: &ArgMatches) -> std::io::Result<(VoterSecrets, KeystoreBinding)> {
+    Ok(keystore::load(
+        args.get_one::<PathBuf>("key").unwrap(),
+        &read_password()?,
+        contract_address(args),
+        election_id(args),
+    )?)
+}
+
+/// Read the keystore password from the VOTER_KEYSTORE_PASSWORD environment variable, or else prompt for it without echoing it
+fn read_password() -> std::io::Result<Zeroizing<Vec<u8>>> {
+    if let Ok(password) = std::env::var("VOTER_KEYSTORE_PASSWORD") {
+        return Ok(Zeroizing::new(password.into_bytes()));
+    }
+
+    let password = Zeroizing::new(rpassword::prompt_password("Keystore password: ")?);
+    Ok(Zeroizing::new(password.as_bytes().to_vec()))
+}
+
+/// The voter's Merkle proof, from the validated eligibility bundle if given and otherwise built from the full leaf list.
+/// Its index is the voter's index in the list of eligible voters
+fn merkle_proof(args: &ArgMatches, account: &AccountAddress) -> std::io::Result<util::MerkleProof> {
+    if let Some(proof_file) = args.get_one::<PathBuf>("proof") {
+        let bundle = eligibility::import(proof_file)?;
+        bundle.validate(account, contract_address(args), election_id(args))?;
+        return Ok(bundle.merkle_proof);
+    }
+
+    let leaves = read_leaves(args.get_one::<PathBuf>("leaves").unwrap())?;
+    let weight = match leaves.iter().find(|(leaf, _)| leaf == account) {
+        Some((_, weight)) => *weight,
+        None => return Err(invalid_input("account is not in the list of eligible voters")),
+    };
+    Ok(off_chain::create_merkle_proof(
+        *account,
+        weight,
+        &off_chain::create_merkle_tree(&leaves),
+    ))
+}
+
+/// Compute the voter's reconstructed key of each question from the on-chain voting keys of the questions
+fn reconstructed_keys(
+    voting_keys_file: &PathBuf,
+    keys: &[(Scalar, ProjectivePoint)],
+) -> std::io::Result<Vec<ProjectivePoint>> {
+    Ok(question_voting_keys(voting_keys_file, keys)?
+        .iter()
+        .map(|(voting_keys, index)| util::compute_reconstructed_key(voting_keys, *index as i32))
+        .collect())
+}
+
+/// The on-chain voting keys of each question along with the voter's index, which the voter's own key gives
+fn question_voting_keys(
+    voting_keys_file: &PathBuf,
+    keys: &[(Scalar, ProjectivePoint)],
+) -> std::io::Result<Vec<(Vec<ProjectivePoint>, usize)>> {
+    let questions = as_array(&read_json(voting_keys_file)?)?.clone();
+    if questions.len() != keys.len() {
+        return Err(invalid_input(&format!(
+            "the ballot has {} questions, but {} choices were given",
+            questions.len(),
+            keys.len()
+        )));
+    }
+
+    let mut question_voting_keys = Vec::new();
+    for (question, (_, g_x)) in questions.iter().zip(keys.iter()) {
+        let voting_keys = as_array(question)?
+            .iter()
+            .map(parse_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let index = voting_keys
+            .iter()
+            .position(|key| *key == g_x.to_bytes().to_vec())
+            .ok_or_else(|| invalid_input("voting key is not registered"))?;
+
+        let voting_keys: Vec<ProjectivePoint> = voting_keys
+            .iter()
+            .map(|key| util::try_convert_vec_to_point(key))
+            .collect::<Result<_, _>>()
+            .map_err(|_| invalid_data("invalid voting key"))?;
+
+        question_voting_keys.push((voting_keys, index));
+    }
+    Ok(question_voting_keys)
+}
+
+/// The indices of the voters who did not vote, from the voters returned by view_voters
+fn missing_voters(voters_file: &PathBuf) -> std::io::Result<Vec<i32>> {
+    let mut missing_voters = Vec::new();
+    for entry in as_array(&read_json(voters_file)?)? {
+        let voter = entry
+            .get(1)
+            .ok_or_else(|| invalid_data("expected (account, voter) pairs"))?;
+        if as_array(&voter["votes"])?.is_empty() {
+            let index = voter["index"]
+                .as_u64()
+                .filter(|index| *index <= i32::MAX as u64)
+                .ok_or_else(|| invalid_data("expected the index of each voter"))?;
+            missing_voters.push(index as i32);
+        }
+    }
+    Ok(missing_voters)
+}
+
+/// The vote as a point g^v for a voter of weight w: v = w for yes and 0 for no, w*m^j for candidate j
+/// and w times the sum of s_j*m^j for the scores s_j, given the encodings m^j of the candidates
+fn choice_to_point(choice: &Choice, weight: u32, encodings: &[u64]) -> ProjectivePoint {
+    let encodings = util::weighted_encodings(encodings, weight as u64);
+    match choice {
+        Choice::YesNo(true) => ProjectivePoint::GENERATOR * Scalar::from(weight as u64),
+        Choice::YesNo(false) => ProjectivePoint::IDENTITY,
+        Choice::Candidate(candidate) => off_chain::encode_candidate(*candidate, &encodings),
+        Choice::Scores(scores) => off_chain::encode_scores(scores, &encodings),
+    }
+}
+
+/// The choice of each question from --choice, --candidate, --approve or --score
+fn parse_choices(args: &ArgMatches) -> std::io::Result<Vec<Choice>> {
+    if let Some(choices) = args.get_many::<String>("choice") {
+        return Ok(choices.map(|choice| Choice::YesNo(choice == "yes")).collect());
+    }
+    if let Some(candidates) = args.get_many::<usize>("candidate") {
+        return Ok(candidates.map(|candidate| Choice::Candidate(*candidate)).collect());
+    }
+    if let Some(approvals) = args.get_many::<String>("approve") {
+        let number_of_options = *args.get_one::<usize>("number-of-options").unwrap();
+        return approvals
+            .map(|approved| {
+                let mut scores = vec![0; number_of_options];
+                for candidate in parse_list(approved)? {
+                    match scores.get_mut(candidate as usize) {
+                        Some(score) => *score = 1,
+                        None => return Err(invalid_input(&format!("there is no candidate {}", candidate))),
+                    }
+                }
+                Ok(Choice::Scores(scores))
+            })
+            .collect();
+    }
+    args.get_many::<String>("score")
+        .unwrap()
+        .map(|scores| Ok(Choice::Scores(parse_list(scores)?)))
+        .collect()
+}
+
+/// The encodings m^j of the candidates the choices are encoded with (the same as the contract's), empty for a yes/no vote.
+/// Every choice must be a valid vote of the ballot given by the arguments
+fn ballot_encodings(args: &ArgMatches, choices: &[Choice]) -> std::io::Result<Vec<u64>> {
+    let number_of_options = *args.get_one::<usize>("number-of-options").unwrap();
+    if choices.iter().all(|choice| matches!(choice, Choice::YesNo(_))) {
+        if number_of_options != 0 {
+            return Err(invalid_input("a vote between candidates takes --candidate, --approve or --score instead of --choice"));
+        }
+        return Ok(Vec::new());
+    }
+    if args.get_flag("veto") {
+        return Err(invalid_input("a veto election has no candidates"));
+    }
+    if number_of_options < 2 {
+        return Err(invalid_input("a vote between candidates needs --number-of-options of at least two"));
+    }
+    let total_weight = args
+        .get_one::<i32>("total-weight")
+        .filter(|total_weight| **total_weight > 0)
+        .ok_or_else(|| invalid_input("a vote between candidates needs --total-weight"))?;
+
+    let max_score = max_score(args);
+    for choice in choices {
+        match choice {
+            Choice::Candidate(candidate) if *candidate < number_of_options => (),
+            Choice::Candidate(candidate) => {
+                return Err(invalid_input(&format!("there is no candidate {}", candidate)));
+            }
+            Choice::Scores(scores) => {
+                if scores.len() != number_of_options || scores.iter().any(|score| *score > max_score) {
+                    return Err(invalid_input(&format!(
+                        "expected a score of at most {} for each of the {} candidates",
+                        max_score, number_of_options
+                    )));
+                }
+                let budget = args
+                    .get_one::<u64>("budget")
+                    .ok_or_else(|| invalid_input("an approval or score ballot needs --budget"))?;
+                if scores.iter().sum::<u64>() > *budget {
+                    return Err(invalid_input(&format!("the scores add up to more than the budget of {}", budget)));
+                }
+            }
+            Choice::YesNo(_) => return Err(invalid_input("expected a choice between candidates on every question")),
+        }
+    }
+
+    util::candidate_encodings(number_of_options, *total_weight as u64 * max_score + 1)
+        .ok_or_else(|| invalid_input("too many candidates to encode"))
+}
+
+/// The highest score of a candidate given by --max-score, 1 if not given (a single vote or an approval ballot)
+fn max_score(args: &ArgMatches) -> u64 {
+    args.get_one::<u64>("max-score").copied().unwrap_or(1)
+}
+
+/// Parse a comma separated list of numbers, which is empty for an empty string
+fn parse_list(list: &str) -> std::io::Result<Vec<u64>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            entry
+                .parse::<u64>()
+                .map_err(|_| invalid_input(&format!("invalid number {:?}", entry)))
+        })
+        .collect()
+}
+
+/// Read the eligible account addresses and their weights from a JSON list of addresses or {"address", "weight"} objects,
+/// or from the first two columns of a CSV file (skipping an "address" header row). A voter without a weight has weight 1.
+/// Every address and weight is validated, and all invalid or duplicate addresses are reported at once
+fn read_addresses(file: &Path) -> std::io::Result<Vec<(String, AccountAddress, u32)>> {
+    let is_json = file.extension().is_some_and(|ext| ext == "json");
+    let entries: Vec<(usize, String, String)> = if is_json {
+        as_array(&serde_json::from_str(&fs::read_to_string(file)?)?)?
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| match entry {
+                Value::Object(leaf) => (
+                    i + 1,
+                    leaf.get("address").and_then(Value::as_str).unwrap_or_default().to_string(),
+                    leaf.get("weight").map(Value::to_string).unwrap_or_default(),
+                ),
+                address => (i + 1, address.as_str().unwrap_or_default().to_string(), String::new()),
+            })
+            .collect()
+    } else {
+        fs::read_to_string(file)?
+            .lines()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut columns = row.split(',').map(|column| column.trim().trim_matches('"').to_string());
+                let address = columns.next().unwrap_or_default();
+                (i + 1, address, columns.next().unwrap_or_default())
+            })
+            .filter(|(i, address, _)| {
+                !address.is_empty() && (*i != 1 || !address.eq_ignore_ascii_case("address"))
+            })
+            .collect()
+    };
+
+    let mut addresses: Vec<(String, AccountAddress, u32)> = Vec::new();
+    let mut problems = Vec::new();
+    for (entry, address, weight) in entries {
+        let weight = match weight.as_str() {
+            "" => 1,
+            weight => match weight.parse::<u32>() {
+                Ok(weight) if weight > 0 => weight,
+                _ => {
+                    problems.push(format!("entry {}: invalid weight {:?}", entry, weight));
+                    continue;
+                }
+            },
+        };
+        match AccountAddress2::from_str(&address) {
+            Ok(AccountAddress2(account)) if addresses.iter().any(|(_, a, _)| *a == account) => {
+                problems.push(format!("entry {}: duplicate address {}", entry, address))
+            }
+            Ok(AccountAddress2(account)) => addresses.push((address, account, weight)),
+            Err(_) => problems.push(format!("entry {}: invalid address {:?}", entry, address)),
+        }
+    }
+
+    if !problems.is_empty() {
+        return Err(invalid_input(&problems.join("\n")));
+    }
+    if addresses.is_empty() || addresses.len() > i32::MAX as usize {
+        return Err(invalid_input("expected at least one eligible account address"));
+    }
+    Ok(addresses)
+}
+
+/// Read the list of eligible account addresses (base58) and their weights in the order of the Merkle leaves.
+/// A leaf is an {"address", "weight"} object, or just the address for a voter of weight 1
+fn read_leaves(leaves_file: &PathBuf) -> std::io::Result<Vec<(AccountAddress, u32)>> {
+    as_array(&read_json(leaves_file)?)?
+        .iter()
+        .map(|leaf| match leaf {
+            Value::Object(_) => {
+                let weight = leaf["weight"]
+                    .as_u64()
+                    .filter(|weight| *weight > 0 && *weight <= u32::MAX as u64)
+                    .ok_or_else(|| invalid_data("invalid weight in the list of eligible voters"))?;
+                Ok((parse_account(leaf["address"].as_str().unwrap_or_default())?, weight as u32))
+            }
+            address => Ok((parse_account(address.as_str().unwrap_or_default())?, 1)),
+        })
+        .collect()
+}
+
+/// Write a binary parameter file for concordium-client
+fn write_parameter(out: &PathBuf, parameter: &[u8]) -> std::io::Result<()> {
+    File::create(out)?.write_all(parameter)?;
+    println!("Wrote {}", out.display());
+    Ok(())
+}
+
+fn read_json(file: &PathBuf) -> std::io::Result<Value> {
+    Ok(serde_json::from_str(&fs::read_to_string(file)?)?)
+}
+
+fn as_array(json: &Value) -> std::io::Result<&Vec<Value>> {
+    json.as_array()
+        .ok_or_else(|| invalid_data("expected a JSON list"))
+}
+
+/// The bytes of the given question from a list with an entry per question, empty if the list is empty (not sent)
+fn question_bytes(json: &Value, question: usize) -> std::io::Result<Vec<u8>> {
+    let entries = as_array(json)?;
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    entries
+        .get(question)
+        .ok_or_else(|| invalid_data("expected an entry for each question"))
+        .and_then(parse_bytes)
+}
+
+/// Parse bytes given either as a hex string or as a list of numbers (as concordium-client prints them)
+fn parse_bytes(json: &Value) -> std::io::Result<Vec<u8>> {
+    match json {
+        Value::String(s) => hex::decode(s).map_err(|_| invalid_data("invalid hex string")),
+        Value::Array(bytes) => bytes
+            .iter()
+            .map(|b| {
+                b.as_u64()
+                    .filter(|b| *b <= u8::MAX as u64)
+                    .map(|b| b as u8)
+                    .ok_or_else(|| invalid_data("invalid byte"))
+            })
+            .collect(),
+        _ => Err(invalid_data("expected bytes")),
+    }
+}
+
+fn parse_account(address: &str) -> std::io::Result<AccountAddress> {
+    AccountAddress2::from_str(address)
+        .map(|a| a.0)
+        .map_err(|_| invalid_input(&format!("invalid account address: {}", address)))
+}
+
+fn invalid_input(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
 pub struct AccountAddress2(AccountAddress);
 
 impl FromStr for AccountAddress2 {
@@ -60,147 +1232,41 @@ impl FromStr for AccountAddress2 {
     }
 }
 
-/// Generates voteconfig and creates MerkleTree
+/// Generates voteconfig and creates MerkleTree for the test election
 pub fn make_voteconfig_json() -> std::io::Result<(MerkleTree<merkle_sha256>, Vec<AccountAddress>)> {
-    let voter_accounts = vec![
-        AccountAddress2::from_str("4SxRVot39zszDDGe1jqprRHbF3D13EJ4MA7i2BMK88kfqG74TB")
-            .unwrap()
-            .0,
-        AccountAddress2::from_str("3n1ogkGKpdXavtV5AKLeEMbyveZs9NXiVcWcjVeTBVzav6CmZK")
-            .unwrap()
-            .0,
-        AccountAddress2::from_str("4mFJcz47gStZE1PqcKisYQsHRYSYTSByPYBaQWK818z71ympj7")
-            .unwrap()
-            .0,
-        AccountAddress2::from_str("3xZ3bWixa3d9WUtWR1d7imnNgvRJnzWJUCcy4mQMJXo8UQvt8C")
-            .unwrap()
-            .0,
-        AccountAddress2::from_str("35RAEq3DsLwkd92b3esHxxBh8JyTpvJPcnKQ7ZoJZ3Yg3qLBqC")
-            .unwrap()
-            .0,
-        // AccountAddress2::from_str("3FibLDUUXmoawtbu4CxHrQ1xo3tu2NwhCeu1xTfhYxgM63V71n")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3vtnN9YhLwBdvnHRkF1Y4xkbZKEnnEroAaWmWuWRQ3c8uss1Xd")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3PQnFEdEQ4F9TSSD5XggYr8MKZcUEfsZ6w4m8CjwkkxBkLZoAY")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("36gPYmMbHdvzuWmWzciacw6iUS8jg8gFXkgkCeYGFipEzeoHW5")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3XwjPSYEbRKjj1dGDVkH19ixtTJHTJw8p9PV19ABtio3vXN31D")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("49GEKXLHAq2cJqDnemFTVXP1nov8UGkpNJJyXdKQMsAd9JDmxr")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("43EHTjg4x2gU3EpiJLCqL9kU78wyxwME7CohWVr5pdzjgpgRdd")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("45zbAdUr8kc5ZNHhkMVQo9fkAgvVV8mtLqrUeFGBUtz81tx28W")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3nP31297qxnQK61eYKaAogkhhRNbyFTE6zszbBRUvGWnyE71Kz")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3DkVqTDUkpLsX5tjyZmr2oLyjbhm8ogyCo9kzn2hjaF1ssLJBs")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("399EHaAqiBhwDq5r9gM2tMLRc1pW3Xhsa4TPgsKEeceNjur6Fg")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("2ydSporJThjG96iQmWt27o9tsr9DP6J83uFh87cEkb2eTWXmqt")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3dogiXxVSzT45o3gDf34NbB2qSXeiUXTJiw7jQJzEiZEgumELr")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4EmBCUFB4SvJ9tGe1mGqmDrqYzvYhgqUF7ZthgFTkTjdvzQZKj")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4JiCVseYuWm3xWqpEj3gjAQHtpF7R7pgJhZ7RcEKemP1bL6Pjf")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3jAoD6JKzENTa8WdDbgY1bYJnAKXbfc9u1mJkC7dQGqfEiJjRo")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4jQKMqxe9uENkx8s3SUkj2TKLGtEJtWeMBc9t2qKa21Y49yBjK")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3qTURAUi4k5LqBuDrLRu5n2qqy772HcQMj4MiMo5KgrUmRYiZ9")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4ak2PL3ELyKxVQSGX2d9tmihcpLVVfX9u2qtpwfPFPgjBxnGXu")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3VuSdUPCLnEx7hgW2PdioXxr6r8A4PSb2pjdPx9Qs9y3426t4t")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3szKe7DeLF19Z9VmYKsiVCnYn2zwHtyGAMfePN9s39LwhJfHvh")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3qpqBxRYYXLrUeXYsu5L5yMQ2uyqmaoYQ8aLXY871ZkXsMYVE9")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("46qbxUp9JB5fYz36Q5ecrkVkatub1QTkarfrbxFFR4mMQWK1FM")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("31PCt8bC5PDVbgFrypdQEMDvekpDZF2TA3vcCPkDeEdjWGTPer")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("44s2udf7Ls4R8W3xDTJtR6HPeuSZTTaRPA5EJjvTF7Fn8svBeH")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3FEof7FzDHJ6FFnzTo4zeHv8gE2i6iJX5uLtWArtDDGA1sWJR2")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4pZP73WrnWmU7KeKBLRFexM2jMHN1y2mrbNaHssW3Q9WfTFjrz")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3kwJKxLRTqhTD9GHV5cXTa4tMceid1zD4kqv8H7zkj8cRe6exp")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("2xxvgHh99puU2WkLXSiyuWNPEnk4T5jKPtCdRZJRcJikPQnCsb")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4pLpayFj8QjT5sxHpUno2RT7Dt7AQ8YafTpDmX1DimD7t1kFkZ")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4HXGL81NCp4tfokz4aeC2YFcP9Xsq8GtwzPmgVqWPvAAiNeVp9")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3hNRjCez6if6HiWyFzwCZeus6rXoGqvYK7uQDD7zRwTXLgcUAu")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("3qxnTWhA7GM9v6ND9koWkzTzve93orudRhViM8LE7qLNzGeeix")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4Wz53dG9dqvc3hUs2FSk1DMieNC8Mk3EQQxwjoc6exmFj7Bd57")
-        //     .unwrap()
-        //     .0,
-        // AccountAddress2::from_str("4WuWh8hsJbeSocCjHRY1sD7DEBz21BgxapAatPFdVaZ3tza7yy")
-        //     .unwrap()
-        //     .0,
-    ];
-
-    let merkle_tree = lib::create_merkle_tree(&voter_accounts);
-    let root = merkle_tree
-        .root_hex()
-        .ok_or("Couldn't get the merkle root")
-        .unwrap();
+    let voter_accounts = [
+        "4SxRVot39zszDDGe1jqprRHbF3D13EJ4MA7i2BMK88kfqG74TB",
+        "3n1ogkGKpdXavtV5AKLeEMbyveZs9NXiVcWcjVeTBVzav6CmZK",
+        "4mFJcz47gStZE1PqcKisYQsHRYSYTSByPYBaQWK818z71ympj7",
+        "3xZ3bWixa3d9WUtWR1d7imnNgvRJnzWJUCcy4mQMJXo8UQvt8C",
+        "35RAEq3DsLwkd92b3esHxxBh8JyTpvJPcnKQ7ZoJZ3Yg3qLBqC",
+    ]
+    .iter()
+    .map(|address| parse_account(address))
+    .collect::<Result<Vec<_>, _>>()?;
 
-    // Voteconfig as json
-    let json = json!({
-        "merkle_root": root,
-        "merkle_leaf_count": merkle_tree.leaves_len(),
-        "voting_question": "Vote for x",
-        "deposit": "1000000",
-        "registration_timeout": "2022-06-08T21:00:01Z",
-        "commit_timeout": "2022-06-08T22:00:01Z",
-        "vote_timeout": "2022-06-08T23:00:01Z"
-    });
+    // Every test voter has weight 1
+    let leaves: Vec<(AccountAddress, u32)> = voter_accounts.iter().map(|account| (*account, 1)).collect();
+    let merkle_tree = off_chain::create_merkle_tree(&leaves);
+
+    let json = voteconfig_json(
+        &merkle_tree,
+        leaves.len() as u64,
+        &["Vote for x"],
+        &[],
+        "single",
+        1000000,
+        [
+            "2022-06-08T21:00:01Z",
+            "2022-06-08T22:00:01Z",
+            "2022-06-08T23:00:01Z",
+            "2022-06-09T00:00:01Z",
+        ],
+        3,
+        (0, 1),
+        "majority",
+        "reporter:1/1",
+    )?;
 
     std::fs::write(
         "../data/parameters/voteconfig.json",
@@ -210,6 +1276,117 @@ pub fn make_voteconfig_json() -> std::io::Result<(MerkleTree<merkle_sha256>, Vec
     Ok((merkle_tree, voter_accounts))
 }
 
+/// The voteconfig as json, as given to the create_election function of the contract.
+/// The timeouts are the registration, commit, vote and recovery timeouts, which the contract checks are increasing.
+/// The quorum is a fraction (numerator, denominator) and the pass rule is majority, unanimity or a supermajority fraction.
+/// The slashing policy is burn:<sink account address>, split or reporter:<fraction>, and the ballot type single, approval:<n>, score:<n>:<n> or veto
+#[allow(clippy::too_many_arguments)]
+fn voteconfig_json(
+    merkle_tree: &MerkleTree<merkle_sha256>,
+    total_weight: u64,
+    voting_questions: &[&str],
+    voting_options: &[&str],
+    ballot_type: &str,
+    deposit: u64,
+    timeouts: [&str; 4],
+    min_registrants: i32,
+    quorum: (u32, u32),
+    pass_rule: &str,
+    slashing_policy: &str,
+) -> std::io::Result<Value> {
+    let root = merkle_tree
+        .root_hex()
+        .ok_or_else(|| invalid_data("couldn't get the merkle root"))?;
+    Ok(json!({
+        "merkle_root": root,
+        "merkle_leaf_count": merkle_tree.leaves_len(),
+        "total_weight": total_weight,
+        "voting_questions": voting_questions,
+        "voting_options": voting_options,
+        "ballot_type": ballot_type_json(ballot_type)?,
+        "deposit": deposit.to_string(),
+        "registration_timeout": timeouts[0],
+        "commit_timeout": timeouts[1],
+        "vote_timeout": timeouts[2],
+        "recovery_timeout": timeouts[3],
+        "min_registrants": min_registrants,
+        "quorum": fraction_json(quorum),
+        "pass_rule": pass_rule_json(pass_rule)?,
+        "slashing_policy": slashing_policy_json(slashing_policy)?
+    }))
+}
+
+/// Parse a fraction of at most 1 given as numerator/denominator, e.g. 2/3
+fn parse_fraction(fraction: &str) -> std::io::Result<(u32, u32)> {
+    let (numerator, denominator) = fraction
+        .split_once('/')
+        .ok_or_else(|| invalid_input(&format!("expected a fraction like 2/3: {}", fraction)))?;
+    match (numerator.trim().parse::<u32>(), denominator.trim().parse::<u32>()) {
+        (Ok(n), Ok(d)) if d > 0 && n <= d => Ok((n, d)),
+        _ => Err(invalid_input(&format!("invalid fraction: {}", fraction))),
+    }
+}
+
+fn fraction_json((numerator, denominator): (u32, u32)) -> Value {
+    json!({ "numerator": numerator, "denominator": denominator })
+}
+
+/// The pass rule as json: majority, unanimity or a supermajority fraction of more than a half
+fn pass_rule_json(pass_rule: &str) -> std::io::Result<Value> {
+    match pass_rule {
+        "majority" => Ok(json!({ "Majority": [] })),
+        "unanimity" => Ok(json!({ "Unanimity": [] })),
+        fraction => match parse_fraction(fraction)? {
+            (n, d) if 2 * n as u64 > d as u64 => {
+                Ok(json!({ "Supermajority": [fraction_json((n, d))] }))
+            }
+            _ => Err(invalid_input("a supermajority must be more than a half")),
+        },
+    }
+}
+
+/// The ballot type as json: a single vote, approval with a maximum number of approvals, score with a maximum score and budget or veto
+fn ballot_type_json(ballot_type: &str) -> std::io::Result<Value> {
+    let parse = |bound: &str| {
+        bound
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|bound| *bound > 0)
+            .ok_or_else(|| invalid_input(&format!("expected a positive number: {}", bound)))
+    };
+    match ballot_type.split(':').collect::<Vec<_>>().as_slice() {
+        ["single"] => Ok(json!({ "Single": [] })),
+        ["veto"] => Ok(json!({ "Veto": [] })),
+        ["approval", max_approvals] => Ok(json!({ "Approval": [parse(max_approvals)?] })),
+        ["score", max_score, budget] => Ok(json!({
+            "Score": [{ "max_score": parse(max_score)?, "budget": parse(budget)? }]
+        })),
+        _ => Err(invalid_input(&format!(
+            "expected single, approval:<max approvals>, score:<max score>:<budget> or veto: {}",
+            ballot_type
+        ))),
+    }
+}
+
+/// The slashing policy as json: burn to a sink account, split among the honest voters or a share for the reporter
+fn slashing_policy_json(slashing_policy: &str) -> std::io::Result<Value> {
+    match slashing_policy.split_once(':') {
+        Some(("burn", address)) => {
+            parse_account(address)?;
+            Ok(json!({ "Burn": [address] }))
+        }
+        Some(("reporter", fraction)) => {
+            Ok(json!({ "Reporter": [fraction_json(parse_fraction(fraction)?)] }))
+        }
+        None if slashing_policy == "split" => Ok(json!({ "SplitAmongHonest": [] })),
+        _ => Err(invalid_input(&format!(
+            "expected burn:<address>, split or reporter:<fraction>: {}",
+            slashing_policy
+        ))),
+    }
+}
+
 /// Generates (x, g_x) and uses them to create register messages as binaries
 pub fn make_register_msg(
     merkle_tree: MerkleTree<merkle_sha256>,
@@ -219,8 +1396,12 @@ pub fn make_register_msg(
     let mut list_of_voting_keys: Vec<ProjectivePoint> = Vec::new();
 
     for i in 0..accounts.len() as usize {
-        let (x, g_x) = lib::create_votingkey_pair();
-        let schnorr = lib::create_schnorr_zkp(g_x, x);
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let schnorr = off_chain::create_schnorr_zkp(
+            g_x,
+            x,
+            &proof_context(TEST_CONTRACT_ADDRESS, TEST_ELECTION_ID, accounts[i]),
+        );
 
         fs::create_dir_all("../data/parameters/register_msgs")?;
 
@@ -231,9 +1412,10 @@ pub fn make_register_msg(
         let mut file = File::create(file_name)?;
 
         let register_msg = RegisterMessage {
-            voting_key: g_x.to_bytes().to_vec(),
-            voting_key_zkp: schnorr,
-            merkle_proof: lib::create_merkle_proof(accounts[i], &merkle_tree),
+            election_id: TEST_ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![schnorr],
+            merkle_proof: off_chain::create_merkle_proof(accounts[i], 1, &merkle_tree),
         };
 
         list_of_scalar.push(x);
@@ -261,8 +1443,9 @@ pub fn make_commit_msg(
         let commitment = off_chain::commit_to_vote(&list_of_scalar[i], &g_y, g_v);
 
         let commit_msg = CommitMessage {
-            reconstructed_key: g_y.to_bytes().to_vec(),
-            commitment,
+            election_id: TEST_ELECTION_ID,
+            reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+            commitments: vec![commitment],
         };
 
         list_of_reconstructed_keys.push(g_y);
@@ -280,6 +1463,7 @@ pub fn make_commit_msg(
 
 /// Generates vote and its one-in-two ZKP to create vote messages as binaries
 pub fn make_vote_msg(
+    accounts: Vec<AccountAddress>,
     list_of_scalar: Vec<Scalar>,
     list_of_voting_keys: Vec<ProjectivePoint>,
     list_of_reconstructed_keys: Vec<ProjectivePoint>,
@@ -292,11 +1476,14 @@ pub fn make_vote_msg(
             list_of_voting_keys[i],
             list_of_reconstructed_keys[i],
             list_of_scalar[i],
+            1,
+            &proof_context(TEST_CONTRACT_ADDRESS, TEST_ELECTION_ID, accounts[i]),
         );
 
         let vote_msg = VoteMessage {
-            vote: vote.to_bytes().to_vec(),
-            vote_zkp,
+            election_id: TEST_ELECTION_ID,
+            votes: vec![vote.to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(vote_zkp)],
         };
 
         fs::create_dir_all("../data/parameters/vote_msgs")?;
@@ -0,0 +1,238 @@
+//! Unit tests of the voter's keystore and of the eligibility bundles.
+
+use crate::eligibility::{self, EligibilityBundle, EligibilityError};
+use crate::keystore::{self, Choice, KeystoreBinding, KeystoreError, VoterSecrets};
+use crate::*;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+const PASSWORD: &[u8] = b"correct horse battery staple";
+
+const BINDING: KeystoreBinding = KeystoreBinding {
+    contract_address: ContractAddress {
+        index: 5124,
+        subindex: 0,
+    },
+    election_id: 3,
+    voter_index: 1,
+};
+
+/// A keystore file of the test, removed when dropped
+struct KeystoreFile(PathBuf);
+
+impl KeystoreFile {
+    fn new(name: &str) -> Self {
+        KeystoreFile(std::env::temp_dir().join(format!("{}-{}.json", name, std::process::id())))
+    }
+}
+
+impl Drop for KeystoreFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Change a plaintext field of the keystore file, as someone editing it would
+fn edit_keystore(file: &KeystoreFile, edit: impl FnOnce(&mut Value)) {
+    let mut json: Value = serde_json::from_str(&fs::read_to_string(&file.0).unwrap()).unwrap();
+    edit(&mut json);
+    fs::write(&file.0, json.to_string()).unwrap();
+}
+
+#[test]
+fn test_keystore_round_trip() {
+    let file = KeystoreFile::new("test_keystore_round_trip");
+    let (x, _) = create_votingkey_pair();
+
+    // Before the commit step there are no choices
+    let secrets = VoterSecrets {
+        x,
+        choices: Vec::new(),
+    };
+    keystore::save(&file.0, PASSWORD, &secrets, &BINDING).unwrap();
+    assert_eq!(keystore::read_binding(&file.0).unwrap(), BINDING);
+
+    let (loaded, binding) =
+        keystore::load(&file.0, PASSWORD, BINDING.contract_address, BINDING.election_id).unwrap();
+    assert_eq!(loaded.x, x);
+    assert!(loaded.choices.is_empty());
+    assert_eq!(binding, BINDING);
+
+    // A choice of every kind is kept
+    let choices = vec![
+        Choice::YesNo(true),
+        Choice::YesNo(false),
+        Choice::Candidate(2),
+        Choice::Scores(vec![3, 0, 2]),
+    ];
+    let secrets = VoterSecrets {
+        x,
+        choices: choices.clone(),
+    };
+    keystore::save(&file.0, PASSWORD, &secrets, &BINDING).unwrap();
+
+    let (loaded, _) =
+        keystore::load(&file.0, PASSWORD, BINDING.contract_address, BINDING.election_id).unwrap();
+    assert_eq!(loaded.x, x);
+    assert_eq!(loaded.choices, choices);
+}
+
+#[test]
+fn test_keystore_wrong_password() {
+    let file = KeystoreFile::new("test_keystore_wrong_password");
+    let secrets = VoterSecrets {
+        x: create_votingkey_pair().0,
+        choices: vec![Choice::YesNo(true)],
+    };
+    keystore::save(&file.0, PASSWORD, &secrets, &BINDING).unwrap();
+
+    assert!(matches!(
+        keystore::load(&file.0, b"wrong password", BINDING.contract_address, BINDING.election_id),
+        Err(KeystoreError::DecryptionFailed)
+    ));
+}
+
+#[test]
+fn test_keystore_other_election() {
+    let file = KeystoreFile::new("test_keystore_other_election");
+    let secrets = VoterSecrets {
+        x: create_votingkey_pair().0,
+        choices: Vec::new(),
+    };
+    keystore::save(&file.0, PASSWORD, &secrets, &BINDING).unwrap();
+
+    let other_contract = ContractAddress {
+        index: 5125,
+        subindex: 0,
+    };
+    assert!(matches!(
+        keystore::load(&file.0, PASSWORD, other_contract, BINDING.election_id),
+        Err(KeystoreError::OtherElection)
+    ));
+    assert!(matches!(
+        keystore::load(&file.0, PASSWORD, BINDING.contract_address, 4),
+        Err(KeystoreError::OtherElection)
+    ));
+
+    // Editing the election id to use the keystore for another election fails decryption, as it is authenticated
+    edit_keystore(&file, |json| json["election_id"] = Value::from(4));
+    assert!(matches!(
+        keystore::load(&file.0, PASSWORD, BINDING.contract_address, 4),
+        Err(KeystoreError::DecryptionFailed)
+    ));
+
+    // And the same for the contract address
+    edit_keystore(&file, |json| {
+        json["election_id"] = Value::from(BINDING.election_id);
+        json["contract_address"]["index"] = Value::from(other_contract.index);
+    });
+    assert!(matches!(
+        keystore::load(&file.0, PASSWORD, other_contract, BINDING.election_id),
+        Err(KeystoreError::DecryptionFailed)
+    ));
+
+    // And the voter index
+    edit_keystore(&file, |json| {
+        json["contract_address"]["index"] = Value::from(BINDING.contract_address.index);
+        json["voter_index"] = Value::from(2);
+    });
+    assert!(matches!(
+        keystore::load(&file.0, PASSWORD, BINDING.contract_address, BINDING.election_id),
+        Err(KeystoreError::DecryptionFailed)
+    ));
+}
+
+/// Eligible accounts of the bundle tests, with their weights
+fn leaves() -> Vec<(AccountAddress, u32)> {
+    vec![
+        (AccountAddress([1u8; 32]), 1),
+        (AccountAddress([2u8; 32]), 2),
+        (AccountAddress([3u8; 32]), 1),
+        (AccountAddress([4u8; 32]), 3),
+    ]
+}
+
+#[test]
+fn test_bundle_validate() {
+    let leaves = leaves();
+    let bundles =
+        eligibility::create_bundles(&leaves, BINDING.contract_address, BINDING.election_id).unwrap();
+    assert_eq!(bundles.len(), leaves.len());
+
+    for ((account, weight), bundle) in leaves.iter().zip(bundles.iter()) {
+        assert_eq!(bundle.merkle_proof.weight, *weight);
+        assert!(bundle
+            .validate(account, BINDING.contract_address, BINDING.election_id)
+            .is_ok());
+    }
+
+    let bundle = &bundles[1];
+    let account = &leaves[1].0;
+    let other_contract = ContractAddress {
+        index: 5125,
+        subindex: 0,
+    };
+    assert!(matches!(
+        bundle.validate(account, other_contract, BINDING.election_id),
+        Err(EligibilityError::OtherElection)
+    ));
+    assert!(matches!(
+        bundle.validate(account, BINDING.contract_address, BINDING.election_id + 1),
+        Err(EligibilityError::OtherElection)
+    ));
+    assert!(matches!(
+        bundle.validate(&leaves[0].0, BINDING.contract_address, BINDING.election_id),
+        Err(EligibilityError::OtherAccount)
+    ));
+
+    // The proof of another tree does not prove membership of this one
+    let mut other_leaves = leaves.clone();
+    other_leaves[3].1 = 4;
+    let other_bundle =
+        &eligibility::create_bundles(&other_leaves, BINDING.contract_address, BINDING.election_id)
+            .unwrap()[1];
+    let mut forged = EligibilityBundle::from_json(&bundle.to_json()).unwrap();
+    forged.merkle_root = other_bundle.merkle_root.clone();
+    assert!(matches!(
+        forged.validate(account, BINDING.contract_address, BINDING.election_id),
+        Err(EligibilityError::InvalidProof)
+    ));
+
+    forged.merkle_proof.index = forged.merkle_leaf_count;
+    assert!(matches!(
+        forged.validate(account, BINDING.contract_address, BINDING.election_id),
+        Err(EligibilityError::InvalidFormat)
+    ));
+}
+
+#[test]
+fn test_bundle_json() {
+    let bundles =
+        eligibility::create_bundles(&leaves(), BINDING.contract_address, BINDING.election_id).unwrap();
+
+    for bundle in bundles.iter() {
+        let json = bundle.to_json();
+        assert_eq!(json["election_id"], BINDING.election_id);
+        assert_eq!(json["contract_address"]["index"], BINDING.contract_address.index);
+        assert!(EligibilityBundle::from_json(&json).unwrap() == *bundle);
+
+        // The binary format holds the same
+        assert!(from_bytes::<EligibilityBundle>(&to_bytes(bundle)).unwrap() == *bundle);
+    }
+
+    // A bundle without an election id is not valid
+    let mut json = bundles[0].to_json();
+    json.as_object_mut().unwrap().remove("election_id");
+    assert!(matches!(
+        EligibilityBundle::from_json(&json),
+        Err(EligibilityError::InvalidFormat)
+    ));
+
+    let mut json = bundles[0].to_json();
+    json["merkle_proof"]["leaf"] = Value::from("not hex");
+    assert!(matches!(
+        EligibilityBundle::from_json(&json),
+        Err(EligibilityError::InvalidFormat)
+    ));
+}
@@ -2,9 +2,11 @@
 
 use concordium_std::*;
 use group::GroupEncoding;
+use k256::elliptic_curve::bigint::U512;
+use k256::elliptic_curve::ops::Reduce;
 use k256::elliptic_curve::{PublicKey, ScalarCore, SecretKey};
 use k256::{ProjectivePoint, Scalar, Secp256k1};
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha512};
 
 #[derive(Serialize, SchemaType, Default, PartialEq, Clone)]
 pub struct OneInTwoZKP {
@@ -49,34 +51,131 @@ impl OneInTwoZKP {
     }
 
     /// Extract the Scalars of the proof: (r1, r2, d1, d2)
-    pub fn extract_scalars(&self) -> (Scalar, Scalar, Scalar, Scalar) {
-        (
-            convert_vec_to_scalar(&self.r1),
-            convert_vec_to_scalar(&self.r2),
-            convert_vec_to_scalar(&self.d1),
-            convert_vec_to_scalar(&self.d2),
-        )
+    pub fn extract_scalars(&self) -> Result<(Scalar, Scalar, Scalar, Scalar), UtilError> {
+        Ok((
+            try_convert_vec_to_scalar(&self.r1)?,
+            try_convert_vec_to_scalar(&self.r2)?,
+            try_convert_vec_to_scalar(&self.d1)?,
+            try_convert_vec_to_scalar(&self.d2)?,
+        ))
     }
 
     /// Extract the Points of the proof: (x, y, a1, b1, a2, b2)
     pub fn extract_points(
         &self,
-    ) -> (
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-        ProjectivePoint,
-    ) {
+    ) -> Result<
+        (
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+            ProjectivePoint,
+        ),
+        UtilError,
+    > {
+        Ok((
+            try_convert_vec_to_point(&self.x)?,
+            try_convert_vec_to_point(&self.y)?,
+            try_convert_vec_to_point(&self.a1)?,
+            try_convert_vec_to_point(&self.b1)?,
+            try_convert_vec_to_point(&self.a2)?,
+            try_convert_vec_to_point(&self.b2)?,
+        ))
+    }
+}
+
+#[derive(Serialize, SchemaType, Default, PartialEq, Clone)]
+pub struct OneInKZKP {
+    r: Vec<Vec<u8>>,
+    d: Vec<Vec<u8>>,
+    x: Vec<u8>,
+    y: Vec<u8>,
+    a: Vec<Vec<u8>>,
+    b: Vec<Vec<u8>>,
+}
+
+impl OneInKZKP {
+    /// Create a new OneInKZKP, with one (r, d, a, b) entry for each of the k options
+    pub fn new(
+        r: Vec<Scalar>,
+        d: Vec<Scalar>,
+        x: ProjectivePoint,
+        y: ProjectivePoint,
+        a: Vec<ProjectivePoint>,
+        b: Vec<ProjectivePoint>,
+    ) -> Self {
+        Self {
+            r: r.iter().map(|s| s.to_bytes().to_vec()).collect(),
+            d: d.iter().map(|s| s.to_bytes().to_vec()).collect(),
+            x: x.to_bytes().to_vec(),
+            y: y.to_bytes().to_vec(),
+            a: a.iter().map(|p| p.to_bytes().to_vec()).collect(),
+            b: b.iter().map(|p| p.to_bytes().to_vec()).collect(),
+        }
+    }
+
+    /// Extract the Scalars of the proof: (r, d)
+    pub fn extract_scalars(&self) -> Result<(Vec<Scalar>, Vec<Scalar>), UtilError> {
+        Ok((
+            self.r
+                .iter()
+                .map(|r_i| try_convert_vec_to_scalar(r_i))
+                .collect::<Result<_, _>>()?,
+            self.d
+                .iter()
+                .map(|d_i| try_convert_vec_to_scalar(d_i))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    /// Extract the Points of the proof: (x, y, a, b)
+    pub fn extract_points(
+        &self,
+    ) -> Result<
         (
-            convert_vec_to_point(&self.x),
-            convert_vec_to_point(&self.y),
-            convert_vec_to_point(&self.a1),
-            convert_vec_to_point(&self.b1),
-            convert_vec_to_point(&self.a2),
-            convert_vec_to_point(&self.b2),
-        )
+            ProjectivePoint,
+            ProjectivePoint,
+            Vec<ProjectivePoint>,
+            Vec<ProjectivePoint>,
+        ),
+        UtilError,
+    > {
+        Ok((
+            try_convert_vec_to_point(&self.x)?,
+            try_convert_vec_to_point(&self.y)?,
+            self.a
+                .iter()
+                .map(|a_i| try_convert_vec_to_point(a_i))
+                .collect::<Result<_, _>>()?,
+            self.b
+                .iter()
+                .map(|b_i| try_convert_vec_to_point(b_i))
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+}
+
+#[derive(Serialize, SchemaType, Default, PartialEq, Clone)]
+pub struct RangeZKP {
+    options: Vec<OneInKZKP>,
+    total: OneInKZKP,
+}
+
+impl RangeZKP {
+    /// Create a new RangeZKP, with a one-in-k proof of the score of each option and one of the total score
+    pub fn new(options: Vec<OneInKZKP>, total: OneInKZKP) -> Self {
+        Self { options, total }
+    }
+
+    /// The proofs of the score of each option, each over an option key g^r_j and an option vote g^(y*r_j) g^(s_j)
+    pub fn options(&self) -> &[OneInKZKP] {
+        &self.options
+    }
+
+    /// The proof of the total score, over the sum of the option keys and the sum of the option votes
+    pub fn total(&self) -> &OneInKZKP {
+        &self.total
     }
 }
 
@@ -96,11 +195,40 @@ impl SchnorrProof {
     }
 
     /// Extract the primitives of the proof: (g_w, r)
-    pub fn extract_primitives(&self) -> (ProjectivePoint, Scalar) {
-        (
-            convert_vec_to_point(&self.g_w),
-            convert_vec_to_scalar(&self.r),
-        )
+    pub fn extract_primitives(&self) -> Result<(ProjectivePoint, Scalar), UtilError> {
+        Ok((
+            try_convert_vec_to_point(&self.g_w)?,
+            try_convert_vec_to_scalar(&self.r)?,
+        ))
+    }
+}
+
+#[derive(Serialize, SchemaType, PartialEq, Default, Clone)]
+pub struct ChaumPedersenProof {
+    pub g_w: Vec<u8>,
+    pub h_w: Vec<u8>,
+    pub r: Vec<u8>,
+}
+
+impl ChaumPedersenProof {
+    /// Create a new ChaumPedersenProof
+    pub fn new(g_w: ProjectivePoint, h_w: ProjectivePoint, r: Scalar) -> Self {
+        Self {
+            g_w: g_w.to_bytes().to_vec(),
+            h_w: h_w.to_bytes().to_vec(),
+            r: r.to_bytes().to_vec(),
+        }
+    }
+
+    /// Extract the primitives of the proof: (g_w, h_w, r)
+    pub fn extract_primitives(
+        &self,
+    ) -> Result<(ProjectivePoint, ProjectivePoint, Scalar), UtilError> {
+        Ok((
+            try_convert_vec_to_point(&self.g_w)?,
+            try_convert_vec_to_point(&self.h_w)?,
+            try_convert_vec_to_scalar(&self.r)?,
+        ))
     }
 }
 
@@ -109,31 +237,193 @@ pub struct MerkleProof {
     pub proof: Vec<u8>,
     pub leaf: [u8; 32],
     pub index: i32,
+    pub weight: u32, // weight of the voter, hashed into the leaf along with the account
+}
+
+/// Errors of the fallible utility functions, such that malformed input can be rejected instead of trapping
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UtilError {
+    // Bytes are not a valid non-zero scalar
+    InvalidScalar,
+    // Bytes are not a valid point on the curve
+    InvalidPoint,
+    // Expected value was not there
+    MissingValue,
 }
 
 /// Utility function to convert Vec -> Scalar
 pub fn convert_vec_to_scalar(vec: &Vec<u8>) -> Scalar {
-    let scalar_option = SecretKey::<Secp256k1>::from_be_bytes(vec).ok();
+    unwrap_abort(try_convert_vec_to_scalar(vec).ok())
+}
 
-    let scalar = unwrap_abort(scalar_option);
+/// Utility function to convert Vec -> Scalar, without trapping on invalid bytes
+pub fn try_convert_vec_to_scalar(vec: &[u8]) -> Result<Scalar, UtilError> {
+    let scalar =
+        SecretKey::<Secp256k1>::from_be_bytes(vec).map_err(|_| UtilError::InvalidScalar)?;
 
-    return From::<&'_ ScalarCore<Secp256k1>>::from(SecretKey::as_scalar_core(&scalar));
+    Ok(From::<&'_ ScalarCore<Secp256k1>>::from(
+        SecretKey::as_scalar_core(&scalar),
+    ))
 }
 
 /// Utility function to convert Vec -> ProjectivePoint
 pub fn convert_vec_to_point(vec: &Vec<u8>) -> ProjectivePoint {
-    let point_option = PublicKey::<Secp256k1>::from_sec1_bytes(vec).ok();
+    unwrap_abort(try_convert_vec_to_point(vec).ok())
+}
+
+/// Utility function to convert Vec -> ProjectivePoint, without trapping on invalid bytes
+pub fn try_convert_vec_to_point(vec: &[u8]) -> Result<ProjectivePoint, UtilError> {
+    let point =
+        PublicKey::<Secp256k1>::from_sec1_bytes(vec).map_err(|_| UtilError::InvalidPoint)?;
 
-    let point = unwrap_abort(point_option);
+    Ok(PublicKey::to_projective(&point))
+}
 
-    return PublicKey::to_projective(&point);
+/// Utility function to convert Vec -> ProjectivePoint, also accepting the identity point (e.g. an empty sum of points)
+pub fn convert_vec_to_point_or_identity(vec: &[u8]) -> ProjectivePoint {
+    unwrap_abort(try_convert_vec_to_point_or_identity(vec).ok())
 }
 
-/// Utility function to go from Vec -> Hash -> Scalar
+/// Utility function to convert Vec -> ProjectivePoint or the identity point, without trapping on invalid bytes
+pub fn try_convert_vec_to_point_or_identity(vec: &[u8]) -> Result<ProjectivePoint, UtilError> {
+    if vec.len() != 33 {
+        return Err(UtilError::InvalidPoint);
+    }
+    let point_option: Option<ProjectivePoint> =
+        ProjectivePoint::from_bytes(k256::CompressedPoint::from_slice(vec)).into();
+
+    point_option.ok_or(UtilError::InvalidPoint)
+}
+
+/// Utility function to go from Vec -> Hash -> Scalar.
+/// The 512 bit SHA-512 digest is reduced modulo the group order, which always succeeds and leaves a negligible bias
 pub fn hash_to_scalar(bytes_to_hash: Vec<u8>) -> Scalar {
-    let hash_value = Sha256::digest(bytes_to_hash);
+    let hash_value = Sha512::digest(bytes_to_hash);
+
+    <Scalar as Reduce<U512>>::from_be_bytes_reduced(hash_value)
+}
+
+/// The context a ZKP is bound to, such that it can not be replayed on another contract instance, in another election or by another account
+#[derive(Serialize, SchemaType, Clone, Copy, PartialEq, Debug)]
+pub struct ProofContext {
+    pub contract_address: ContractAddress,
+    pub election_id: u64,
+    pub sender: AccountAddress,
+}
+
+/// Fiat-Shamir transcript. The challenge of a proof is the hash of a domain separating label,
+/// the proof context and the canonical encoding of every point in the statement and commitments (in order)
+pub struct Transcript {
+    bytes: Vec<u8>,
+}
+
+impl Transcript {
+    /// Start a new transcript for the proof with the given label (e.g. "schnorr") in the given context
+    pub fn new(label: &str, context: &ProofContext) -> Self {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(label.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(label.as_bytes());
+        bytes.extend_from_slice(&to_bytes(context));
+        Self { bytes }
+    }
+
+    /// Absorb the 33 byte compressed SEC1 encoding of a point
+    pub fn append_point(&mut self, point: &ProjectivePoint) {
+        self.bytes.extend_from_slice(&point.to_bytes());
+    }
+
+    /// Absorb a u64, e.g. a candidate encoding
+    pub fn append_u64(&mut self, value: u64) {
+        self.bytes.extend_from_slice(&value.to_be_bytes());
+    }
+
+    /// Derive the challenge from everything absorbed so far
+    pub fn challenge(self) -> Scalar {
+        hash_to_scalar(self.bytes)
+    }
+}
+
+/// Compute the exponents m^j used to encode a vote for candidate j as g^(m^j).
+/// The base m must be larger than the number of voters, so the tally can be decoded per candidate.
+/// Returns None if the largest possible tally does not fit in a u64
+pub fn candidate_encodings(number_of_options: usize, base: u64) -> Option<Vec<u64>> {
+    let mut encodings = Vec::new();
+    let mut encoding: u64 = 1;
+    for _ in 0..number_of_options {
+        encodings.push(encoding);
+        encoding = encoding.checked_mul(base)?;
+    }
+    Some(encodings)
+}
+
+/// The values 0, 1, ..., max_score a score can take, used as the encodings of the one-in-k proofs of a range proof
+pub fn score_encodings(max_score: u64) -> Vec<u64> {
+    (0..=max_score).collect()
+}
 
-    return convert_vec_to_scalar(&hash_value.to_vec());
+/// Scale the encodings of a vote by the voter's weight w, as a voter of weight w votes g^(w*m^j) instead of g^(m^j)
+pub fn weighted_encodings(encodings: &[u64], weight: u64) -> Vec<u64> {
+    encodings.iter().map(|encoding| encoding * weight).collect()
+}
+
+/// Decode a tally of candidate votes (sum of m^j over all votes) into a count per candidate, by reading it in base m
+pub fn decode_candidate_tally(tally: u64, number_of_options: usize, base: u64) -> Vec<i32> {
+    let mut remaining = tally;
+    let mut counts = Vec::new();
+    for _ in 0..number_of_options {
+        counts.push((remaining % base) as i32);
+        remaining /= base;
+    }
+    counts
+}
+/// Encode a count per candidate into the tally (sum of m^j over all votes) it decodes from.
+/// Returns None if a count is negative or not smaller than the base, or the tally does not fit in a u64
+pub fn encode_candidate_tally(counts: &[i32], base: u64) -> Option<u64> {
+    let mut tally: u64 = 0;
+    for count in counts.iter().rev() {
+        if *count < 0 || *count as u64 >= base {
+            return None;
+        }
+        tally = tally.checked_mul(base)?.checked_add(*count as u64)?;
+    }
+    Some(tally)
+}
+
+/// Largest number of baby steps `discrete_log` stores, which limits its bound to below 2^20 such that the search fits in the energy of a contract call.
+/// Larger tallies must be claimed along with the result, which the contract then only verifies (see `verify_tally` of the voting contract)
+pub const MAX_BABY_STEPS: u64 = 1 << 10;
+
+/// Find t such that g^t = point, with 0 <= t <= bound, using baby-step giant-step.
+/// Takes about 2 * sqrt(bound) point additions, and returns None if no such t exists or the bound needs more than MAX_BABY_STEPS
+pub fn discrete_log(point: ProjectivePoint, bound: u64) -> Option<u64> {
+    // Number of baby steps m, the smallest such that m * m > bound
+    let m = bound.isqrt() + 1;
+    if m > MAX_BABY_STEPS {
+        return None;
+    }
+
+    // Baby steps: store g^j for 0 <= j < m
+    let mut baby_steps = collections::BTreeMap::new();
+    let mut current = ProjectivePoint::IDENTITY;
+    for j in 0..m {
+        baby_steps.insert(current.to_bytes().to_vec(), j);
+        current += ProjectivePoint::GENERATOR;
+    }
+
+    // Giant steps: look for point * g^(-im) among the baby steps, then t = im + j
+    let giant_step = ProjectivePoint::GENERATOR * Scalar::from(m);
+    let mut current = point;
+    for i in 0..m {
+        if let Some(j) = baby_steps.get(&current.to_bytes().to_vec()) {
+            let t = i * m + j;
+            if t <= bound {
+                return Some(t);
+            }
+            return None;
+        }
+        current -= giant_step;
+    }
+    None
 }
 
 /// Utility to better unwrap a value in WASM
@@ -145,25 +435,39 @@ pub fn unwrap_abort<T>(o: Option<T>) -> T {
     }
 }
 
+/// Utility to unwrap a value without trapping, e.g. a value looked up from input
+#[inline]
+pub fn try_unwrap<T>(o: Option<T>) -> Result<T, UtilError> {
+    o.ok_or(UtilError::MissingValue)
+}
+
 /// Compute a voter's reconstructed key (g^y) from their voting key (g^x) and all other voting keys in a given vote
 /// Note: It's important that the list of keys is in the same order for all voters
 pub fn compute_reconstructed_key(
     keys: &Vec<ProjectivePoint>,
     index: i32,
 ) -> ProjectivePoint {
+    unwrap_abort(try_compute_reconstructed_key(keys, index).ok())
+}
+
+/// Compute a voter's reconstructed key (g^y), without trapping if the list of keys is empty
+pub fn try_compute_reconstructed_key(
+    keys: &[ProjectivePoint],
+    index: i32,
+) -> Result<ProjectivePoint, UtilError> {
     //Get our key's position in the list of voting keys
     let position = index as usize;
 
-    let mut after_points = unwrap_abort(keys.get(keys.len() - 1)).clone();
+    let mut after_points = *try_unwrap(keys.last())?;
     // Fill after points with every key except the last and return if you are the first
     if position == 0 {
         for i in 1..keys.len() - 1 {
-            after_points = after_points + unwrap_abort(keys.get(i)).clone();
+            after_points += try_unwrap(keys.get(i))?;
         }
-        return -after_points;
+        return Ok(-after_points);
     }
 
-    let mut before_points = unwrap_abort(keys.get(0)).clone();
+    let mut before_points = *try_unwrap(keys.first())?;
     for j in 1..keys.len() - 1 {
         // Skip your own key
         if j == position {
@@ -172,17 +476,45 @@ pub fn compute_reconstructed_key(
 
         // add to before points when j is less than your position
         if j < position {
-            before_points = before_points + unwrap_abort(keys.get(j)).clone();
+            before_points += try_unwrap(keys.get(j))?;
         }
 
         // add to after points when j is greater than your position
         if j > position {
-            after_points += unwrap_abort(keys.get(j)).clone();
+            after_points += try_unwrap(keys.get(j))?;
         }
     }
     // If you are the last just return before points
     if position == keys.len() - 1 {
-        return before_points;
+        return Ok(before_points);
+    }
+    Ok(before_points - after_points)
+}
+
+/// Compute the base a voter (who voted) uses in the recovery round, given the indices of the voters who did not vote.
+/// It is the reconstructed key computed over the missing voters only: the product of the keys before index divided by the keys after it
+pub fn compute_recovery_base(
+    keys: &[ProjectivePoint],
+    index: i32,
+    missing_voters: &[i32],
+) -> ProjectivePoint {
+    unwrap_abort(try_compute_recovery_base(keys, index, missing_voters).ok())
+}
+
+/// Compute the recovery base (h) of a voter, without trapping if a missing voter has no key in the list
+pub fn try_compute_recovery_base(
+    keys: &[ProjectivePoint],
+    index: i32,
+    missing_voters: &[i32],
+) -> Result<ProjectivePoint, UtilError> {
+    let mut recovery_base = ProjectivePoint::IDENTITY;
+    for missing in missing_voters {
+        let key = try_unwrap(keys.get(*missing as usize))?;
+        if *missing < index {
+            recovery_base += key;
+        } else if *missing > index {
+            recovery_base -= key;
+        }
     }
-    return before_points - after_points;
+    Ok(recovery_base)
 }
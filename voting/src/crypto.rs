@@ -1,89 +1,241 @@
 //! Rust file containing the required on-chain crypto functions needed in the *voting* contract.
 //!
-//! These are verifications of ZKPs, checking vote commitments and brute forcing the final tally.
+//! These are verifications of ZKPs, checking vote commitments and computing the final tally.
 
 use concordium_std::*;
 use group::GroupEncoding;
-use k256::ProjectivePoint;
+use k256::{ProjectivePoint, Scalar};
 use rs_merkle::algorithms::Sha256 as merkle_sha256;
 use rs_merkle::*;
 use sha2::{Digest, Sha256};
-use util::{hash_to_scalar, unwrap_abort, MerkleProof, OneInTwoZKP, SchnorrProof};
+use util::{
+    ChaumPedersenProof, MerkleProof, OneInKZKP, OneInTwoZKP, ProofContext, RangeZKP,
+    SchnorrProof, Transcript, UtilError,
+};
 
 /// Check Schnorr ZKP: g^w = g^r * g^xz
-pub fn verify_schnorr_zkp(g_x: ProjectivePoint, schnorr: util::SchnorrProof) -> bool {
-    let (g_w, r) = SchnorrProof::extract_primitives(&schnorr);
+pub fn verify_schnorr_zkp(
+    g_x: ProjectivePoint,
+    schnorr: util::SchnorrProof,
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    let (g_w, r) = SchnorrProof::extract_primitives(&schnorr)?;
 
-    // Create hash z = H(g, g^w, g^x)
-    let value_to_hash = ProjectivePoint::GENERATOR + g_w + g_x;
-    let z = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // Create hash z = H(context, g, g^w, g^x)
+    let mut transcript = Transcript::new("schnorr", context);
+    transcript.append_point(&ProjectivePoint::GENERATOR);
+    transcript.append_point(&g_w);
+    transcript.append_point(&g_x);
+    let z = transcript.challenge();
 
     let g_r = ProjectivePoint::GENERATOR * r;
     let g_x_z = g_x * z;
     let g_rg_x_z: ProjectivePoint = g_x_z + g_r;
 
-    if g_rg_x_z == g_w {
-        return true;
-    }
-    false
+    Ok(g_rg_x_z == g_w)
+}
+
+/// Check veto ZKP, a Schnorr ZKP in base g^y of knowing c such that the vote is g^(c*y): (g^y)^w = (g^y)^r * vote^z.
+/// The voter knows c whether it is x (no veto) or random (veto), so the proof does not tell which
+pub fn verify_veto_zkp(
+    g_y: ProjectivePoint,
+    vote: ProjectivePoint,
+    proof: util::SchnorrProof,
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    let (g_y_w, r) = SchnorrProof::extract_primitives(&proof)?;
+
+    // Create hash z = H(context, g^y, (g^y)^w, vote)
+    let mut transcript = Transcript::new("veto", context);
+    transcript.append_point(&g_y);
+    transcript.append_point(&g_y_w);
+    transcript.append_point(&vote);
+    let z = transcript.challenge();
+
+    Ok((g_y * r) + (vote * z) == g_y_w)
 }
 
-/// Check one-in-two ZKP: check v = 1 or v = 0 without knowing which
-pub fn verify_one_in_two_zkp(zkp: util::OneInTwoZKP, g_y: ProjectivePoint) -> bool {
-    let (r1, r2, d1, d2) = OneInTwoZKP::extract_scalars(&zkp);
+/// Check one-in-two ZKP: check v = w or v = 0 without knowing which, for a voter of weight w
+pub fn verify_one_in_two_zkp(
+    zkp: util::OneInTwoZKP,
+    g_y: ProjectivePoint,
+    weight: u64,
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    let (r1, r2, d1, d2) = OneInTwoZKP::extract_scalars(&zkp)?;
 
-    let (x, y, a1, b1, a2, b2) = OneInTwoZKP::extract_points(&zkp);
+    let (x, y, a1, b1, a2, b2) = OneInTwoZKP::extract_points(&zkp)?;
 
-    // c = H(g^x, y, a1, b1, a2, b2)
-    let value_to_hash = x.clone() + y.clone() + a1.clone() + b1.clone() + a2.clone() + b2.clone();
-    let c = hash_to_scalar(value_to_hash.to_bytes().to_vec());
+    // c = H(context, w, g^y, g^x, y, a1, b1, a2, b2)
+    let mut transcript = Transcript::new("one-in-two", context);
+    transcript.append_u64(weight);
+    for point in [g_y, x, y, a1, b1, a2, b2].iter() {
+        transcript.append_point(point);
+    }
+    let c = transcript.challenge();
 
-    if c != d1.clone() + d2.clone() {
-        return false;
+    if c != d1 + d2 {
+        return Ok(false);
     };
-    if a1 != (ProjectivePoint::GENERATOR * r1.clone()) + (x.clone() * d1.clone()) {
-        return false;
+    if a1 != (ProjectivePoint::GENERATOR * r1) + (x * d1) {
+        return Ok(false);
     }
-    if b1 != (g_y.clone() * r1) + (y.clone() * d1) {
-        return false;
+    if b1 != (g_y * r1) + (y * d1) {
+        return Ok(false);
     }
-    if a2 != (ProjectivePoint::GENERATOR * r2.clone()) + (x * d2.clone()) {
-        return false;
+    if a2 != (ProjectivePoint::GENERATOR * r2) + (x * d2) {
+        return Ok(false);
     }
-    if b2 != (g_y * r2) + ((y - ProjectivePoint::GENERATOR) * d2) {
-        return false;
+    if b2 != (g_y * r2) + ((y - ProjectivePoint::GENERATOR * Scalar::from(weight)) * d2) {
+        return Ok(false);
     }
-    true
+    Ok(true)
 }
 
-/// Check commitment matches actual vote
-pub fn check_commitment(vote: ProjectivePoint, commitment: Vec<u8>) -> bool {
-    Sha256::digest(&vote.to_bytes().to_vec()).to_vec() == commitment
+/// Check one-in-k ZKP: check the vote is g^xy*g^(m^j) for exactly one candidate j without knowing which
+pub fn verify_one_in_k_zkp(
+    zkp: util::OneInKZKP,
+    g_y: ProjectivePoint,
+    encodings: &[u64],
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    let (r, d) = OneInKZKP::extract_scalars(&zkp)?;
+
+    let (x, y, a, b) = OneInKZKP::extract_points(&zkp)?;
+
+    // There must be exactly one branch of the proof per candidate
+    let k = encodings.len();
+    if r.len() != k || d.len() != k || a.len() != k || b.len() != k {
+        return Ok(false);
+    }
+
+    // c = H(context, m^0, ..., m^(k-1), g^y, g^x, y, a1, b1, ..., ak, bk)
+    let mut transcript = Transcript::new("one-in-k", context);
+    for encoding in encodings {
+        transcript.append_u64(*encoding);
+    }
+    transcript.append_point(&g_y);
+    transcript.append_point(&x);
+    transcript.append_point(&y);
+    for (a_i, b_i) in a.iter().zip(b.iter()) {
+        transcript.append_point(a_i);
+        transcript.append_point(b_i);
+    }
+    let c = transcript.challenge();
+
+    if c != d.iter().fold(Scalar::ZERO, |acc, d_i| acc + d_i) {
+        return Ok(false);
+    }
+
+    for (i, encoding) in encodings.iter().enumerate() {
+        let g_m = ProjectivePoint::GENERATOR * Scalar::from(*encoding);
+
+        if a[i] != (ProjectivePoint::GENERATOR * r[i]) + (x * d[i]) {
+            return Ok(false);
+        }
+        if b[i] != (g_y * r[i]) + ((y - g_m) * d[i]) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
-/// Brute force and tally yes votes on-chain
-pub fn brute_force_tally(votes: Vec<ProjectivePoint>) -> i32 {
-    // Set first vote as initial tally
-    let mut tally = unwrap_abort(votes.get(0)).clone();
+/// Check range ZKP of an approval or score ballot: the vote is g^xy*g^(w * sum of s_j*m^j) with 0 <= s_j <= max_score for every option j
+/// and sum of s_j <= budget, without knowing the scores, for a voter of weight w.
+/// Every option j has a key g^r_j and vote g^(y*r_j) g^(w*s_j) with a one-in-k proof that s_j is in 0..=max_score. The option keys and votes
+/// must add up to the voting key and the vote when weighted by m^j, and their plain sums have a one-in-k proof that the total is in 0..=budget
+#[allow(clippy::too_many_arguments)]
+pub fn verify_range_zkp(
+    zkp: RangeZKP,
+    g_x: ProjectivePoint,
+    g_y: ProjectivePoint,
+    vote: ProjectivePoint,
+    max_score: u64,
+    budget: u64,
+    encodings: &[u64],
+    weight: u64,
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    // There must be exactly one option proof per candidate
+    if zkp.options().len() != encodings.len() {
+        return Ok(false);
+    }
+
+    let scores = util::weighted_encodings(&util::score_encodings(max_score), weight);
+    let mut weighted_keys = ProjectivePoint::IDENTITY;
+    let mut weighted_votes = ProjectivePoint::IDENTITY;
+    let mut total_key = ProjectivePoint::IDENTITY;
+    let mut total_vote = ProjectivePoint::IDENTITY;
+    for (option_zkp, encoding) in zkp.options().iter().zip(encodings.iter()) {
+        if !verify_one_in_k_zkp(option_zkp.clone(), g_y, &scores, context)? {
+            return Ok(false);
+        }
+
+        let (option_key, option_vote, _, _) = OneInKZKP::extract_points(option_zkp)?;
+        weighted_keys += option_key * Scalar::from(*encoding);
+        weighted_votes += option_vote * Scalar::from(*encoding);
+        total_key += option_key;
+        total_vote += option_vote;
+    }
+
+    // The option votes must be a split of the vote under the voter's key, so the tally counts exactly these scores
+    if weighted_keys != g_x || weighted_votes != vote {
+        return Ok(false);
+    }
 
-    for i in 1..votes.len() {
-        // Add all the rest of the votes (curve points) to tally, e.g \prod g^xy*g^v (calculated differently due to additive curve)
-        tally = tally + unwrap_abort(votes.get(i));
+    let (x, y, _, _) = OneInKZKP::extract_points(zkp.total())?;
+    if x != total_key || y != total_vote {
+        return Ok(false);
     }
+    let totals = util::weighted_encodings(&util::score_encodings(budget), weight);
+    verify_one_in_k_zkp(zkp.total().clone(), g_y, &totals, context)
+}
 
-    let mut current_g = ProjectivePoint::IDENTITY;
-    let mut yes_votes = 0;
-    let pg = ProjectivePoint::GENERATOR;
+/// Check Chaum-Pedersen ZKP of equal discrete logs: log_g(g^x) = log_h(h^x), i.e. g^w = g^r * g^xc and h^w = h^r * h^xc
+pub fn verify_chaum_pedersen_zkp(
+    g_x: ProjectivePoint,
+    h: ProjectivePoint,
+    h_x: ProjectivePoint,
+    proof: util::ChaumPedersenProof,
+    context: &ProofContext,
+) -> Result<bool, UtilError> {
+    let (g_w, h_w, r) = ChaumPedersenProof::extract_primitives(&proof)?;
 
-    // Go through all votes and brute force number of yes votes
-    while current_g != tally {
-        yes_votes += 1;
-        current_g += &pg;
+    // Create hash c = H(context, g, h, g^x, h^x, g^w, h^w)
+    let mut transcript = Transcript::new("chaum-pedersen", context);
+    for point in [ProjectivePoint::GENERATOR, h, g_x, h_x, g_w, h_w].iter() {
+        transcript.append_point(point);
+    }
+    let c = transcript.challenge();
+
+    if g_w != (ProjectivePoint::GENERATOR * r) + (g_x * c) {
+        return Ok(false);
+    }
+    if h_w != (h * r) + (h_x * c) {
+        return Ok(false);
     }
-    yes_votes
+    Ok(true)
 }
 
-/// Checks merkle proof-of-membership and that the hash of the sender matches the leaf that is proved
+/// Check commitment matches actual vote
+pub fn check_commitment(vote: ProjectivePoint, commitment: Vec<u8>) -> bool {
+    Sha256::digest(vote.to_bytes()).to_vec() == commitment
+}
+
+/// Tally the votes on-chain, i.e. find t <= bound such that g^t equals the sum of all votes.
+/// For yes/no votes t is the number of yes votes, for candidate votes it is the packed tally (of the counts or score sums).
+/// Returns None if the sum is not in range
+pub fn compute_tally(vote_aggregate: ProjectivePoint, bound: u64) -> Option<u64> {
+    // Find the number of yes votes (or packed tally) with baby-step giant-step
+    util::discrete_log(vote_aggregate, bound)
+}
+
+/// Check a claimed tally on-chain, i.e. that the sum of all votes equals g^tally
+pub fn verify_tally(vote_aggregate: ProjectivePoint, tally: u64) -> bool {
+    vote_aggregate == ProjectivePoint::GENERATOR * Scalar::from(tally)
+}
+
+/// Checks merkle proof-of-membership and that the hash of the sender and the weight in the proof matches the leaf that is proved
 pub fn verify_merkle_proof(
     merkle_root: &String,
     merkle_leaf_count: i32,
@@ -97,8 +249,11 @@ pub fn verify_merkle_proof(
         Err(err) => bail!(err),
     };
 
-    let proof =
-        unwrap_abort(rs_merkle::MerkleProof::<merkle_sha256>::from_bytes(&merkle_proof.proof).ok());
+    // A malformed proof does not prove membership
+    let proof = match rs_merkle::MerkleProof::<merkle_sha256>::from_bytes(&merkle_proof.proof) {
+        Ok(p) => p,
+        Err(_) => return Ok(false),
+    };
 
     if proof.verify(
         root_as_slice,
@@ -106,7 +261,7 @@ pub fn verify_merkle_proof(
         &[merkle_proof.leaf],
         merkle_leaf_count as usize,
     ) {
-        let account_hash = merkle_sha256::hash(&to_bytes(sender));
+        let account_hash = merkle_sha256::hash(&to_bytes(&(*sender, merkle_proof.weight)));
 
         return Ok(account_hash == merkle_proof.leaf);
     }
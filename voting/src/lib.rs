@@ -2,12 +2,27 @@
 //!
 //! It implements the Open Vote Network protocol using the elliptic curve *secp256k1*.
 //! The protocol allows for decentralized privacy-preserving online voting, as defined here: http://homepages.cs.ncl.ac.uk/feng.hao/files/OpenVote_IET.pdf
+//!
+//! An instance of the contract hosts any number of elections, each created with `create_election` and identified by the id it returns.
+//! Elections are fully isolated: every entrypoint takes the election id, and the deposits, phases, keys and ZKPs of one election never affect another.
+//!
+//! The account that creates an election is its organizer. The organizer can cancel the election during the registration phase,
+//! extend the timeouts of phases that have not ended and sweep leftover CCD once the election has ended. The config can not be changed otherwise,
+//! so the organizer can never change the eligible voters, the questions, the options, the thresholds or anything else the result depends on.
+//!
+//! A ballot answers every question of the election at once. Voters register, commit, vote and recover once, with one message holding a key,
+//! commitment, vote or recovery key per question. Each question has its own voting key (g^x), as two votes encrypted with the same key would
+//! reveal g^(v - v') to anyone dividing them.
+//!
+//! An election can instead be an anonymous veto network (AV-net, by Hao and Zieliński), with the same keys. A voter publishes g^(x*y)
+//! to consent or g^(r*y) with a random r to veto, and the votes only cancel out to the identity if nobody vetoed.
 
 use concordium_std::*;
 use group::GroupEncoding;
-use k256::elliptic_curve::PublicKey;
-use k256::Secp256k1;
-use util::{convert_vec_to_point, OneInTwoZKP, SchnorrProof};
+use k256::ProjectivePoint;
+use util::{
+    convert_vec_to_point, ChaumPedersenProof, OneInKZKP, OneInTwoZKP, RangeZKP, SchnorrProof,
+};
 
 pub mod crypto;
 pub mod tests;
@@ -15,66 +30,239 @@ pub mod types;
 
 // Contract structs
 
-#[derive(Serialize, SchemaType)]
+#[derive(Serialize, SchemaType, Clone)]
 pub struct VoteConfig {
     merkle_root: String,
     merkle_leaf_count: i32,
-    voting_question: String,
+    total_weight: i32, // sum of the weights in the merkle tree, which is merkle_leaf_count if every voter has weight 1
+    voting_questions: Vec<String>, // every question has the same options
+    voting_options: Vec<String>, // empty for a yes/no vote, otherwise the list of candidates
+    ballot_type: types::BallotType, // single for a yes/no vote, otherwise how the candidates are voted for
     deposit: Amount,
     registration_timeout: types::RegistrationTimeout,
     commit_timeout: types::CommitTimeout,
     vote_timeout: types::VoteTimeout,
+    recovery_timeout: types::RecoveryTimeout,
+    min_registrants: i32,     // registrations needed to leave the registration phase at its timeout
+    quorum: types::Fraction,  // fraction of total_weight that must vote for the result to count
+    pass_rule: types::PassRule,
+    slashing_policy: types::SlashingPolicy,
+}
+
+impl VoteConfig {
+    fn number_of_questions(&self) -> usize {
+        self.voting_questions.len()
+    }
+
+    /// Bounds (max_score, budget) of the scores of an approval or score ballot. None for single votes
+    fn score_range(&self) -> Option<(u64, u64)> {
+        match &self.ballot_type {
+            types::BallotType::Single | types::BallotType::Veto => None,
+            types::BallotType::Approval(max_approvals) => Some((1, *max_approvals as u64)),
+            types::BallotType::Score(rule) => Some((rule.max_score as u64, rule.budget as u64)),
+        }
+    }
+
+    /// Highest score a voter can give a candidate, which is 1 unless it is a score ballot
+    fn max_score(&self) -> u64 {
+        self.score_range().map_or(1, |(max_score, _)| max_score)
+    }
+
+    /// Base m of the packed tally, larger than the weighted count or score sum any candidate can get
+    fn tally_base(&self) -> u64 {
+        self.total_weight as u64 * self.max_score() + 1
+    }
+
+    /// Exponents m^j used to encode a vote for (or the score of) each candidate. None for yes/no votes
+    fn candidate_encodings(&self) -> Option<Vec<u64>> {
+        if self.voting_options.is_empty() {
+            return None;
+        }
+        util::candidate_encodings(self.voting_options.len(), self.tally_base())
+    }
+
+    fn is_veto(&self) -> bool {
+        self.ballot_type == types::BallotType::Veto
+    }
+
+    /// Whether enough of the eligible weight voted for the result to count
+    fn quorum_met(&self, voted_weight: i32) -> bool {
+        voted_weight as u64 * (self.quorum.denominator as u64)
+            >= (self.quorum.numerator as u64) * (self.total_weight as u64)
+    }
+
+    /// Outcome of the vote given the weighted counts. The pass rule is applied to the yes votes, or to the leading candidate.
+    /// The score sum of a candidate is compared to the highest score every voter could give it
+    fn outcome(&self, counts: &[i32], voted_weight: i32) -> types::VoteOutcome {
+        if !self.quorum_met(voted_weight) {
+            return types::VoteOutcome::QuorumNotMet;
+        }
+        let votes = voted_weight as u64;
+
+        let in_favour = match self.voting_options.is_empty() {
+            true => counts.first(),
+            false => counts.iter().max(),
+        };
+        let in_favour = *in_favour.unwrap_or(&0) as u64;
+        let votes = votes * self.max_score();
+
+        let passed = match &self.pass_rule {
+            types::PassRule::Majority => in_favour * 2 > votes,
+            types::PassRule::Supermajority(fraction) => {
+                in_favour * (fraction.denominator as u64) >= (fraction.numerator as u64) * votes
+            }
+            types::PassRule::Unanimity => in_favour == votes,
+        };
+
+        if passed && votes > 0 {
+            types::VoteOutcome::Passed
+        } else {
+            types::VoteOutcome::Rejected
+        }
+    }
+
+    /// Outcome of a veto election, which passes if atleast one voted and nobody vetoed
+    fn veto_outcome(&self, vetoed: bool, voted_weight: i32) -> types::VoteOutcome {
+        if !self.quorum_met(voted_weight) {
+            types::VoteOutcome::QuorumNotMet
+        } else if vetoed {
+            types::VoteOutcome::Vetoed
+        } else if voted_weight > 0 {
+            types::VoteOutcome::Passed
+        } else {
+            types::VoteOutcome::Rejected
+        }
+    }
 }
 
 #[derive(Serialize, SchemaType)]
 pub struct RegisterMessage {
-    pub voting_key: Vec<u8>,          // g^x
-    pub voting_key_zkp: SchnorrProof, // zkp for x
+    pub election_id: u64,
+    pub voting_keys: Vec<Vec<u8>>,          // g^x for each question
+    pub voting_key_zkps: Vec<SchnorrProof>, // zkp for each x
     pub merkle_proof: util::MerkleProof,
 }
 
 #[derive(Serialize, SchemaType)]
 pub struct CommitMessage {
-    pub reconstructed_key: Vec<u8>, // g^y
-    pub commitment: Vec<u8>,        // H(g^y*g^xv)
+    pub election_id: u64,
+    pub reconstructed_keys: Vec<Vec<u8>>, // g^y for each question
+    pub commitments: Vec<Vec<u8>>,        // H(g^y*g^xv) for each question
 }
 
 #[derive(Serialize, SchemaType)]
 pub struct VoteMessage {
-    pub vote: Vec<u8>,         // g^y*g^xv, v = {0, 1}
-    pub vote_zkp: OneInTwoZKP, // one-in-two zkp for v
+    pub election_id: u64,
+    pub votes: Vec<Vec<u8>>,     // g^y*g^xv for each question, v = {0, w}, v = w*m^j for candidate j or v = w * sum of s_j*m^j for scores s_j (w the voter's weight), or g^(c*y) to veto (random c) or not (c = x)
+    pub vote_zkps: Vec<VoteZKP>, // zkp for each v
+}
+
+#[derive(Serialize, SchemaType)]
+pub struct RecoveryMessage {
+    pub election_id: u64,
+    pub recovery_keys: Vec<Vec<u8>>,            // h^x for each question, h = reconstructed key over the voters who did not vote
+    pub recovery_zkps: Vec<ChaumPedersenProof>, // zkp for each log_g(g^x) = log_h(h^x)
+}
+
+#[derive(Serialize, SchemaType)]
+pub struct ExtendTimeoutMessage {
+    pub election_id: u64,
+    pub phase: types::VotingPhase, // registration, commit, vote or recovery
+    pub timeout: Timestamp,        // the new timeout of the phase
+}
+
+#[derive(Serialize, SchemaType)]
+pub struct ResultMessage {
+    pub election_id: u64,
+    pub claimed_result: Option<Vec<Vec<i32>>>, // counts for each question, None to compute the tallies on-chain
+}
+
+#[derive(Serialize, SchemaType, Clone)]
+pub enum VoteZKP {
+    OneInTwo(OneInTwoZKP), // yes/no votes
+    OneInK(OneInKZKP),     // votes for one of k candidates
+    Range(RangeZKP),       // approval and score ballots
+    Veto(SchnorrProof),    // veto elections, in base g^y
 }
 
 // Contract state
 #[derive(Serial, DeserialWithState)]
 #[concordium(state_parameter = "S")]
 pub struct VotingState<S> {
+    elections: StateMap<u64, Election<S>, S>,
+    next_election_id: u64,
+}
+
+#[derive(Serial, DeserialWithState)]
+#[concordium(state_parameter = "S")]
+pub struct Election<S> {
     config: VoteConfig,
+    organizer: AccountAddress, // the account that created the election
     voting_phase: types::VotingPhase,
-    voting_result: (i32, i32),
+    voting_result: Vec<Vec<i32>>,
+    voting_outcome: Vec<types::VoteOutcome>, // one per question, empty until the result is computed
     voters: StateMap<AccountAddress, Voter, S>,
-    voting_keys: Vec<Vec<u8>>,
+    voting_keys: Vec<Vec<Vec<u8>>>, // for each question, the voting keys in the order voters registered
+    missing_voters: Vec<i32>,       // indices of the voters who did not vote, used in the recovery round
+    vote_aggregates: Vec<Vec<u8>>,  // for each question, running sum of the votes minus the recovery keys
+    number_of_voters: i32,      // number of registered voters, also the index of the next voter to register
+    number_of_commitments: i32, // number of voters who committed
+    number_of_votes: i32,
+    registered_weight: i32, // sum of the weights of the registered voters, which can not exceed the total weight
+    voted_weight: i32,      // sum of the weights of the voters who voted
+    number_of_recoveries: i32,
+    balances: StateMap<AccountAddress, Amount, S>, // refunded deposits and rewards, claimed with withdraw
+    total_balance: Amount,                         // sum of the balances, which the organizer can not sweep
+    funds: Amount,                                 // CCD the contract holds for the election, deposits minus withdrawals and sweeps
 }
 
 #[derive(Serialize, SchemaType, Clone, PartialEq, Default)]
 struct Voter {
-    voting_key: Vec<u8>,
-    reconstructed_key: Vec<u8>,
-    commitment: Vec<u8>,
-    vote: Vec<u8>,
-    index: i32
+    voting_keys: Vec<Vec<u8>>, // one per question, empty until sent
+    reconstructed_keys: Vec<Vec<u8>>,
+    commitments: Vec<Vec<u8>>,
+    votes: Vec<Vec<u8>>,
+    recovery_keys: Vec<Vec<u8>>,
+    index: i32,
+    weight: u32, // weight proven in the merkle proof at registration
 }
 
 // Contract functions
 
-/// SETUP PHASE: create an instance of the contract with a voting config
-#[init(contract = "voting", parameter = "VoteConfig")]
+/// SETUP: create an instance of the contract, which can host any number of elections
+#[init(contract = "voting")]
 fn setup<S: HasStateApi>(
-    ctx: &impl HasInitContext,
+    _ctx: &impl HasInitContext,
     state_builder: &mut StateBuilder<S>,
-) -> Result<VotingState<S>, types::SetupError> {
+) -> InitResult<VotingState<S>> {
+    Ok(VotingState {
+        elections: state_builder.new_map(),
+        next_election_id: 0,
+    })
+}
+
+/// SETUP PHASE: function anyone can call to create an election with a voting config, of which they become the organizer.
+/// Returns the id of the election, which the other functions take to select it
+#[receive(
+    contract = "voting",
+    name = "create_election",
+    parameter = "VoteConfig",
+    return_value = "u64",
+    mutable,
+    enable_logger
+)]
+fn create_election<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<u64, types::SetupError> {
     let vote_config: VoteConfig = ctx.parameter_cursor().get()?;
 
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::SetupError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
     // Ensure config is valid
     ensure!(
         vote_config.registration_timeout > ctx.metadata().slot_time(),
@@ -88,37 +276,127 @@ fn setup<S: HasStateApi>(
         vote_config.vote_timeout > vote_config.commit_timeout,
         types::SetupError::InvalidVoteTimeout
     );
+    ensure!(
+        vote_config.recovery_timeout > vote_config.vote_timeout,
+        types::SetupError::InvalidRecoveryTimeout
+    );
     ensure!(
         vote_config.deposit >= Amount::zero(),
         types::SetupError::NegativeDeposit
     );
+    ensure!(
+        vote_config.min_registrants > 2
+            && vote_config.min_registrants <= vote_config.merkle_leaf_count,
+        types::SetupError::InvalidNumberOfVoters
+    );
+    // Every voter has a weight of atleast 1
+    ensure!(
+        vote_config.total_weight >= vote_config.merkle_leaf_count,
+        types::SetupError::InvalidTotalWeight
+    );
+    ensure!(
+        vote_config.quorum.denominator > 0
+            && vote_config.quorum.numerator <= vote_config.quorum.denominator,
+        types::SetupError::InvalidQuorum
+    );
+    if let types::PassRule::Supermajority(fraction) = &vote_config.pass_rule {
+        ensure!(
+            fraction.numerator <= fraction.denominator
+                && (fraction.numerator as u64) * 2 > fraction.denominator as u64,
+            types::SetupError::InvalidPassRule
+        );
+    }
+    if let types::SlashingPolicy::Reporter(fraction) = &vote_config.slashing_policy {
+        ensure!(
+            fraction.denominator > 0 && fraction.numerator <= fraction.denominator,
+            types::SetupError::InvalidSlashingPolicy
+        );
+    }
+    // Candidate votes need atleast 2 options and a packed tally that fits in a u64
+    ensure!(
+        vote_config.voting_options.is_empty()
+            || (vote_config.voting_options.len() > 1
+                && vote_config.candidate_encodings().is_some()),
+        types::SetupError::InvalidVotingOptions
+    );
+    ensure!(
+        vote_config.number_of_questions() > 0,
+        types::SetupError::InvalidVotingQuestions
+    );
+    // Veto elections have no candidates
+    ensure!(
+        !vote_config.is_veto() || vote_config.voting_options.is_empty(),
+        types::SetupError::InvalidBallotType
+    );
+    // Approval and score ballots need candidates, a score and budget of atleast 1 and no more budget than the candidates can take
+    if let Some((max_score, budget)) = vote_config.score_range() {
+        ensure!(
+            !vote_config.voting_options.is_empty()
+                && max_score > 0
+                && budget > 0
+                && budget <= vote_config.voting_options.len() as u64 * max_score,
+            types::SetupError::InvalidBallotType
+        );
+    }
 
-    // Set initial state
-    let state = VotingState {
+    let (state, state_builder) = host.state_and_builder();
+    let election_id = state.next_election_id;
+    state.next_election_id += 1;
+
+    log_event(
+        logger,
+        election_id,
+        types::VotingEvent::Setup {
+            voting_questions: vote_config.voting_questions.clone(),
+            merkle_leaf_count: vote_config.merkle_leaf_count,
+            deposit: vote_config.deposit,
+        },
+    )?;
+
+    // Set initial state of the election
+    let number_of_questions = vote_config.number_of_questions();
+    let election = Election {
         config: vote_config,
+        organizer: sender_address,
         voting_phase: types::VotingPhase::Registration,
-        voting_result: (-1, -1), // -1 = no result yet
+        voting_result: Vec::new(), // empty = no result yet
+        voting_outcome: Vec::new(),
         voters: state_builder.new_map(),
-        voting_keys: Vec::new(),
+        voting_keys: vec![Vec::new(); number_of_questions],
+        missing_voters: Vec::new(),
+        vote_aggregates: vec![ProjectivePoint::IDENTITY.to_bytes().to_vec(); number_of_questions],
+        number_of_voters: 0,
+        number_of_commitments: 0,
+        number_of_votes: 0,
+        registered_weight: 0,
+        voted_weight: 0,
+        number_of_recoveries: 0,
+        balances: state_builder.new_map(),
+        total_balance: Amount::zero(),
+        funds: Amount::zero(),
     };
+    state.elections.insert(election_id, election);
 
-    // Return success with initial voting state
-    Ok(state)
+    // Return success with the id of the election
+    Ok(election_id)
 }
 
-/// REGISTRATION PHASE: function voters call to register them for the vote by sending (voting key, ZKP, deposit)
+/// REGISTRATION PHASE: function voters call to register them for the vote by sending (voting key and ZKP for each question, deposit).
+/// Returns the index of the voter, or None if registration had ended, in which case only the due phase change is applied and the deposit can be withdrawn again
 #[receive(
     contract = "voting",
     name = "register",
     parameter = "RegisterMessage",
     payable,
-    mutable
+    mutable,
+    enable_logger
 )]
 fn register<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
     deposit: Amount,
-) -> Result<i32, types::RegisterError> {
+    logger: &mut impl HasLogger,
+) -> Result<Option<i32>, types::RegisterError> {
     let register_message: RegisterMessage = ctx.parameter_cursor().get()?;
 
     // Get sender address and bail if its another smart contract
@@ -127,24 +405,46 @@ fn register<S: HasStateApi>(
         Address::Account(account_address) => account_address,
     };
 
+    let election_id = register_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::RegisterError::ElectionNotFound),
+    };
+    election.funds += deposit;
+
+    // ZKPs must be bound to this contract instance, election and sender
+    let context = proof_context(ctx, election_id, sender_address);
+
+    // Apply a due phase change first, keeping it (by not failing) if it ends the registration
+    if advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?
+        && election.voting_phase != types::VotingPhase::Registration
+    {
+        credit(&mut election, sender_address, deposit);
+        log_event(logger, election_id, types::VotingEvent::Refund {
+            account: sender_address,
+            amount: deposit,
+        })?;
+        return Ok(None);
+    }
+
     ensure!(
-        host.state().voting_phase == types::VotingPhase::Registration,
+        election.voting_phase == types::VotingPhase::Registration,
         types::RegisterError::NotRegistrationPhase
     );
     ensure!(
-        host.state().config.deposit == deposit,
+        election.config.deposit == deposit,
         types::RegisterError::WrongDeposit
     );
     ensure!(
-        ctx.metadata().slot_time() <= host.state().config.registration_timeout,
+        ctx.metadata().slot_time() <= election.config.registration_timeout,
         types::RegisterError::PhaseEnded
     );
 
-    // Check voter is authorized through verifying merkle proof-of-membership
+    // Check voter is authorized through verifying merkle proof-of-membership of the account and its weight
     ensure_eq!(
         crypto::verify_merkle_proof(
-            &host.state().config.merkle_root,
-            host.state().config.merkle_leaf_count,
+            &election.config.merkle_root,
+            election.config.merkle_leaf_count,
             &register_message.merkle_proof,
             &sender_address
         ),
@@ -152,59 +452,106 @@ fn register<S: HasStateApi>(
         types::RegisterError::UnauthorizedVoter
     );
 
+    // One voting key and ZKP for each question
+    let number_of_questions = election.config.number_of_questions();
+    ensure!(
+        register_message.voting_keys.len() == number_of_questions
+            && register_message.voting_key_zkps.len() == number_of_questions,
+        types::RegisterError::InvalidVotingKey
+    );
+
+    // A voter has a weight of atleast 1, and all registered weights must fit in the total weight the tally is decoded with
+    let weight = register_message.merkle_proof.weight;
+    ensure!(
+        weight > 0
+            && election.registered_weight as u64 + weight as u64
+                <= election.config.total_weight as u64,
+        types::RegisterError::InvalidWeight
+    );
+
     // Register the voter in the map, ensure they can only do this once
-    match host.state().voters.get(&sender_address) {
+    match election.voters.get(&sender_address) {
         Some(_) => bail!(types::RegisterError::AlreadyRegistered),
-        None => host
-            .state_mut()
+        None => election
             .voters
             .insert(sender_address, Default::default()),
     };
 
-    let index = host.state().voters.iter().count() as i32 - 1;
+    let index = election.number_of_voters;
 
-    // Wrap in code block to scope host.state borrow
+    // Wrap in code block to scope the election borrow
     {
-        let state = host.state_mut();
+        let state = &mut *election;
 
         // Get the inserted voter
-        let mut voter = util::unwrap_abort(state.voters.get_mut(&sender_address));
-
-        // Check voting key (g^x) is valid point on curve, by attempting to convert
-        match PublicKey::<Secp256k1>::from_sec1_bytes(&register_message.voting_key) {
-            Ok(p) => p,
-            Err(_) => bail!(types::RegisterError::InvalidVotingKey),
+        let mut voter = match util::try_unwrap(state.voters.get_mut(&sender_address)) {
+            Ok(v) => v,
+            Err(_) => bail!(types::RegisterError::VoterNotFound),
         };
 
-        // Check validity of ZKP
-        let zkp: SchnorrProof = register_message.voting_key_zkp.clone();
-        ensure!(
-            crypto::verify_schnorr_zkp(convert_vec_to_point(&register_message.voting_key), zkp),
-            types::RegisterError::InvalidZKP
-        );
+        for (voting_key, zkp) in register_message
+            .voting_keys
+            .iter()
+            .zip(register_message.voting_key_zkps.iter())
+        {
+            // Check voting key (g^x) is valid point on curve, by attempting to convert
+            let voting_key = match util::try_convert_vec_to_point(voting_key) {
+                Ok(p) => p,
+                Err(_) => bail!(types::RegisterError::InvalidVotingKey),
+            };
 
-        // Add voting key to correct voter
-        voter.voting_key = register_message.voting_key.clone();
+            // Check validity of ZKP (a malformed proof is invalid)
+            ensure_eq!(
+                crypto::verify_schnorr_zkp(voting_key, zkp.clone(), &context),
+                Ok(true),
+                types::RegisterError::InvalidZKP
+            );
+        }
+
+        // Add voting keys to correct voter
+        voter.voting_keys = register_message.voting_keys.clone();
 
         voter.index = index;
+        voter.weight = weight;
+        state.number_of_voters += 1;
+        state.registered_weight += weight as i32;
 
-        // List of all voting keys
-        state.voting_keys.push(register_message.voting_key);
+        // Lists of all voting keys
+        for (keys, voting_key) in state
+            .voting_keys
+            .iter_mut()
+            .zip(register_message.voting_keys.iter())
+        {
+            keys.push(voting_key.clone());
+        }
     }
 
-    Ok(index)
+    log_event(logger, election_id, types::VotingEvent::Register {
+        account: sender_address,
+        index,
+        voting_keys: register_message.voting_keys,
+        weight,
+    })?;
+
+    // Move on to the commit phase if all eligible voters have registered
+    advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?;
+
+    Ok(Some(index))
 }
 
-/// COMMIT PHASE: function voters call to submit reconstructed key and commit to their vote (by sending a hash of it)
+/// COMMIT PHASE: function voters call to submit reconstructed key and commit to their vote (by sending a hash of it).
+/// If the commit phase had ended, only the due phase change is applied
 #[receive(
     contract = "voting",
     name = "commit",
     parameter = "CommitMessage",
-    mutable
+    mutable,
+    enable_logger
 )]
 fn commit<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
 ) -> Result<(), types::CommitError> {
     let commitment_message: CommitMessage = ctx.parameter_cursor().get()?;
 
@@ -214,62 +561,114 @@ fn commit<S: HasStateApi>(
         Address::Account(account_address) => account_address,
     };
 
+    let election_id = commitment_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::CommitError::ElectionNotFound),
+    };
+
+    // Apply a due phase change first, keeping it (by not failing) if it ends the commit phase
+    if advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?
+        && election.voting_phase != types::VotingPhase::Commit
+    {
+        return Ok(());
+    }
+
     ensure!(
-        host.state().voting_phase == types::VotingPhase::Commit,
+        election.voting_phase == types::VotingPhase::Commit,
         types::CommitError::NotCommitPhase
     );
     ensure!(
-        host.state().voters.get(&sender_address).is_some(),
+        election.voters.get(&sender_address).is_some(),
         types::CommitError::UnauthorizedVoter
     );
     ensure!(
-        ctx.metadata().slot_time() <= host.state().config.commit_timeout,
+        ctx.metadata().slot_time() <= election.config.commit_timeout,
         types::CommitError::PhaseEnded
     );
 
+    // One reconstructed key and commitment for each question
+    let number_of_questions = election.config.number_of_questions();
+    ensure!(
+        commitment_message.commitments.len() == number_of_questions
+            && commitment_message.reconstructed_keys.len() == number_of_questions,
+        types::CommitError::InvalidCommitMessage
+    );
     ensure!(
-        commitment_message.commitment != Vec::<u8>::new(),
+        commitment_message
+            .commitments
+            .iter()
+            .all(|commitment| *commitment != Vec::<u8>::new()),
         types::CommitError::InvalidCommitMessage
     );
     ensure!(
-        commitment_message.reconstructed_key != Vec::<u8>::new(),
+        commitment_message
+            .reconstructed_keys
+            .iter()
+            .all(|reconstructed_key| *reconstructed_key != Vec::<u8>::new()),
         types::CommitError::InvalidCommitMessage
     );
 
-    // Save voter's reconstructed key and commitment in voter state
-    let state = host.state_mut();
+    // Save voter's reconstructed keys and commitments in voter state
+    let state = &mut *election;
     match state.voters.get_mut(&sender_address) {
         Some(mut v) => {
-            // Re-compute voter's reconstructed key to check whether the one send along is valid
-            ensure!(
-                commitment_message.reconstructed_key
-                    == util::compute_reconstructed_key(
-                        &state.voting_keys
+            // Re-compute voter's reconstructed key of each question to check whether the ones send along are valid
+            for (voting_keys, reconstructed_key) in state
+                .voting_keys
+                .iter()
+                .zip(commitment_message.reconstructed_keys.iter())
+            {
+                let computed_key = match util::try_compute_reconstructed_key(
+                    &voting_keys
                         .iter()
-                        .map(|vk| convert_vec_to_point(&vk))
-                        .collect(),
-                        v.index
-                    )
-                    .to_bytes()
-                    .to_vec(),
-                types::CommitError::InvalidReconstructedKey
-            );
+                        .map(convert_vec_to_point)
+                        .collect::<Vec<_>>(),
+                    v.index,
+                ) {
+                    Ok(key) => key,
+                    Err(_) => bail!(types::CommitError::InvalidReconstructedKey),
+                };
+                ensure!(
+                    *reconstructed_key == computed_key.to_bytes().to_vec(),
+                    types::CommitError::InvalidReconstructedKey
+                );
+            }
 
-            v.reconstructed_key = commitment_message.reconstructed_key;
-            v.commitment = commitment_message.commitment;
+            // A voter counts as committed once, even if they send their commitments again
+            if v.commitments.is_empty() {
+                state.number_of_commitments += 1;
+            }
+            v.reconstructed_keys = commitment_message.reconstructed_keys;
+            v.commitments = commitment_message.commitments;
         }
 
         None => bail!(types::CommitError::VoterNotFound),
     };
 
+    log_event(logger, election_id, types::VotingEvent::Commit {
+        account: sender_address,
+    })?;
+
+    // Move on to the vote phase if all voters have committed
+    advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?;
+
     Ok(())
 }
 
-/// VOTE PHASE: function voters call to send their encrypted vote along with a one-in-two ZKP
-#[receive(contract = "voting", name = "vote", parameter = "VoteMessage", mutable)]
+/// VOTE PHASE: function voters call to send their encrypted vote on each question along with a one-in-two (or one-in-k) ZKP.
+/// If the vote phase had ended, only the due phase change is applied
+#[receive(
+    contract = "voting",
+    name = "vote",
+    parameter = "VoteMessage",
+    mutable,
+    enable_logger
+)]
 fn vote<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
 ) -> Result<(), types::VoteError> {
     let vote_message: VoteMessage = ctx.parameter_cursor().get()?;
 
@@ -279,172 +678,1014 @@ fn vote<S: HasStateApi>(
         Address::Account(account_address) => account_address,
     };
 
+    let election_id = vote_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::VoteError::ElectionNotFound),
+    };
+
+    // ZKPs must be bound to this contract instance, election and sender
+    let context = proof_context(ctx, election_id, sender_address);
+
+    // Apply a due phase change first, keeping it (by not failing) if it ends the vote phase
+    if advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?
+        && election.voting_phase != types::VotingPhase::Vote
+    {
+        return Ok(());
+    }
+
     ensure!(
-        host.state().voting_phase == types::VotingPhase::Vote,
+        election.voting_phase == types::VotingPhase::Vote,
         types::VoteError::NotVotePhase
     );
     ensure!(
-        host.state().voters.get(&sender_address).is_some(),
+        election.voters.get(&sender_address).is_some(),
         types::VoteError::UnauthorizedVoter
     );
     ensure!(
-        ctx.metadata().slot_time() <= host.state().config.vote_timeout,
+        ctx.metadata().slot_time() <= election.config.vote_timeout,
         types::VoteError::PhaseEnded
     );
 
-    // Get voter
-    match host.state_mut().voters.get_mut(&sender_address) {
+    let candidate_encodings = election.config.candidate_encodings();
+    let score_range = election.config.score_range();
+    let veto = election.config.is_veto();
+
+    // One vote and ZKP for each question
+    let number_of_questions = election.config.number_of_questions();
+    ensure!(
+        vote_message.votes.len() == number_of_questions
+            && vote_message.vote_zkps.len() == number_of_questions,
+        types::VoteError::InvalidVote
+    );
+
+    // Check votes are valid points on curve, by attempting to convert
+    let mut vote_points = Vec::with_capacity(number_of_questions);
+    for vote in vote_message.votes.iter() {
+        match util::try_convert_vec_to_point(vote) {
+            Ok(p) => vote_points.push(p),
+            Err(_) => bail!(types::VoteError::InvalidVote),
+        };
+    }
+
+    // Get voter, returning their weight once the votes are set
+    let weight = match election.voters.get_mut(&sender_address) {
         Some(mut v) => {
             // Ensure that voters cannot change their vote (cannot call vote function multiple times)
-            ensure!(v.vote == Vec::<u8>::new(), types::VoteError::AlreadyVoted);
+            ensure!(v.votes.is_empty(), types::VoteError::AlreadyVoted);
 
-            // Verify one-in-two ZKP
+            // Voters without reconstructed keys did not commit, so there are no commitments to match
             ensure!(
-                crypto::verify_one_in_two_zkp(
-                    vote_message.vote_zkp.clone(),
-                    convert_vec_to_point(&v.reconstructed_key)
-                ),
-                types::VoteError::InvalidZKP
+                v.reconstructed_keys.len() == number_of_questions,
+                types::VoteError::VoteCommitmentMismatch
             );
 
-            // Check commitment matches vote
+            // A voter of weight w votes v = 0 or w, or with every encoding scaled by w
+            let weight = v.weight as u64;
+
+            for (question, vote_point) in vote_points.iter().enumerate() {
+                let g_y = match util::try_convert_vec_to_point(&v.reconstructed_keys[question]) {
+                    Ok(p) => p,
+                    Err(_) => bail!(types::VoteError::VoteCommitmentMismatch),
+                };
+
+                // The proof must be about the voter's registered key, otherwise the vote does not cancel out in the tally
+                let g_x = v
+                    .voting_keys
+                    .get(question)
+                    .and_then(|g_x| util::try_convert_vec_to_point(g_x).ok());
+
+                // Verify the ZKP matching the type of vote, and that it is a proof of the vote being cast (a malformed proof is invalid)
+                let valid_zkp = match (&vote_message.vote_zkps[question], &candidate_encodings, score_range) {
+                    (VoteZKP::OneInTwo(zkp), None, None) if !veto => {
+                        OneInTwoZKP::extract_points(zkp).map(|points| (Some(points.0), points.1))
+                            == Ok((g_x, *vote_point))
+                            && crypto::verify_one_in_two_zkp(zkp.clone(), g_y, weight, &context)
+                                == Ok(true)
+                    }
+                    (VoteZKP::OneInK(zkp), Some(encodings), None) => {
+                        OneInKZKP::extract_points(zkp).map(|points| (Some(points.0), points.1))
+                            == Ok((g_x, *vote_point))
+                            && crypto::verify_one_in_k_zkp(
+                                zkp.clone(),
+                                g_y,
+                                &util::weighted_encodings(encodings, weight),
+                                &context,
+                            ) == Ok(true)
+                    }
+                    (VoteZKP::Veto(zkp), None, None) if veto => {
+                        crypto::verify_veto_zkp(g_y, *vote_point, zkp.clone(), &context) == Ok(true)
+                    }
+                    // The scores are split off the voter's registered key, so it must be a valid one
+                    (VoteZKP::Range(zkp), Some(encodings), Some((max_score, budget))) => {
+                        match g_x {
+                            Some(g_x) => {
+                                crypto::verify_range_zkp(
+                                    zkp.clone(),
+                                    g_x,
+                                    g_y,
+                                    *vote_point,
+                                    max_score,
+                                    budget,
+                                    encodings,
+                                    weight,
+                                    &context,
+                                ) == Ok(true)
+                            }
+                            None => false,
+                        }
+                    }
+                    _ => false,
+                };
+                ensure!(valid_zkp, types::VoteError::InvalidZKP);
+
+                // Check commitment matches vote
+                ensure!(
+                    crypto::check_commitment(*vote_point, v.commitments[question].clone()),
+                    types::VoteError::VoteCommitmentMismatch
+                );
+            }
+
+            // Set votes
+            v.votes = vote_message.votes;
+            v.weight
+        }
+        None => bail!(types::VoteError::VoterNotFound),
+    };
+
+    // Add the votes to the running aggregates, such that tallying does not need to go through all voters
+    let state = &mut *election;
+    for (vote_aggregate, vote_point) in state.vote_aggregates.iter_mut().zip(vote_points) {
+        *vote_aggregate = (util::convert_vec_to_point_or_identity(vote_aggregate) + vote_point)
+            .to_bytes()
+            .to_vec();
+    }
+    state.number_of_votes += 1;
+    state.voted_weight += weight as i32;
+
+    log_event(logger, election_id, types::VotingEvent::Vote {
+        account: sender_address,
+    })?;
+
+    // Refund deposit to sender address (they have voted and their job is done), to be withdrawn
+    let deposit = election.config.deposit;
+    credit(&mut election, sender_address, deposit);
+    log_event(logger, election_id, types::VotingEvent::Refund {
+        account: sender_address,
+        amount: election.config.deposit,
+    })?;
+
+    // Move on to the result phase if all voters have voted
+    advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?;
+
+    Ok(())
+}
+
+/// RECOVERY PHASE: function voters who voted call to send their recovery key of each question for the voters who did not vote, along with Chaum-Pedersen ZKPs
+/// If the recovery phase had ended, only the due phase change is applied
+#[receive(
+    contract = "voting",
+    name = "recover",
+    parameter = "RecoveryMessage",
+    mutable,
+    enable_logger
+)]
+fn recover<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), types::RecoveryError> {
+    let recovery_message: RecoveryMessage = ctx.parameter_cursor().get()?;
+
+    // Get sender address and bail if its another smart contract
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::RecoveryError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    let election_id = recovery_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::RecoveryError::ElectionNotFound),
+    };
+
+    // ZKPs must be bound to this contract instance, election and sender
+    let context = proof_context(ctx, election_id, sender_address);
+
+    // Apply a due phase change first, keeping it (by not failing) if it ends the recovery phase
+    if advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?
+        && election.voting_phase != types::VotingPhase::Recovery
+    {
+        return Ok(());
+    }
+
+    ensure!(
+        election.voting_phase == types::VotingPhase::Recovery,
+        types::RecoveryError::NotRecoveryPhase
+    );
+    ensure!(
+        election.voters.get(&sender_address).is_some(),
+        types::RecoveryError::UnauthorizedVoter
+    );
+    ensure!(
+        ctx.metadata().slot_time() <= election.config.recovery_timeout,
+        types::RecoveryError::PhaseEnded
+    );
+
+    // One recovery key and ZKP for each question
+    let number_of_questions = election.config.number_of_questions();
+    ensure!(
+        recovery_message.recovery_keys.len() == number_of_questions
+            && recovery_message.recovery_zkps.len() == number_of_questions,
+        types::RecoveryError::InvalidRecoveryKey
+    );
+
+    // Check recovery keys (h^x) are valid points on curve, by attempting to convert
+    let mut recovery_keys = Vec::with_capacity(number_of_questions);
+    for recovery_key in recovery_message.recovery_keys.iter() {
+        match util::try_convert_vec_to_point(recovery_key) {
+            Ok(p) => recovery_keys.push(p),
+            Err(_) => bail!(types::RecoveryError::InvalidRecoveryKey),
+        };
+    }
+
+    let state = &mut *election;
+    match state.voters.get_mut(&sender_address) {
+        Some(mut v) => {
+            // Only voters who voted take part in the recovery, and only once
+            ensure!(!v.votes.is_empty(), types::RecoveryError::DidNotVote);
             ensure!(
-                crypto::check_commitment(
-                    convert_vec_to_point(&vote_message.vote),
-                    v.commitment.clone()
-                ),
-                types::VoteError::VoteCommitmentMismatch
+                v.recovery_keys.is_empty(),
+                types::RecoveryError::AlreadyRecovered
             );
 
-            // Set vote
-            v.vote = vote_message.vote;
+            for (question, (recovery_key, zkp)) in recovery_keys
+                .iter()
+                .zip(recovery_message.recovery_zkps)
+                .enumerate()
+            {
+                // Compute the recovery base (h) of the voter from the voting keys of the voters who did not vote
+                let recovery_base = match util::try_compute_recovery_base(
+                    &state.voting_keys[question]
+                        .iter()
+                        .map(convert_vec_to_point)
+                        .collect::<Vec<_>>(),
+                    v.index,
+                    &state.missing_voters,
+                ) {
+                    Ok(h) => h,
+                    Err(_) => bail!(types::RecoveryError::VoterNotFound),
+                };
+
+                // Verify Chaum-Pedersen ZKP, that the recovery key uses the same x as the voting key (a malformed proof is invalid)
+                ensure_eq!(
+                    crypto::verify_chaum_pedersen_zkp(
+                        convert_vec_to_point(&v.voting_keys[question]),
+                        recovery_base,
+                        *recovery_key,
+                        zkp,
+                        &context
+                    ),
+                    Ok(true),
+                    types::RecoveryError::InvalidZKP
+                );
+            }
+
+            v.recovery_keys = recovery_message.recovery_keys.clone();
         }
-        None => bail!(types::VoteError::VoterNotFound),
+        None => bail!(types::RecoveryError::VoterNotFound),
     };
 
-    // Refund deposit to sender address (they have voted and their job is done)
-    host.invoke_transfer(&sender_address, host.state().config.deposit)?;
+    // Remove the recovery keys from the running aggregates of the votes
+    for (vote_aggregate, recovery_key) in state.vote_aggregates.iter_mut().zip(recovery_keys) {
+        *vote_aggregate = (util::convert_vec_to_point_or_identity(vote_aggregate) - recovery_key)
+            .to_bytes()
+            .to_vec();
+    }
+    state.number_of_recoveries += 1;
+
+    log_event(logger, election_id, types::VotingEvent::Recover {
+        account: sender_address,
+    })?;
+
+    // Move on to the result phase if all voters who voted have sent their recovery keys
+    advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?;
 
     Ok(())
 }
 
-/// RESULT PHASE: function anyone can call to compute tally if vote is over
-#[receive(contract = "voting", name = "result", mutable)]
+/// RESULT PHASE: function anyone can call to compute the tally of each question if vote is over.
+/// Optionally takes the claimed result as parameter, in which case the tallies are only verified and not searched for on-chain.
+/// Returns for each question (yes, no) for yes/no votes, the count of each candidate for single and approval ballots and the score sum
+/// of each candidate for score ballots, all weighted by the voters' weights, along with the outcome given the quorum and pass rule. Veto elections only get an outcome
+#[receive(
+    contract = "voting",
+    name = "result",
+    parameter = "ResultMessage",
+    return_value = "Vec<types::VotingResult>",
+    mutable,
+    enable_logger
+)]
 fn result<S: HasStateApi>(
-    _ctx: &impl HasReceiveContext,
+    ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
-) -> Result<(i32, i32), types::ResultError> {
-    let mut state = host.state_mut();
+    logger: &mut impl HasLogger,
+) -> Result<Vec<types::VotingResult>, types::ResultError> {
+    let result_message: ResultMessage = ctx.parameter_cursor().get()?;
+
+    let election_id = result_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::ResultError::ElectionNotFound),
+    };
+    let state = &mut *election;
 
     ensure!(
         state.voting_phase == types::VotingPhase::Result,
         types::ResultError::NotResultPhase
     );
 
-    // Create list of all votes
-    let mut votes = Vec::new();
-    votes.extend(
-        state
-            .voters
-            .iter()
-            .map(|(_, v)| convert_vec_to_point(&v.vote)),
+    // Votes count with the weight of the voter, so the tallies are bounded by the weight that voted
+    let voted_weight = state.voted_weight;
+    let candidate_encodings = state.config.candidate_encodings();
+    let score_range = state.config.score_range();
+    let base = state.config.tally_base();
+    let veto = state.config.is_veto();
+
+    // A claimed result must have the counts of every question
+    let claimed_results = match result_message.claimed_result {
+        Some(claimed) => {
+            ensure!(
+                claimed.len() == state.config.number_of_questions(),
+                types::ResultError::WrongClaimedResult
+            );
+            claimed.into_iter().map(Some).collect()
+        }
+        None => vec![None; state.config.number_of_questions()],
+    };
+
+    let mut results = Vec::new();
+    for (vote_aggregate, claimed_result) in state.vote_aggregates.iter().zip(claimed_results) {
+        // Sum of all cast votes, with the recovery keys for the voters who did not vote removed (if any)
+        let vote_aggregate = util::convert_vec_to_point_or_identity(vote_aggregate);
+
+        // A veto election only tells whether anyone vetoed, which is when the votes g^(c*y) do not cancel out to the identity
+        if veto {
+            ensure!(
+                claimed_result.is_none_or(|claimed| claimed.is_empty()),
+                types::ResultError::WrongClaimedResult
+            );
+            let vetoed = vote_aggregate != ProjectivePoint::IDENTITY;
+            results.push(types::VotingResult {
+                counts: Vec::new(),
+                outcome: state.config.veto_outcome(vetoed, voted_weight),
+            });
+            continue;
+        }
+
+        let tally = match claimed_result {
+            Some(claimed) => {
+                // The claimed result must count every vote exactly once with its weight, or every weighted score within the budgets
+                let counts_votes = match score_range {
+                    None => claimed.iter().sum::<i32>() == voted_weight,
+                    Some((_, budget)) => {
+                        claimed.iter().map(|c| *c as i64).sum::<i64>()
+                            <= voted_weight as i64 * budget as i64
+                    }
+                };
+                ensure!(
+                    claimed.iter().all(|c| *c >= 0) && counts_votes,
+                    types::ResultError::WrongClaimedResult
+                );
+
+                // Pack the claimed result into a tally (number of yes votes or the packed candidate tally)
+                let claimed_tally = match &candidate_encodings {
+                    Some(encodings) if claimed.len() == encodings.len() => {
+                        util::encode_candidate_tally(&claimed, base)
+                    }
+                    None if claimed.len() == 2 => Some(claimed[0] as u64),
+                    _ => None,
+                };
+
+                // Check the sum of the votes is g^tally
+                match claimed_tally {
+                    Some(t) if crypto::verify_tally(vote_aggregate, t) => t,
+                    _ => bail!(types::ResultError::WrongClaimedResult),
+                }
+            }
+            None => {
+                // The tally is at most the weight of every voter for the highest encoded option, or the highest score for every option
+                let bound = match (&candidate_encodings, score_range) {
+                    (Some(encodings), None) => match util::try_unwrap(encodings.last()) {
+                        Ok(highest) => voted_weight as u64 * highest,
+                        Err(_) => bail!(types::ResultError::TallyNotInRange),
+                    },
+                    (Some(encodings), Some((max_score, _))) => {
+                        voted_weight as u64 * max_score * encodings.iter().sum::<u64>()
+                    }
+                    (None, _) => voted_weight as u64,
+                };
+
+                // Compute the tally (number of yes votes or the packed candidate tally)
+                match crypto::compute_tally(vote_aggregate, bound) {
+                    Some(t) => t,
+                    None => bail!(types::ResultError::TallyNotInRange),
+                }
+            }
+        };
+
+        let voting_result = match &candidate_encodings {
+            Some(encodings) => {
+                // Decode the count (or score sum) of each candidate from the tally
+                util::decode_candidate_tally(tally, encodings.len(), base)
+            }
+            None => {
+                // Calc no votes
+                let yes_votes = tally as i32;
+                let no_votes = voted_weight - yes_votes;
+                vec![yes_votes, no_votes]
+            }
+        };
+
+        let outcome = state.config.outcome(&voting_result, voted_weight);
+        results.push(types::VotingResult {
+            counts: voting_result,
+            outcome,
+        });
+    }
+
+    // Set voting results and outcomes in public state
+    state.voting_result = results.iter().map(|r| r.counts.clone()).collect();
+    state.voting_outcome = results.iter().map(|r| r.outcome.clone()).collect();
+
+    log_event(logger, election_id, types::VotingEvent::Result {
+        results: results.clone(),
+    })?;
+
+    Ok(results)
+}
+
+/// WITHDRAW: function anyone can call in any phase to claim their refunded deposits and rewards. Returns the amount transferred
+#[receive(
+    contract = "voting",
+    name = "withdraw",
+    parameter = "u64",
+    return_value = "Amount",
+    mutable,
+    enable_logger
+)]
+fn withdraw<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<Amount, types::WithdrawError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::WithdrawError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::WithdrawError::ElectionNotFound),
+    };
+
+    // Clear the balance before transferring it. A failing transfer rolls this back, so nothing is lost
+    let amount = match election.balances.remove_and_get(&sender_address) {
+        Some(amount) if amount > Amount::zero() => amount,
+        _ => bail!(types::WithdrawError::NothingToWithdraw),
+    };
+
+    election.total_balance -= amount;
+    election.funds -= amount;
+    drop(election);
+    host.invoke_transfer(&sender_address, amount)?;
+    log_event(logger, election_id, types::VotingEvent::Withdraw {
+        account: sender_address,
+        amount,
+    })?;
+
+    Ok(amount)
+}
+
+/// SWEEP: function the organizer calls once the election has ended, to recover the CCD of the election not owed to anyone, such as slashed deposits that were not paid out.
+/// Returns the amount transferred
+#[receive(
+    contract = "voting",
+    name = "sweep",
+    parameter = "u64",
+    return_value = "Amount",
+    mutable,
+    enable_logger
+)]
+fn sweep<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<Amount, types::SweepError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::SweepError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::SweepError::ElectionNotFound),
+    };
+
+    ensure!(
+        sender_address == election.organizer,
+        types::SweepError::NotOrganizer
+    );
+    ensure!(
+        election.voting_phase == types::VotingPhase::Result
+            || election.voting_phase == types::VotingPhase::Abort
+            || election.voting_phase == types::VotingPhase::Cancelled,
+        types::SweepError::ElectionNotEnded
+    );
+
+    // Everything the election holds but the balances voters and reporters can still withdraw.
+    // CCD of the other elections in the contract is never touched
+    let amount = Amount::from_micro_ccd(
+        election
+            .funds
+            .micro_ccd
+            .saturating_sub(election.total_balance.micro_ccd),
+    );
+    ensure!(amount > Amount::zero(), types::SweepError::NothingToSweep);
+
+    election.funds -= amount;
+    drop(election);
+    host.invoke_transfer(&sender_address, amount)?;
+    log_event(logger, election_id, types::VotingEvent::Sweep {
+        account: sender_address,
+        amount,
+    })?;
+
+    Ok(amount)
+}
+
+/// CANCEL: function the organizer can call to cancel the election during the registration phase, such as to fix a mistake in the config.
+/// The deposits of all registered voters are refunded, to be withdrawn
+#[receive(
+    contract = "voting",
+    name = "cancel",
+    parameter = "u64",
+    mutable,
+    enable_logger
+)]
+fn cancel<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), types::CancelError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::CancelError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::CancelError::ElectionNotFound),
+    };
+
+    ensure!(
+        sender_address == election.organizer,
+        types::CancelError::NotOrganizer
+    );
+    // Once registration is over voters start committing to their votes, which the organizer must not be able to throw away
+    ensure!(
+        election.voting_phase == types::VotingPhase::Registration
+            && ctx.metadata().slot_time() <= election.config.registration_timeout,
+        types::CancelError::NotRegistrationPhase
+    );
+
+    let registered_accounts: Vec<AccountAddress> =
+        election.voters.iter().map(|(addr, _)| *addr).collect();
+    let deposit = election.config.deposit;
+    for account in registered_accounts {
+        credit(&mut election, account, deposit);
+        log_event(logger, election_id, types::VotingEvent::Refund {
+            account,
+            amount: deposit,
+        })?;
+    }
+
+    election.voting_phase = types::VotingPhase::Cancelled;
+    log_event(logger, election_id, types::VotingEvent::Cancel {
+        organizer: sender_address,
+    })?;
+
+    Ok(())
+}
+
+/// EXTEND TIMEOUT: function the organizer can call to move the timeout of a phase that has not ended later in time.
+/// The timeouts stay in order, so a timeout can not be moved past the timeout of the next phase (which can be extended first)
+#[receive(
+    contract = "voting",
+    name = "extend_timeout",
+    parameter = "ExtendTimeoutMessage",
+    mutable,
+    enable_logger
+)]
+fn extend_timeout<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), types::ExtendError> {
+    let extend_message: ExtendTimeoutMessage = ctx.parameter_cursor().get()?;
+
+    let sender_address = match ctx.sender() {
+        Address::Contract(_) => bail!(types::ExtendError::ContractSender),
+        Address::Account(account_address) => account_address,
+    };
+
+    let election_id = extend_message.election_id;
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::ExtendError::ElectionNotFound),
+    };
+
+    ensure!(
+        sender_address == election.organizer,
+        types::ExtendError::NotOrganizer
     );
 
-    // Brute force the tally (number of yes votes)
-    let yes_votes = crypto::brute_force_tally(votes.clone());
+    // The phases with a timeout, in order
+    let phases = [
+        types::VotingPhase::Registration,
+        types::VotingPhase::Commit,
+        types::VotingPhase::Vote,
+        types::VotingPhase::Recovery,
+    ];
+    let config = &election.config;
+    let timeouts = [
+        config.registration_timeout,
+        config.commit_timeout,
+        config.vote_timeout,
+        config.recovery_timeout,
+    ];
 
-    // Calc no votes
-    let no_votes = votes.len() as i32 - yes_votes;
+    // The phase must be the current or a later phase, and not have timed out
+    let current = phases
+        .iter()
+        .position(|phase| *phase == election.voting_phase);
+    let extended = phases
+        .iter()
+        .position(|phase| *phase == extend_message.phase);
+    let extended = match (current, extended) {
+        (Some(current), Some(extended)) if extended >= current => extended,
+        _ => bail!(types::ExtendError::PhaseClosed),
+    };
+    ensure!(
+        ctx.metadata().slot_time() <= timeouts[extended],
+        types::ExtendError::PhaseClosed
+    );
 
-    // Set voting result in public state
-    state.voting_result = (yes_votes, no_votes);
+    ensure!(
+        extend_message.timeout > timeouts[extended],
+        types::ExtendError::NotLater
+    );
+    if let Some(next_timeout) = timeouts.get(extended + 1) {
+        ensure!(
+            extend_message.timeout < *next_timeout,
+            types::ExtendError::InvalidTimeout
+        );
+    }
+
+    let config = &mut election.config;
+    match extend_message.phase {
+        types::VotingPhase::Registration => config.registration_timeout = extend_message.timeout,
+        types::VotingPhase::Commit => config.commit_timeout = extend_message.timeout,
+        types::VotingPhase::Vote => config.vote_timeout = extend_message.timeout,
+        _ => config.recovery_timeout = extend_message.timeout,
+    }
 
-    Ok((yes_votes, no_votes))
+    log_event(logger, election_id, types::VotingEvent::ExtendTimeout {
+        phase: extend_message.phase,
+        timeout: extend_message.timeout,
+    })?;
+
+    Ok(())
 }
 
 /// CHANGE PHASE: function anyone can call to change voting phase if conditions are met
-#[receive(contract = "voting", name = "change_phase", mutable)]
+#[receive(
+    contract = "voting",
+    name = "change_phase",
+    parameter = "u64",
+    mutable,
+    enable_logger
+)]
 fn change_phase<S: HasStateApi>(
     ctx: &impl HasReceiveContext,
     host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
+    logger: &mut impl HasLogger,
 ) -> Result<(), types::ChangeError> {
-    let now = ctx.metadata().slot_time();
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+
     let sender_address = match ctx.sender() {
         Address::Contract(_) => bail!(types::ChangeError::ContractSender),
         Address::Account(account_address) => account_address,
     };
 
-    match host.state().voting_phase {
-        types::VotingPhase::Registration => {
-            // Change to commit phase if registration time is over and atleast 3 voters have registered
-            // Note: will move on with the vote without stalling/too slow authorized voters
-            if (now > host.state().config.registration_timeout
-                && host.state().voters.iter().count() > 2)
-                || host.state().voters.iter().count() as i32
-                    == host.state().config.merkle_leaf_count
-            {
-                host.state_mut().voting_phase = types::VotingPhase::Commit
-            }
-            // Change to abort if <3 voters have registered and time is over
-            else if now > host.state().config.registration_timeout {
-                refund_deposits(sender_address, host)?;
-                host.state_mut().voting_phase = types::VotingPhase::Abort
-            }
-        }
-        types::VotingPhase::Commit => {
-            // Change to vote phase, if all voters have committed
-            if host
-                .state()
-                .voters
-                .iter()
-                .all(|(_, v)| v.commitment != Vec::<u8>::new())
-            {
-                host.state_mut().voting_phase = types::VotingPhase::Vote
+    let mut election = match host.state_mut().elections.get_mut(&election_id) {
+        Some(election) => election,
+        None => bail!(types::ChangeError::ElectionNotFound),
+    };
+
+    advance_phase(election_id, ctx.metadata().slot_time(), sender_address, &mut election, logger)?;
+    Ok(())
+}
+
+/// VIEW: function anyone can call to get the config of an election
+#[receive(
+    contract = "voting",
+    name = "view_config",
+    parameter = "u64",
+    return_value = "VoteConfig"
+)]
+fn view_config<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<VoteConfig, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(get_election(host, election_id)?.config.clone())
+}
+
+/// VIEW: function anyone can call to get the current voting phase of an election
+#[receive(
+    contract = "voting",
+    name = "view_phase",
+    parameter = "u64",
+    return_value = "types::VotingPhase"
+)]
+fn view_phase<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<types::VotingPhase, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(get_election(host, election_id)?.voting_phase.clone())
+}
+
+/// VIEW: function anyone can call to get the voting keys (g^x) of each question in the order voters registered, needed to compute reconstructed keys
+#[receive(
+    contract = "voting",
+    name = "view_voting_keys",
+    parameter = "u64",
+    return_value = "Vec<Vec<Vec<u8>>>"
+)]
+fn view_voting_keys<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Vec<Vec<Vec<u8>>>, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(get_election(host, election_id)?.voting_keys.clone())
+}
+
+/// VIEW: function anyone can call to get the record of a registered voter of an election
+#[receive(
+    contract = "voting",
+    name = "view_voter",
+    parameter = "(u64, AccountAddress)",
+    return_value = "Voter"
+)]
+fn view_voter<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Voter, types::ViewError> {
+    let (election_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+
+    let election = get_election(host, election_id)?;
+    let voter = match election.voters.get(&account) {
+        Some(v) => v.clone(),
+        None => bail!(types::ViewError::VoterNotFound),
+    };
+    Ok(voter)
+}
+
+/// VIEW: function anyone can call to get the records of all registered voters of an election
+#[receive(
+    contract = "voting",
+    name = "view_voters",
+    parameter = "u64",
+    return_value = "Vec<(AccountAddress, Voter)>"
+)]
+fn view_voters<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Vec<(AccountAddress, Voter)>, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    let election = get_election(host, election_id)?;
+    let voters = election
+        .voters
+        .iter()
+        .map(|(addr, v)| (*addr, v.clone()))
+        .collect();
+    Ok(voters)
+}
+
+/// VIEW: function anyone can call to get the stored voting result of each question of an election (empty until result has been called)
+#[receive(
+    contract = "voting",
+    name = "view_result",
+    parameter = "u64",
+    return_value = "Vec<Vec<i32>>"
+)]
+fn view_result<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Vec<Vec<i32>>, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(get_election(host, election_id)?.voting_result.clone())
+}
+
+/// VIEW: function anyone can call to get the stored outcome of each question of an election (empty until result has been called)
+#[receive(
+    contract = "voting",
+    name = "view_outcome",
+    parameter = "u64",
+    return_value = "Vec<types::VoteOutcome>"
+)]
+fn view_outcome<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Vec<types::VoteOutcome>, types::ViewError> {
+    let election_id: u64 = ctx.parameter_cursor().get()?;
+    Ok(get_election(host, election_id)?.voting_outcome.clone())
+}
+
+/// VIEW: function anyone can call to get the amount an account can withdraw from an election
+#[receive(
+    contract = "voting",
+    name = "view_balance",
+    parameter = "(u64, AccountAddress)",
+    return_value = "Amount"
+)]
+fn view_balance<S: HasStateApi>(
+    ctx: &impl HasReceiveContext,
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+) -> Result<Amount, types::ViewError> {
+    let (election_id, account): (u64, AccountAddress) = ctx.parameter_cursor().get()?;
+
+    let election = get_election(host, election_id)?;
+    let balance = election
+        .balances
+        .get(&account)
+        .map(|balance| *balance)
+        .unwrap_or_else(Amount::zero);
+    Ok(balance)
+}
+
+/// The election with the given id, for the view functions
+fn get_election<S: HasStateApi>(
+    host: &impl HasHost<VotingState<S>, StateApiType = S>,
+    election_id: u64,
+) -> Result<StateRef<'_, Election<S>>, types::ViewError> {
+    match host.state().elections.get(&election_id) {
+        Some(election) => Ok(election),
+        None => bail!(types::ViewError::ElectionNotFound),
+    }
+}
+
+/// Function to move the vote on through every phase whose conditions are met, such as all voters having acted or a timeout having passed.
+/// Called by change_phase and by the voter functions, such that no separate change_phase transaction is needed in the common case.
+/// The sender is rewarded as the change_phase caller if the vote aborts. Returns whether the phase changed
+fn advance_phase<S: HasStateApi>(
+    election_id: u64,
+    now: Timestamp,
+    sender: AccountAddress,
+    election: &mut Election<S>,
+    logger: &mut impl HasLogger,
+) -> Result<bool, types::ChangeError> {
+    let mut changed = false;
+
+    // A late call can apply several phase changes at once, e.g. registration timing out after the commit timeout has passed too
+    loop {
+        let old_phase = election.voting_phase.clone();
+        let mut abort_reason = None;
+
+        match election.voting_phase {
+            types::VotingPhase::Registration => {
+                // Change to commit phase if registration time is over and atleast the minimum number of voters have registered
+                // Note: will move on with the vote without stalling/too slow authorized voters
+                if (now > election.config.registration_timeout
+                    && election.number_of_voters >= election.config.min_registrants)
+                    || election.number_of_voters == election.config.merkle_leaf_count
+                {
+                    election.voting_phase = types::VotingPhase::Commit
+                }
+                // Change to abort if too few voters have registered and time is over
+                else if now > election.config.registration_timeout {
+                    refund_deposits(election_id, sender, election, logger)?;
+                    election.voting_phase = types::VotingPhase::Abort;
+                    abort_reason = Some(types::AbortReason::TooFewRegistered)
+                }
             }
-            // Change to abort if all have not committed and commit time is over
-            else if now > host.state().config.commit_timeout {
-                refund_deposits(sender_address, host)?;
-                host.state_mut().voting_phase = types::VotingPhase::Abort
+            types::VotingPhase::Commit => {
+                // Change to vote phase, if all voters have committed
+                if election.number_of_commitments == election.number_of_voters {
+                    election.voting_phase = types::VotingPhase::Vote
+                }
+                // Change to abort if all have not committed and commit time is over
+                else if now > election.config.commit_timeout {
+                    refund_deposits(election_id, sender, election, logger)?;
+                    election.voting_phase = types::VotingPhase::Abort;
+                    abort_reason = Some(types::AbortReason::NotAllCommitted)
+                }
             }
-        }
-        types::VotingPhase::Vote => {
-            // Change to result phase, if all voters have voted
-            if host
-                .state()
-                .voters
-                .iter()
-                .all(|(_, v)| v.vote != Vec::<u8>::new())
-            {
-                host.state_mut().voting_phase = types::VotingPhase::Result
+            types::VotingPhase::Vote => {
+                // Change to result phase, if all voters have voted
+                if election.number_of_votes == election.number_of_voters {
+                    election.voting_phase = types::VotingPhase::Result
+                }
+                // Change to recovery if vote time is over and atleast 3 voters have voted, such that the tally can be computed without the rest
+                else if now > election.config.vote_timeout && election.number_of_votes > 2 {
+                    let missing_voters = election
+                        .voters
+                        .iter()
+                        .filter(|(_, v)| v.votes.is_empty())
+                        .map(|(_, v)| v.index)
+                        .collect();
+                    election.missing_voters = missing_voters;
+                    election.voting_phase = types::VotingPhase::Recovery
+                }
+                // Change to abort if vote time is over and too few have voted
+                else if now > election.config.vote_timeout {
+                    refund_deposits(election_id, sender, election, logger)?;
+                    election.voting_phase = types::VotingPhase::Abort;
+                    abort_reason = Some(types::AbortReason::TooFewVoted)
+                }
             }
-            // Change to abort if vote time is over and not all have voted
-            else if now > host.state().config.vote_timeout {
-                refund_deposits(sender_address, host)?;
-                host.state_mut().voting_phase = types::VotingPhase::Abort
+            types::VotingPhase::Recovery => {
+                // Change to result phase, if all voters who voted have sent their recovery key
+                if election.number_of_recoveries == election.number_of_votes {
+                    // The voters who did not vote stalled the vote, and their deposits are slashed
+                    let mut honest_accounts = Vec::<AccountAddress>::new();
+                    let mut stalling_accounts = Vec::<AccountAddress>::new();
+                    for (addr, voter) in election.voters.iter() {
+                        if voter.votes.is_empty() {
+                            stalling_accounts.push(*addr);
+                        } else {
+                            honest_accounts.push(*addr);
+                        }
+                    }
+                    let slashed_deposits = stalling_accounts.len() as u64;
+                    slash_deposits(
+                        election_id,
+                        sender,
+                        &honest_accounts,
+                        slashed_deposits,
+                        election,
+                        logger,
+                    )?;
+                    election.voting_phase = types::VotingPhase::Result
+                }
+                // Change to abort if recovery time is over and not all have sent their recovery key
+                else if now > election.config.recovery_timeout {
+                    refund_deposits(election_id, sender, election, logger)?;
+                    election.voting_phase = types::VotingPhase::Abort;
+                    abort_reason = Some(types::AbortReason::NotAllRecovered)
+                }
             }
+            _ => (), // Handles abort, result and cancelled phases which we can't move on from
+        };
+
+        if election.voting_phase == old_phase {
+            return Ok(changed);
         }
-        _ => (), // Handles abort and result phases which we can't move on from
-    };
-    Ok(())
+        changed = true;
+        log_event(logger, election_id, types::VotingEvent::ChangePhase {
+            from: old_phase,
+            to: election.voting_phase.clone(),
+            abort_reason,
+        })?;
+    }
 }
 
-/// Function to refund deposits, in case of the vote aborting. It penalizes stalling/malicious voters, refunds honest and rewards the change_phase caller who found out that we needed to abort
+/// Function to refund deposits, in case of the vote aborting. It refunds honest voters and slashes the deposits of stalling/malicious voters according to the slashing policy, which may reward the change_phase caller who found out that we needed to abort.
+/// In the recovery phase only the voters who did not vote are slashed, since voters who voted were refunded then, and only the voters who sent their recovery key count as honest
+/// Nothing is transferred: refunds and payouts are credited to the balances the accounts withdraw, and logged as Refund, Reward or Burn events
 fn refund_deposits<S: HasStateApi>(
+    election_id: u64,
     sender: AccountAddress,
-    host: &mut impl HasHost<VotingState<S>, StateApiType = S>,
-) -> Result<(), TransferError> {
-    // Number of voters registered for the vote
-    let number_of_voters = host.state().voters.iter().count() as u64;
-
+    election: &mut Election<S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), types::ChangeError> {
     // Get account list of the voters who stalled the vote and the ones who were honest
     let (honest_accounts, stalling_accounts): (Vec<AccountAddress>, Vec<AccountAddress>) =
-        match host.state().voting_phase {
+        match election.voting_phase {
             types::VotingPhase::Registration => {
                 let mut honest_accounts = Vec::<AccountAddress>::new();
                 let mut stalling_accounts = Vec::<AccountAddress>::new();
 
-                for (addr, voter) in host.state().voters.iter() {
-                    if voter.voting_key == Vec::<u8>::new() {
+                for (addr, voter) in election.voters.iter() {
+                    if voter.voting_keys.is_empty() {
                         stalling_accounts.push(*addr);
                     } else {
                         honest_accounts.push(*addr);
@@ -456,8 +1697,8 @@ fn refund_deposits<S: HasStateApi>(
                 let mut honest_accounts = Vec::<AccountAddress>::new();
                 let mut stalling_accounts = Vec::<AccountAddress>::new();
 
-                for (addr, voter) in host.state().voters.iter() {
-                    if voter.commitment == Vec::<u8>::new() {
+                for (addr, voter) in election.voters.iter() {
+                    if voter.commitments.is_empty() {
                         stalling_accounts.push(*addr);
                     } else {
                         honest_accounts.push(*addr);
@@ -469,8 +1710,8 @@ fn refund_deposits<S: HasStateApi>(
                 let mut honest_accounts = Vec::<AccountAddress>::new();
                 let mut stalling_accounts = Vec::<AccountAddress>::new();
 
-                for (addr, voter) in host.state().voters.iter() {
-                    if voter.vote == Vec::<u8>::new() {
+                for (addr, voter) in election.voters.iter() {
+                    if voter.votes.is_empty() {
                         stalling_accounts.push(*addr);
                     } else {
                         honest_accounts.push(*addr);
@@ -478,21 +1719,141 @@ fn refund_deposits<S: HasStateApi>(
                 }
                 (honest_accounts, stalling_accounts)
             }
+            types::VotingPhase::Recovery => {
+                let mut honest_accounts = Vec::<AccountAddress>::new();
+                let mut stalling_accounts = Vec::<AccountAddress>::new();
+
+                // Voters who did not vote stalled the vote and voters who sent their recovery key were honest.
+                // Voters who voted but did not take part in the recovery are neither: their deposit was refunded when voting, so there is nothing to slash
+                for (addr, voter) in election.voters.iter() {
+                    if voter.votes.is_empty() {
+                        stalling_accounts.push(*addr);
+                    } else if !voter.recovery_keys.is_empty() {
+                        honest_accounts.push(*addr);
+                    }
+                }
+                (honest_accounts, stalling_accounts)
+            }
             // Impossible case
             _ => trap(),
         };
 
-    // Reward sender (caller of change_phase) if they are not a stalling voter and there were honest voters
-    if !stalling_accounts.contains(&sender) && number_of_voters - honest_accounts.len() as u64 > 0 {
-        host.invoke_transfer(&sender, host.state().config.deposit)?;
+    // Go through all honest voters and refund their deposit (voters have already been refunded when voting)
+    if election.voting_phase != types::VotingPhase::Vote
+        && election.voting_phase != types::VotingPhase::Recovery
+    {
+        for account in honest_accounts.iter().copied() {
+            let deposit = election.config.deposit;
+            credit(election, account, deposit);
+            log_event(logger, election_id, types::VotingEvent::Refund {
+                account,
+                amount: election.config.deposit,
+            })?;
+        }
+    }
+
+    // The deposits of the stalling voters are still held by the contract and slashed
+    let slashed_deposits = stalling_accounts.len() as u64;
+    slash_deposits(
+        election_id,
+        sender,
+        &honest_accounts,
+        slashed_deposits,
+        election,
+        logger,
+    )
+}
+
+/// Function to pay out the slashed deposits of the stalling voters according to the slashing policy of the vote.
+/// Payouts are credited to balances and logged as Burn or Reward events. What is not paid out, such as the remainder of an uneven split, is left for the organizer to sweep
+fn slash_deposits<S: HasStateApi>(
+    election_id: u64,
+    sender: AccountAddress,
+    honest_accounts: &[AccountAddress],
+    slashed_deposits: u64,
+    election: &mut Election<S>,
+    logger: &mut impl HasLogger,
+) -> Result<(), types::ChangeError> {
+    let slashed = election.config.deposit.micro_ccd * slashed_deposits;
+    if slashed == 0 {
+        return Ok(());
     }
 
-    // Go through all honest voters and refund their deposit
-    if host.state().voting_phase != types::VotingPhase::Vote {
-        for account in honest_accounts {
-            host.invoke_transfer(&account, host.state().config.deposit)?;
+    match election.config.slashing_policy.clone() {
+        types::SlashingPolicy::Burn(sink) => {
+            let amount = Amount::from_micro_ccd(slashed);
+            credit(election, sink, amount);
+            log_event(logger, election_id, types::VotingEvent::Burn {
+                account: sink,
+                amount,
+            })?;
+        }
+        types::SlashingPolicy::SplitAmongHonest => {
+            // Nothing is split if no one was honest
+            if !honest_accounts.is_empty() {
+                let amount = Amount::from_micro_ccd(slashed / honest_accounts.len() as u64);
+                for account in honest_accounts {
+                    credit(election, *account, amount);
+                    log_event(logger, election_id, types::VotingEvent::Reward {
+                        account: *account,
+                        amount,
+                    })?;
+                }
+            }
+        }
+        types::SlashingPolicy::Reporter(fraction) => {
+            // Reward sender (caller of change_phase) if they are an honest voter or not a voter at all,
+            // so a voter who withheld their recovery key can not profit from the abort they caused
+            if honest_accounts.contains(&sender) || election.voters.get(&sender).is_none() {
+                let amount = Amount::from_micro_ccd(
+                    (slashed as u128 * fraction.numerator as u128 / fraction.denominator as u128)
+                        as u64,
+                );
+                credit(election, sender, amount);
+                log_event(logger, election_id, types::VotingEvent::Reward {
+                    account: sender,
+                    amount,
+                })?;
+            }
         }
     }
 
     Ok(())
 }
+
+/// Add the amount to the balance the account can withdraw
+fn credit<S: HasStateApi>(
+    election: &mut Election<S>,
+    account: AccountAddress,
+    amount: Amount,
+) {
+    let mut balance = election
+        .balances
+        .entry(account)
+        .or_insert_with(Amount::zero);
+    *balance += amount;
+    drop(balance);
+    election.total_balance += amount;
+}
+
+/// Log the event, tagged with the election it belongs to
+fn log_event(
+    logger: &mut impl HasLogger,
+    election_id: u64,
+    event: types::VotingEvent,
+) -> Result<(), LogError> {
+    logger.log(&types::ElectionEvent { election_id, event })
+}
+
+/// The context the ZKPs of the sender are bound to, such that they can not be replayed in another election
+fn proof_context(
+    ctx: &impl HasReceiveContext,
+    election_id: u64,
+    sender: AccountAddress,
+) -> util::ProofContext {
+    util::ProofContext {
+        contract_address: ctx.self_address(),
+        election_id,
+        sender,
+    }
+}
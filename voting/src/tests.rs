@@ -6,61 +6,89 @@ pub mod test_utils;
 #[concordium_cfg_test]
 mod tests {
     use super::*;
+    use test_infrastructure::*;
     use group::GroupEncoding;
     use k256::ProjectivePoint;
 
     #[concordium_test]
     fn test_setup() {
+        let mut logger = TestLogger::init();
         let (_, vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
 
-        let vote_config_bytes = to_bytes(&vote_config);
-        let ctx = test_utils::setup_init_context(&vote_config_bytes);
-
-        // Setup the state of the contract
-        let (_state, mut state_builder) = test_utils::setup_state(
-            &Vec::<AccountAddress>::new(),
-            vote_config,
-            types::VotingPhase::Registration,
-        );
-
-        let result = setup(&ctx, &mut state_builder);
-        let state = match result {
+        // Setup the contract, which starts without elections
+        let init_ctx = test_utils::setup_init_context();
+        let mut state_builder = TestStateBuilder::new();
+        let state = match setup(&init_ctx, &mut state_builder) {
             Ok(s) => s,
             Err(e) => fail!("Setup failed: {:?}", e),
         };
+        claim_eq!(
+            state.elections.iter().count(),
+            0,
+            "Contract should start without elections"
+        );
+
+        let vote_config_bytes = to_bytes(&vote_config);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_config_bytes),
+            test_utils::ORGANIZER,
+            state,
+            state_builder,
+        );
+
+        let result = create_election(&ctx, &mut host, &mut logger);
+        claim_eq!(result, Ok(0), "First election should have id 0");
 
+        let election = test_utils::election(&host);
         claim_eq!(
-            state.config.deposit,
+            election.organizer,
+            test_utils::ORGANIZER,
+            "Creator should be the organizer of the election"
+        );
+        claim_eq!(
+            election.config.deposit,
             Amount::from_micro_ccd(0),
             "Deposit should be 0"
         );
         claim_eq!(
-            state.config.voting_question,
-            "Vote for x".to_string(),
-            "Voting question should be: Vote for x"
+            election.config.voting_questions,
+            vec!["Vote for x".to_string()],
+            "Voting questions should be: Vote for x"
         );
 
         claim_eq!(
-            state.voting_phase,
+            election.voting_phase,
             types::VotingPhase::Registration,
             "types::VotingPhase should be Registration"
         );
 
         claim_eq!(
-            state.voting_result,
-            (-1, -1),
-            "Voting result should be -1, since voting is not done"
+            election.voting_result,
+            Vec::<Vec<i32>>::new(),
+            "Voting result should be empty, since voting is not done"
         );
 
         claim_eq!(
-            state.voters.iter().count(),
+            election.voters.iter().count(),
             0,
             "Registered voters map should be empty"
         );
+        drop(election);
+
+        claim_eq!(
+            logger.logs,
+            vec![test_utils::event(types::VotingEvent::Setup {
+                voting_questions: vec!["Vote for x".to_string()],
+                merkle_leaf_count: 3,
+                deposit: Amount::from_micro_ccd(0),
+            })],
+            "Setup event should have been logged"
+        );
     }
 
     #[concordium_test]
     fn test_register() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, merkle_tree) =
             test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
 
@@ -72,9 +100,10 @@ mod tests {
         let (x, g_x) = off_chain::create_votingkey_pair();
 
         let register_message = RegisterMessage {
-            voting_key: g_x.to_bytes().to_vec(),
-            voting_key_zkp: off_chain::create_schnorr_zkp(g_x, x),
-            merkle_proof: off_chain::create_merkle_proof(accounts[0], &merkle_tree),
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &test_utils::proof_context(accounts[0]))],
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
         };
 
         let register_message_bytes = to_bytes(&register_message);
@@ -86,7 +115,7 @@ mod tests {
             state_builder,
         );
 
-        let result = register(&ctx, &mut host, Amount::from_micro_ccd(0));
+        let result = register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger);
 
         claim_ne!(
             result,
@@ -99,24 +128,37 @@ mod tests {
             "Contract receive failed, but should not have"
         );
 
-        let voter1 = match host.state().voters.get(&accounts[0]) {
+        let election = test_utils::election(&host);
+        let voter1 = match election.voters.get(&accounts[0]) {
             Some(v) => v,
             None => fail!("Voter 1 should exist"),
         };
         claim_ne!(
-            voter1.voting_key,
-            Vec::<u8>::new(),
+            voter1.voting_keys,
+            Vec::<Vec<u8>>::new(),
             "Voter 1 should have a registered voting key"
         );
         claim_eq!(
-            host.state().voters.iter().count(),
+            test_utils::election(&host).voters.iter().count(),
             1,
             "Length of voters should be 1"
         );
+
+        claim_eq!(
+            logger.logs,
+            vec![test_utils::event(types::VotingEvent::Register {
+                account: accounts[0],
+                index: 0,
+                voting_keys: vec![g_x.to_bytes().to_vec()],
+                weight: 1,
+            })],
+            "Register event should have been logged"
+        );
     }
 
     #[concordium_test]
     fn test_register_unauthorized_voter() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, merkle_tree) =
             test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
 
@@ -131,10 +173,15 @@ mod tests {
         let (x2, g_x2) = off_chain::create_votingkey_pair();
 
         let register_message2 = RegisterMessage {
-            voting_key: g_x2.to_bytes().to_vec(),
-            voting_key_zkp: off_chain::create_schnorr_zkp(g_x2, x2),
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x2.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(
+                g_x2,
+                x2,
+                &test_utils::proof_context(voter2),
+            )],
             // Unauthorized voter creates a malicious proof as another voter (account 0)
-            merkle_proof: off_chain::create_merkle_proof(accounts[0], &merkle_tree),
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
         };
 
         let register_message_bytes2 = to_bytes(&register_message2);
@@ -146,7 +193,7 @@ mod tests {
             state_builder,
         );
 
-        let result = register(&ctx, &mut host, Amount::from_micro_ccd(0));
+        let result = register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger);
 
         // Proof should not work, since the hash of the register caller is matched with the leaf to prove
         claim_eq!(
@@ -155,14 +202,152 @@ mod tests {
             "Voter should be unauthorized"
         );
         claim_eq!(
-            host.state().voters.iter().count(),
+            test_utils::election(&host).voters.iter().count(),
             0,
             "Length of voters should be 0"
         );
     }
 
+    #[concordium_test]
+    fn test_register_proof_bound_to_context() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let context = test_utils::proof_context(accounts[1]);
+        let schnorr = off_chain::create_schnorr_zkp(g_x, x, &context);
+
+        claim_eq!(
+            crypto::verify_schnorr_zkp(g_x, schnorr.clone(), &context),
+            Ok(true),
+            "Proof should verify in the context it was created in"
+        );
+        claim_eq!(
+            crypto::verify_schnorr_zkp(
+                g_x,
+                schnorr.clone(),
+                &util::ProofContext {
+                    contract_address: ContractAddress {
+                        index: 1,
+                        subindex: 0
+                    },
+                    ..context
+                }
+            ),
+            Ok(false),
+            "Proof should not verify on another contract instance"
+        );
+        claim_eq!(
+            crypto::verify_schnorr_zkp(
+                g_x,
+                schnorr.clone(),
+                &util::ProofContext {
+                    election_id: 1,
+                    ..context
+                }
+            ),
+            Ok(false),
+            "Proof should not verify in another election"
+        );
+
+        // Account 0 copies the registration proof of account 1
+        let register_message = RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![schnorr],
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+        };
+        let register_message_bytes = to_bytes(&register_message);
+
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&register_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        let result = register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger);
+
+        claim_eq!(
+            result,
+            Err(types::RegisterError::InvalidZKP),
+            "Proof created for another account should be rejected"
+        );
+    }
+
+    #[concordium_test]
+    fn test_register_malformed_message() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(2, Amount::from_micro_ccd(0));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let context = test_utils::proof_context(accounts[0]);
+
+        // Malformed proofs, keys and merkle proofs are rejected rather than trapping
+        let malformed_zkp_bytes = to_bytes(&RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![SchnorrProof {
+                g_w: vec![1, 2, 3],
+                r: Vec::new(),
+            }],
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+        });
+        let malformed_key_bytes = to_bytes(&RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![vec![5; 33]],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &context)],
+            merkle_proof: off_chain::create_merkle_proof(accounts[1], 1, &merkle_tree),
+        });
+        let mut merkle_proof = off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree);
+        merkle_proof.proof = vec![1, 2, 3];
+        let malformed_merkle_proof_bytes = to_bytes(&RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &context)],
+            merkle_proof,
+        });
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&malformed_merkle_proof_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Err(types::RegisterError::UnauthorizedVoter),
+            "Malformed merkle proof should be rejected"
+        );
+
+        ctx.set_parameter(&malformed_zkp_bytes);
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Err(types::RegisterError::InvalidZKP),
+            "Malformed ZKP should be rejected"
+        );
+
+        ctx.set_parameter(&malformed_key_bytes);
+        ctx.set_sender(Address::Account(accounts[1]));
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Err(types::RegisterError::InvalidVotingKey),
+            "Malformed voting key should be rejected"
+        );
+    }
+
     #[concordium_test]
     fn test_change_phase() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
 
@@ -177,30 +362,34 @@ mod tests {
         let (x2, g_x2) = off_chain::create_votingkey_pair();
         let (x3, g_x3) = off_chain::create_votingkey_pair();
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                voting_key: g_x1.to_bytes().to_vec(),
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                voting_key: g_x2.to_bytes().to_vec(),
+                voting_keys: vec![g_x2.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                voting_key: g_x3.to_bytes().to_vec(),
+                voting_keys: vec![g_x3.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
+        test_utils::count_voters(&mut test_utils::election_mut(&mut host));
 
         // Testing that the phase does not change when time has not passed registration timeout
-        let result = change_phase(&ctx, &mut host);
+        let result = change_phase(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -208,7 +397,7 @@ mod tests {
         );
 
         claim_eq!(
-            host.state().voting_phase,
+            test_utils::election(&host).voting_phase,
             types::VotingPhase::Registration,
             "Changed phase but should not have since time is not beyond registration timeout"
         );
@@ -217,7 +406,7 @@ mod tests {
         ctx.metadata_mut()
             .set_slot_time(Timestamp::from_timestamp_millis(101));
 
-        let result = change_phase(&ctx, &mut host);
+        let result = change_phase(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -225,7 +414,7 @@ mod tests {
         );
 
         claim_eq!(
-            host.state().voting_phase,
+            test_utils::election(&host).voting_phase,
             types::VotingPhase::Commit,
             "Did not change from registration to commit"
         );
@@ -234,7 +423,7 @@ mod tests {
         ctx.metadata_mut()
             .set_slot_time(Timestamp::from_timestamp_millis(201));
 
-        let result = change_phase(&ctx, &mut host);
+        let result = change_phase(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -242,13 +431,30 @@ mod tests {
         );
 
         claim_eq!(
-            host.state().voting_phase,
+            test_utils::election(&host).voting_phase,
             types::VotingPhase::Abort,
             "Should change to abort phase since no one comitted"
         );
 
+        claim_eq!(
+            logger.logs,
+            vec![
+                test_utils::event(types::VotingEvent::ChangePhase {
+                    from: types::VotingPhase::Registration,
+                    to: types::VotingPhase::Commit,
+                    abort_reason: None,
+                }),
+                test_utils::event(types::VotingEvent::ChangePhase {
+                    from: types::VotingPhase::Commit,
+                    to: types::VotingPhase::Abort,
+                    abort_reason: Some(types::AbortReason::NotAllCommitted),
+                }),
+            ],
+            "Only the two phase changes should have been logged"
+        );
+
         // Testing that phase changes from commit to vote, if all voters have reconstructed keys and commitments.
-        host.state_mut().voting_phase = types::VotingPhase::Commit;
+        test_utils::election_mut(&mut host).voting_phase = types::VotingPhase::Commit;
 
         let keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone()];
 
@@ -261,35 +467,39 @@ mod tests {
         let commitment2 = off_chain::commit_to_vote(&x2, &g_y2, g_v);
         let commitment3 = off_chain::commit_to_vote(&x3, &g_y3, g_v);
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: commitment1,
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![commitment1],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: commitment2,
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![commitment2],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: commitment3,
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![commitment3],
+                weight: 1,
                 ..Default::default()
             },
         );
+        test_utils::count_voters(&mut test_utils::election_mut(&mut host));
 
         ctx.metadata_mut()
             .set_slot_time(Timestamp::from_timestamp_millis(201));
 
-        let result = change_phase(&ctx, &mut host);
+        let result = change_phase(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -297,38 +507,44 @@ mod tests {
         );
 
         claim_eq!(
-            host.state().voting_phase,
+            test_utils::election(&host).voting_phase,
             types::VotingPhase::Vote,
             "Should change to abort phase since no one comitted"
         );
 
         // Testing that phase changes from vote to result if all voted
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                vote: g_v.to_bytes().to_vec(),
+                votes: vec![g_v.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                vote: g_v.to_bytes().to_vec(),
+                votes: vec![g_v.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                vote: g_v.to_bytes().to_vec(),
+                votes: vec![g_v.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
 
+        test_utils::election_mut(&mut host).voting_keys = vec![keys.iter().map(|k| k.to_bytes().to_vec()).collect()];
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
         ctx.metadata_mut()
             .set_slot_time(Timestamp::from_timestamp_millis(301));
 
-        let result = change_phase(&ctx, &mut host);
+        let result = change_phase(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -336,169 +552,688 @@ mod tests {
         );
 
         claim_eq!(
-            host.state().voting_phase,
+            test_utils::election(&host).voting_phase,
             types::VotingPhase::Result,
             "Phase should have changed to result"
         )
     }
 
     #[concordium_test]
-    fn test_commit() {
-        let (accounts, vote_config, _) =
-            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+    fn test_change_phase_min_registrants() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(5, Amount::from_micro_ccd(0));
+        vote_config.min_registrants = 4;
 
-        // Create pk, sk pair of g^x and x for accounts
-        let (x1, g_x1) = off_chain::create_votingkey_pair();
-        let (x2, g_x2) = off_chain::create_votingkey_pair();
-        let (x3, g_x3) = off_chain::create_votingkey_pair();
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
 
-        // Compute reconstructed key
-        let keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone()];
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
 
-        let g_y1 = util::compute_reconstructed_key(&keys, 0);
-        let g_y2 = util::compute_reconstructed_key(&keys, 1);
-        let g_y3 = util::compute_reconstructed_key(&keys, 2);
+        // Simulate that 3 of the 5 voters have registered, fewer than the minimum of 4
+        for account in accounts.iter().take(3) {
+            test_utils::election_mut(&mut host).voters.insert(
+                *account,
+                Voter {
+                    voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+        test_utils::count_voters(&mut test_utils::election_mut(&mut host));
 
-        let g_v = ProjectivePoint::GENERATOR;
-        let commitment = off_chain::commit_to_vote(&x1, &g_y1, g_v);
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(101));
 
-        let commitment_message = CommitMessage {
-            reconstructed_key: g_y1.to_bytes().to_vec(),
-            commitment,
-        };
-        let commitment_message_bytes = to_bytes(&commitment_message);
+        claim_eq!(
+            change_phase(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Abort,
+            "Should abort since fewer than the minimum number of voters registered"
+        );
+        claim_eq!(
+            logger.logs.last(),
+            Some(&test_utils::event(types::VotingEvent::ChangePhase {
+                from: types::VotingPhase::Registration,
+                to: types::VotingPhase::Abort,
+                abort_reason: Some(types::AbortReason::TooFewRegistered),
+            })),
+            "Abort should have been logged with its reason"
+        );
+    }
+
+    #[concordium_test]
+    fn test_voter_applies_timeout() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
 
         let (state, state_builder) =
-            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Commit);
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        // Voter 4 registers after the registration timeout
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let register_message = RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &test_utils::proof_context(accounts[3]))],
+            merkle_proof: off_chain::create_merkle_proof(accounts[3], 1, &merkle_tree),
+        };
+        let register_message_bytes = to_bytes(&register_message);
 
         let (mut ctx, mut host) = test_utils::setup_receive_context(
-            Some(&commitment_message_bytes),
-            accounts[0],
+            Some(&register_message_bytes),
+            accounts[3],
             state,
             state_builder,
         );
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
+
+        // Simulate that the other 3 voters have registered
+        for account in accounts.iter().take(3) {
+            test_utils::election_mut(&mut host).voters.insert(
+                *account,
+                Voter {
+                    voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+        test_utils::count_voters(&mut test_utils::election_mut(&mut host));
 
-        // Set voting_keys that would have been pushed to state in register
-        host.state_mut().voting_keys = vec![
-            g_x1.to_bytes().to_vec(),
-            g_x2.to_bytes().to_vec(),
-            g_x3.to_bytes().to_vec(),
-        ];
-
-        // Set voter's voting keys in their structs
-        host.state_mut()
-            .voters
-            .get_mut(&accounts[0])
-            .unwrap()
-            .voting_key = g_x1.to_bytes().to_vec();
-        host.state_mut()
-            .voters
-            .get_mut(&accounts[1])
-            .unwrap()
-            .voting_key = g_x2.to_bytes().to_vec();
-        host.state_mut()
-            .voters
-            .get_mut(&accounts[2])
-            .unwrap()
-            .voting_key = g_x3.to_bytes().to_vec();
-
-            host.state_mut()
-            .voters
-            .get_mut(&accounts[0])
-            .unwrap()
-            .index = 0;
-        host.state_mut()
-            .voters
-            .get_mut(&accounts[1])
-            .unwrap()
-            .index = 1;
-        host.state_mut()
-            .voters
-            .get_mut(&accounts[2])
-            .unwrap()
-            .index = 2;
-
-        let result = commit(&ctx, &mut host);
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(101));
 
-        claim!(
-            result.is_ok(),
-            "Contract receive failed, but should not have"
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(1), &mut logger),
+            Ok(None),
+            "Late registration should only apply the phase change"
         );
-
-        let voter1 = match host.state().voters.get(&accounts[0]) {
-            Some(v) => v,
-            None => fail!("Voter 1 should exist"),
-        };
-        claim_ne!(
-            voter1.reconstructed_key,
-            Vec::<u8>::new(),
-            "Voter 1 should have a registered reconstructed key"
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Commit,
+            "Registration timeout should have moved the vote on to the commit phase"
         );
-        claim_ne!(
-            voter1.commitment,
-            Vec::<u8>::new(),
-            "Voter 1 should have a committed to a vote"
+        claim!(
+            test_utils::election(&host).voters.get(&accounts[3]).is_none(),
+            "Late voter should not have been registered"
+        );
+        claim_eq!(
+            test_utils::balance(&host, &accounts[3]),
+            Amount::from_micro_ccd(1),
+            "Deposit of the late voter should be withdrawable"
         );
 
-        // Test function briefly for other 2 accounts
-        let commitment = off_chain::commit_to_vote(&x2, &g_y2, g_v);
-
+        // Voter 1 commits after the commit timeout, while no one has committed
         let commitment_message = CommitMessage {
-            reconstructed_key: g_y2.to_bytes().to_vec(),
-            commitment,
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_x.to_bytes().to_vec()],
+            commitments: vec![vec![1]],
         };
         let commitment_message_bytes = to_bytes(&commitment_message);
-
         ctx.set_parameter(&commitment_message_bytes);
-        ctx.set_sender(Address::Account(accounts[1]));
+        ctx.set_sender(Address::Account(accounts[0]));
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(201));
 
-        let result = commit(&ctx, &mut host);
+        claim_eq!(
+            commit(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Late commit should only apply the phase change"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Abort,
+            "Commit timeout should have aborted the vote"
+        );
+        claim_eq!(
+            test_utils::election(&host).voters.get(&accounts[0]).unwrap().commitments,
+            Vec::<Vec<u8>>::new(),
+            "Late commitment should not have been stored"
+        );
 
-        claim!(
-            result.is_ok(),
-            "Contract receive failed, but should not have"
+        claim_eq!(
+            logger.logs,
+            vec![
+                test_utils::event(types::VotingEvent::ChangePhase {
+                    from: types::VotingPhase::Registration,
+                    to: types::VotingPhase::Commit,
+                    abort_reason: None,
+                }),
+                test_utils::event(types::VotingEvent::Refund {
+                    account: accounts[3],
+                    amount: Amount::from_micro_ccd(1),
+                }),
+                test_utils::event(types::VotingEvent::ChangePhase {
+                    from: types::VotingPhase::Commit,
+                    to: types::VotingPhase::Abort,
+                    abort_reason: Some(types::AbortReason::NotAllCommitted),
+                }),
+            ],
+            "Phase changes and the refund should have been logged"
         );
+    }
 
-        let commitment = off_chain::commit_to_vote(&x3, &g_y3, g_v);
+    #[concordium_test]
+    fn test_cancel() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
 
-        let commitment_message = CommitMessage {
-            reconstructed_key: g_y3.to_bytes().to_vec(),
-            commitment,
-        };
-        let commitment_message_bytes = to_bytes(&commitment_message);
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
 
-        ctx.set_parameter(&commitment_message_bytes);
-        ctx.set_sender(Address::Account(accounts[2]));
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(2));
+
+        // Simulate that 2 voters have registered
+        for account in accounts.iter().take(2) {
+            test_utils::election_mut(&mut host).voters.insert(
+                *account,
+                Voter {
+                    voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
 
-        let result = commit(&ctx, &mut host);
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Err(types::CancelError::NotOrganizer),
+            "Only the organizer should cancel"
+        );
 
-        claim!(
-            result.is_ok(),
+        // The organizer can not cancel once registration has timed out
+        ctx.set_sender(Address::Account(test_utils::ORGANIZER));
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(101));
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Err(types::CancelError::NotRegistrationPhase),
+            "Should not cancel after the registration timeout"
+        );
+
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(100));
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Ok(()),
             "Contract receive failed, but should not have"
         );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Cancelled,
+            "Election should have been cancelled"
+        );
+        for account in accounts.iter().take(2) {
+            claim_eq!(
+                test_utils::balance(&host, account),
+                Amount::from_micro_ccd(1),
+                "Registered voters should have been refunded"
+            );
+        }
+        claim_eq!(
+            logger.logs.last(),
+            Some(&test_utils::event(types::VotingEvent::Cancel {
+                organizer: test_utils::ORGANIZER,
+            })),
+            "Cancel should have been logged"
+        );
 
-        // All have committed, check change phase moves to vote
-        let _ = change_phase(&ctx, &mut host);
+        // A cancelled election does not move on, and can not be cancelled again
+        claim_eq!(
+            change_phase(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Cancelled,
+            "Cancelled election should stay cancelled"
+        );
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Err(types::CancelError::NotRegistrationPhase),
+            "Should not cancel twice"
+        );
 
+        // The organizer can not cancel after registration
+        test_utils::election_mut(&mut host).voting_phase = types::VotingPhase::Commit;
         claim_eq!(
-            host.state().voting_phase,
-            types::VotingPhase::Vote,
-            "Should be voting phase since all committed"
-        )
+            cancel(&ctx, &mut host, &mut logger),
+            Err(types::CancelError::NotRegistrationPhase),
+            "Should not cancel in the commit phase"
+        );
     }
 
     #[concordium_test]
-    fn test_commit_with_stolen_reconstructed_key() {
+    fn test_extend_timeout() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
 
-        // Create pk, sk pair of g^x and x for accounts
-        let (x1, g_x1) = off_chain::create_votingkey_pair();
-        let (x2, g_x2) = off_chain::create_votingkey_pair();
-        let (x3, g_x3) = off_chain::create_votingkey_pair();
+        let extend_message = |phase, timeout| {
+            to_bytes(&ExtendTimeoutMessage {
+                election_id: test_utils::ELECTION_ID,
+                phase,
+                timeout: Timestamp::from_timestamp_millis(timeout),
+            })
+        };
 
-        // Compute reconstructed key
-        let keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone()];
+        // Invalid extensions (the commit phase is open and its timeout is 200, the vote timeout is 300)
+        let invalid_extensions = [
+            (extend_message(types::VotingPhase::Registration, 150), types::ExtendError::PhaseClosed),
+            (extend_message(types::VotingPhase::Result, 500), types::ExtendError::PhaseClosed),
+            (extend_message(types::VotingPhase::Commit, 150), types::ExtendError::NotLater),
+            (extend_message(types::VotingPhase::Commit, 200), types::ExtendError::NotLater),
+            (extend_message(types::VotingPhase::Commit, 300), types::ExtendError::InvalidTimeout),
+        ];
+        let extend_vote = extend_message(types::VotingPhase::Vote, 350);
+        let extend_commit = extend_message(types::VotingPhase::Commit, 300);
+        let extend_commit_late = extend_message(types::VotingPhase::Commit, 320);
+        let extend_vote_by_voter = extend_message(types::VotingPhase::Vote, 360);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Commit);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, test_utils::ORGANIZER, state, state_builder);
+
+        for (message, error) in invalid_extensions.iter() {
+            ctx.set_parameter(message);
+            claim_eq!(
+                extend_timeout(&ctx, &mut host, &mut logger).err().as_ref(),
+                Some(error),
+                "Timeout should not have been extended"
+            );
+        }
+
+        // The commit timeout can be extended up to the vote timeout, after extending that first
+        ctx.set_parameter(&extend_vote);
+        claim_eq!(
+            extend_timeout(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        ctx.set_parameter(&extend_commit);
+        claim_eq!(
+            extend_timeout(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).config.commit_timeout,
+            Timestamp::from_timestamp_millis(300),
+            "Commit timeout should have been extended"
+        );
+        claim_eq!(
+            test_utils::election(&host).config.vote_timeout,
+            Timestamp::from_timestamp_millis(350),
+            "Vote timeout should have been extended"
+        );
+
+        // A phase that has timed out is closed, even if the phase has not been changed yet
+        ctx.set_parameter(&extend_commit_late);
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(301));
+        claim_eq!(
+            extend_timeout(&ctx, &mut host, &mut logger),
+            Err(types::ExtendError::PhaseClosed),
+            "Timed out phase should not be extended"
+        );
+
+        ctx.set_parameter(&extend_vote_by_voter);
+        ctx.set_sender(Address::Account(accounts[0]));
+        claim_eq!(
+            extend_timeout(&ctx, &mut host, &mut logger),
+            Err(types::ExtendError::NotOrganizer),
+            "Only the organizer should extend timeouts"
+        );
+
+        claim_eq!(
+            logger.logs,
+            vec![
+                test_utils::event(types::VotingEvent::ExtendTimeout {
+                    phase: types::VotingPhase::Vote,
+                    timeout: Timestamp::from_timestamp_millis(350),
+                }),
+                test_utils::event(types::VotingEvent::ExtendTimeout {
+                    phase: types::VotingPhase::Commit,
+                    timeout: Timestamp::from_timestamp_millis(300),
+                }),
+            ],
+            "Only the two extensions should have been logged"
+        );
+    }
+
+    #[concordium_test]
+    fn test_multiple_elections() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, merkle_tree) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+        let other_organizer = AccountAddress([7u8; 32]);
+
+        // A second election with the same voters and a higher deposit
+        let mut other_vote_config = vote_config.clone();
+        other_vote_config.deposit = Amount::from_micro_ccd(2);
+
+        // Voter 1 registers for the second election, with a ZKP bound to it
+        let (x, g_x) = off_chain::create_votingkey_pair();
+        let other_context = util::ProofContext {
+            contract_address: test_utils::CONTRACT_ADDRESS,
+            election_id: 1,
+            sender: accounts[0],
+        };
+        let register_message = RegisterMessage {
+            election_id: 1,
+            voting_keys: vec![g_x.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &other_context)],
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+        };
+        let register_bytes = to_bytes(&register_message);
+        // The same registration replayed in the first election
+        let replayed_bytes = to_bytes(&RegisterMessage {
+            election_id: 0,
+            ..register_message
+        });
+
+        let vote_config_bytes = to_bytes(&vote_config);
+        let other_vote_config_bytes = to_bytes(&other_vote_config);
+        let other_election_bytes = to_bytes(&1u64);
+        let missing_election_bytes = to_bytes(&2u64);
+
+        let (state, state_builder) = test_utils::setup_empty_state();
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_config_bytes),
+            test_utils::ORGANIZER,
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            create_election(&ctx, &mut host, &mut logger),
+            Ok(0),
+            "First election should have id 0"
+        );
+        ctx.set_sender(Address::Account(other_organizer));
+        ctx.set_parameter(&other_vote_config_bytes);
+        claim_eq!(
+            create_election(&ctx, &mut host, &mut logger),
+            Ok(1),
+            "Second election should have id 1"
+        );
+
+        ctx.set_sender(Address::Account(accounts[0]));
+        ctx.set_parameter(&register_bytes);
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(2), &mut logger),
+            Ok(Some(0)),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            logger.logs.last(),
+            Some(&to_bytes(&types::ElectionEvent {
+                election_id: 1,
+                event: types::VotingEvent::Register {
+                    account: accounts[0],
+                    index: 0,
+                    voting_keys: vec![g_x.to_bytes().to_vec()],
+                    weight: 1,
+                },
+            })),
+            "Register event should have been logged for the second election"
+        );
+
+        // Voters, keys and deposits are kept per election
+        {
+            let election = test_utils::election(&host);
+            let other_election = host.state().elections.get(&1).unwrap();
+            claim_eq!(
+                election.voters.iter().count(),
+                0,
+                "First election should have no voters"
+            );
+            claim_eq!(
+                other_election.voting_keys,
+                vec![vec![g_x.to_bytes().to_vec()]],
+                "Second election should have the voting key"
+            );
+            claim_eq!(election.funds, Amount::zero(), "First election should hold nothing");
+            claim_eq!(
+                other_election.funds,
+                Amount::from_micro_ccd(2),
+                "Second election should hold the deposit"
+            );
+        }
+
+        // A ZKP bound to the second election is not valid in the first
+        ctx.set_parameter(&replayed_bytes);
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(1), &mut logger),
+            Err(types::RegisterError::InvalidZKP),
+            "ZKP of another election should be rejected"
+        );
+
+        // The deposit can not be withdrawn from the other election
+        ctx.set_parameter(&[0u8; 8]);
+        claim_eq!(
+            withdraw(&ctx, &mut host, &mut logger),
+            Err(types::WithdrawError::NothingToWithdraw),
+            "Deposit of the second election should not be withdrawn from the first"
+        );
+
+        // Each election has its own organizer and phase
+        ctx.set_sender(Address::Account(test_utils::ORGANIZER));
+        ctx.set_parameter(&other_election_bytes);
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Err(types::CancelError::NotOrganizer),
+            "Organizer of the first election should not cancel the second"
+        );
+        ctx.set_parameter(&[0u8; 8]);
+        claim_eq!(
+            cancel(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Cancelled,
+            "First election should have been cancelled"
+        );
+        claim_eq!(
+            host.state().elections.get(&1).unwrap().voting_phase,
+            types::VotingPhase::Registration,
+            "Second election should still be in registration"
+        );
+
+        ctx.set_parameter(&missing_election_bytes);
+        claim_eq!(
+            change_phase(&ctx, &mut host, &mut logger),
+            Err(types::ChangeError::ElectionNotFound),
+            "Should not find an election that was not created"
+        );
+    }
+
+    #[concordium_test]
+    fn test_commit() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (x2, g_x2) = off_chain::create_votingkey_pair();
+        let (x3, g_x3) = off_chain::create_votingkey_pair();
+
+        // Compute reconstructed key
+        let keys = vec![g_x1, g_x2, g_x3];
+
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+        let g_y2 = util::compute_reconstructed_key(&keys, 1);
+        let g_y3 = util::compute_reconstructed_key(&keys, 2);
+
+        let g_v = ProjectivePoint::GENERATOR;
+        let commitment = off_chain::commit_to_vote(&x1, &g_y1, g_v);
+
+        let commitment_message = CommitMessage {
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+            commitments: vec![commitment],
+        };
+        let commitment_message_bytes = to_bytes(&commitment_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Commit);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&commitment_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        // Set voting_keys that would have been pushed to state in register
+        test_utils::election_mut(&mut host).voting_keys = vec![vec![
+            g_x1.to_bytes().to_vec(),
+            g_x2.to_bytes().to_vec(),
+            g_x3.to_bytes().to_vec(),
+        ]];
+
+        // Set voter's voting keys in their structs
+        test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[0])
+            .unwrap()
+            .voting_keys = vec![g_x1.to_bytes().to_vec()];
+        test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[1])
+            .unwrap()
+            .voting_keys = vec![g_x2.to_bytes().to_vec()];
+        test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[2])
+            .unwrap()
+            .voting_keys = vec![g_x3.to_bytes().to_vec()];
+
+            test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[0])
+            .unwrap()
+            .index = 0;
+        test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[1])
+            .unwrap()
+            .index = 1;
+        test_utils::election_mut(&mut host)
+            .voters
+            .get_mut(&accounts[2])
+            .unwrap()
+            .index = 2;
+
+        let result = commit(&ctx, &mut host, &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        let election = test_utils::election(&host);
+        let voter1 = match election.voters.get(&accounts[0]) {
+            Some(v) => v,
+            None => fail!("Voter 1 should exist"),
+        };
+        claim_ne!(
+            voter1.reconstructed_keys,
+            Vec::<Vec<u8>>::new(),
+            "Voter 1 should have a registered reconstructed key"
+        );
+        claim_ne!(
+            voter1.commitments,
+            Vec::<Vec<u8>>::new(),
+            "Voter 1 should have a committed to a vote"
+        );
+
+        // Test function briefly for other 2 accounts
+        let commitment = off_chain::commit_to_vote(&x2, &g_y2, g_v);
+
+        let commitment_message = CommitMessage {
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+            commitments: vec![commitment],
+        };
+        let commitment_message_bytes = to_bytes(&commitment_message);
+
+        ctx.set_parameter(&commitment_message_bytes);
+        ctx.set_sender(Address::Account(accounts[1]));
+
+        let result = commit(&ctx, &mut host, &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        let commitment = off_chain::commit_to_vote(&x3, &g_y3, g_v);
+
+        let commitment_message = CommitMessage {
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+            commitments: vec![commitment],
+        };
+        let commitment_message_bytes = to_bytes(&commitment_message);
+
+        ctx.set_parameter(&commitment_message_bytes);
+        ctx.set_sender(Address::Account(accounts[2]));
+
+        let result = commit(&ctx, &mut host, &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        // All have committed, check the last commit moved the vote on to the vote phase
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Vote,
+            "Should be voting phase since all committed"
+        );
+        claim_eq!(
+            logger.logs.last(),
+            Some(&test_utils::event(types::VotingEvent::ChangePhase {
+                from: types::VotingPhase::Commit,
+                to: types::VotingPhase::Vote,
+                abort_reason: None,
+            })),
+            "Phase change should have been logged"
+        )
+    }
+
+    #[concordium_test]
+    fn test_commit_with_stolen_reconstructed_key() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (x2, g_x2) = off_chain::create_votingkey_pair();
+        let (x3, g_x3) = off_chain::create_votingkey_pair();
+
+        // Compute reconstructed key
+        let keys = vec![g_x1, g_x2, g_x3];
 
         let g_y1 = util::compute_reconstructed_key(&keys, 0);
         let g_y2 = util::compute_reconstructed_key(&keys, 1);
@@ -510,8 +1245,9 @@ mod tests {
         let commitment = off_chain::commit_to_vote(&x1, &g_y1, g_v);
 
         let commitment_message = CommitMessage {
-            reconstructed_key: g_y1.to_bytes().to_vec(),
-            commitment,
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+            commitments: vec![commitment],
         };
         let commitment_message_bytes = to_bytes(&commitment_message);
 
@@ -526,73 +1262,75 @@ mod tests {
         );
 
         // Set voting_keys that would have been pushed to state in register
-        host.state_mut().voting_keys = vec![
+        test_utils::election_mut(&mut host).voting_keys = vec![vec![
             g_x1.to_bytes().to_vec(),
             g_x2.to_bytes().to_vec(),
             g_x3.to_bytes().to_vec(),
-        ];
+        ]];
 
         // Set voter's voting keys in their structs
-        host.state_mut()
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[0])
             .unwrap()
-            .voting_key = g_x1.to_bytes().to_vec();
-        host.state_mut()
+            .voting_keys = vec![g_x1.to_bytes().to_vec()];
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[1])
             .unwrap()
-            .voting_key = g_x2.to_bytes().to_vec();
-        host.state_mut()
+            .voting_keys = vec![g_x2.to_bytes().to_vec()];
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[2])
             .unwrap()
-            .voting_key = g_x3.to_bytes().to_vec();
+            .voting_keys = vec![g_x3.to_bytes().to_vec()];
 
-        host.state_mut()
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[0])
             .unwrap()
             .index = 0;
-        host.state_mut()
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[1])
             .unwrap()
             .index = 1;
-        host.state_mut()
+        test_utils::election_mut(&mut host)
             .voters
             .get_mut(&accounts[2])
             .unwrap()
             .index = 2;
 
-        let _ = commit(&ctx, &mut host);
+        let _ = commit(&ctx, &mut host, &mut logger);
 
         // Test function briefly for other 2 accounts
         let commitment = off_chain::commit_to_vote(&x2, &g_y2, g_v);
 
         let commitment_message = CommitMessage {
-            reconstructed_key: g_y2.to_bytes().to_vec(),
-            commitment,
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+            commitments: vec![commitment],
         };
         let commitment_message_bytes = to_bytes(&commitment_message);
 
         ctx.set_parameter(&commitment_message_bytes);
         ctx.set_sender(Address::Account(accounts[1]));
 
-        let _ = commit(&ctx, &mut host);
+        let _ = commit(&ctx, &mut host, &mut logger);
 
         let commitment = off_chain::commit_to_vote(&x3, &g_y3, g_v);
 
         let commitment_message = CommitMessage {
-            reconstructed_key: g_y3.to_bytes().to_vec(),
-            commitment,
+            election_id: test_utils::ELECTION_ID,
+            reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+            commitments: vec![commitment],
         };
         let commitment_message_bytes = to_bytes(&commitment_message);
 
         ctx.set_parameter(&commitment_message_bytes);
         ctx.set_sender(Address::Account(accounts[2]));
 
-        let result = commit(&ctx, &mut host);
+        let result = commit(&ctx, &mut host, &mut logger);
 
         claim_eq!(
             result,
@@ -603,6 +1341,7 @@ mod tests {
 
     #[concordium_test]
     fn test_vote() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
@@ -620,12 +1359,19 @@ mod tests {
 
         // Testing no vote
         let one_in_two_zkp_account1 =
-            off_chain::create_one_in_two_zkp_no(g_x1, g_y1.clone(), x1.clone());
+            off_chain::create_one_in_two_zkp_no(
+                g_x1,
+                g_y1,
+                x1,
+                1,
+                &test_utils::proof_context(accounts[0]),
+            );
         let vote_message1 = VoteMessage {
-            vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + ProjectivePoint::IDENTITY)
                 .to_bytes()
-                .to_vec(),
-            vote_zkp: one_in_two_zkp_account1,
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(one_in_two_zkp_account1)],
         };
         let vote_message_bytes = to_bytes(&vote_message1);
 
@@ -640,34 +1386,40 @@ mod tests {
         );
 
         // Set self balance to three as deposit is 1 from 3 voters
-        host.set_self_balance(Amount::from_micro_ccd(3));
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR),
+                voting_keys: vec![g_x2.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
+                voting_keys: vec![g_x3.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR)],
+                weight: 1,
                 ..Default::default()
             },
         );
 
-        let result = vote(&ctx, &mut host);
+        let result = vote(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -675,33 +1427,55 @@ mod tests {
         );
 
         // Check that voter1 has indeed voted
-        let voter1 = match host.state().voters.get(&accounts[0]) {
+        let election = test_utils::election(&host);
+        let voter1 = match election.voters.get(&accounts[0]) {
             Some(v) => v,
             None => fail!("Voter 1 should exist"),
         };
 
-        claim_ne!(voter1.vote, Vec::<u8>::new(), "Voter 1 should have voted");
+        claim_ne!(voter1.votes, Vec::<Vec<u8>>::new(), "Voter 1 should have voted");
 
         claim_eq!(
-            host.self_balance(),
-            Amount::from_micro_ccd(2),
+            test_utils::balance(&host, &accounts[0]),
+            Amount::from_micro_ccd(1),
             "Voter 1 should have been refunded"
         );
 
+        claim_eq!(
+            logger.logs,
+            vec![
+                test_utils::event(types::VotingEvent::Vote {
+                    account: accounts[0],
+                }),
+                test_utils::event(types::VotingEvent::Refund {
+                    account: accounts[0],
+                    amount: Amount::from_micro_ccd(1),
+                }),
+            ],
+            "Vote and refund events should have been logged"
+        );
+
         // Testing yes vote
         let one_two_zkp_account2 =
-            off_chain::create_one_in_two_zkp_yes(g_x2, g_y2.clone(), x2.clone());
+            off_chain::create_one_in_two_zkp_yes(
+                g_x2,
+                g_y2,
+                x2,
+                1,
+                &test_utils::proof_context(accounts[1]),
+            );
         let vote_message2 = VoteMessage {
-            vote: ((g_y2 * x2) + ProjectivePoint::GENERATOR)
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y2 * x2) + ProjectivePoint::GENERATOR)
                 .to_bytes()
-                .to_vec(),
-            vote_zkp: one_two_zkp_account2,
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(one_two_zkp_account2)],
         };
         let vote_message_bytes = to_bytes(&vote_message2);
         ctx.set_parameter(&vote_message_bytes);
         ctx.set_sender(Address::Account(accounts[1]));
 
-        let result = vote(&ctx, &mut host);
+        let result = vote(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
@@ -709,14 +1483,30 @@ mod tests {
         );
 
         claim_eq!(
-            host.self_balance(),
+            test_utils::balance(&host, &accounts[1]),
             Amount::from_micro_ccd(1),
             "Voter 2 should have been refunded"
         );
+        claim_eq!(
+            host.self_balance(),
+            Amount::from_micro_ccd(3),
+            "Refunds should only be transferred when withdrawn"
+        );
+
+        // Both votes should be in the running aggregate
+        claim_eq!(test_utils::election(&host).number_of_votes, 2, "Number of votes should be 2");
+        claim_eq!(
+            test_utils::election(&host).vote_aggregates,
+            vec![((g_y1 * x1) + (g_y2 * x2) + ProjectivePoint::GENERATOR)
+                .to_bytes()
+                .to_vec()],
+            "Vote aggregate should be the sum of the votes"
+        );
     }
 
     #[concordium_test]
     fn test_vote_with_dishonest_reconstructed_key() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
@@ -735,12 +1525,19 @@ mod tests {
 
         // Testing no vote
         let one_in_two_zkp_account3 =
-            off_chain::create_one_in_two_zkp_no(g_x3, g_y3.clone(), x3.clone());
+            off_chain::create_one_in_two_zkp_no(
+                g_x3,
+                g_y3,
+                x3,
+                1,
+                &test_utils::proof_context(accounts[0]),
+            );
         let vote_message1 = VoteMessage {
-            vote: ((g_y3.clone() * x3.clone()) + ProjectivePoint::IDENTITY)
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y3 * x3) + ProjectivePoint::IDENTITY)
                 .to_bytes()
-                .to_vec(),
-            vote_zkp: one_in_two_zkp_account3,
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(one_in_two_zkp_account3)],
         };
         let vote_message_bytes = to_bytes(&vote_message1);
 
@@ -755,34 +1552,37 @@ mod tests {
         );
 
         // Set self balance to three as deposit is 1 from 3 voters
-        host.set_self_balance(Amount::from_micro_ccd(3));
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR),
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::GENERATOR)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::IDENTITY),
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::IDENTITY)],
+                weight: 1,
                 ..Default::default()
             },
         );
 
-        let result = vote(&ctx, &mut host);
+        let result = vote(&ctx, &mut host, &mut logger);
 
         claim_eq! {
             result,
@@ -791,8 +1591,69 @@ mod tests {
         }
     }
 
+    #[concordium_test]
+    fn test_vote_with_other_key() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (_, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
+
+        let keys = vec![g_x1, g_x2, g_x3];
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+
+        // Voter 1 votes with a key x' other than the registered one, with a valid ZKP and a matching commitment.
+        // The vote g^(x'y)*g^v would not cancel out in the tally
+        let (other_x, other_g_x) = off_chain::create_votingkey_pair();
+        let vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * other_x) + ProjectivePoint::GENERATOR)
+                .to_bytes()
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_yes(
+                other_g_x,
+                g_y1,
+                other_x,
+                1,
+                &test_utils::proof_context(accounts[0]),
+            ))],
+        };
+        let vote_message_bytes = to_bytes(&vote_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&other_x, &g_y1, ProjectivePoint::GENERATOR)],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "A ZKP for another key than the registered voting key should be rejected"
+        );
+    }
+
     #[concordium_test]
     fn test_result() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
 
@@ -802,7 +1663,7 @@ mod tests {
         let (x3, g_x3) = off_chain::create_votingkey_pair();
         let (x4, g_x4) = off_chain::create_votingkey_pair();
 
-        let list_of_voting_keys = vec![g_x1.clone(), g_x2.clone(), g_x3.clone(), g_x4.clone()];
+        let list_of_voting_keys = vec![g_x1, g_x2, g_x3, g_x4];
 
         // Compute reconstructed key
         let g_y1 = util::compute_reconstructed_key(&list_of_voting_keys, 0);
@@ -813,66 +1674,153 @@ mod tests {
         let (state, state_builder) =
             test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
 
-        let (ctx, mut host) =
-            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+        let result_message_bytes = test_utils::result_message(None);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&result_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
-                vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY)],
+                votes: vec![((g_y1 * x1) + ProjectivePoint::IDENTITY)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY),
-                vote: ((g_y2.clone() * x2.clone()) + ProjectivePoint::IDENTITY)
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY)],
+                votes: vec![((g_y2 * x2) + ProjectivePoint::IDENTITY)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
-                vote: ((g_y3.clone() * x3.clone()) + ProjectivePoint::GENERATOR)
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR)],
+                votes: vec![((g_y3 * x3) + ProjectivePoint::GENERATOR)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[3],
             Voter {
-                reconstructed_key: g_y4.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x4, &g_y4, ProjectivePoint::GENERATOR),
-                vote: ((g_y4.clone() * x4.clone()) + ProjectivePoint::GENERATOR)
+                reconstructed_keys: vec![g_y4.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x4, &g_y4, ProjectivePoint::GENERATOR)],
+                votes: vec![((g_y4 * x4) + ProjectivePoint::GENERATOR)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
 
-        let result = result(&ctx, &mut host);
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        let result = result(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
             "Contract receive failed, but should not have"
         );
 
-        claim_eq!((2, 2), host.state().voting_result, "Wrong voting result")
+        claim_eq!(vec![vec![2, 2]], test_utils::election(&host).voting_result, "Wrong voting result");
+        claim_eq!(
+            test_utils::election(&host).voting_outcome,
+            vec![types::VoteOutcome::Rejected],
+            "A tie should not pass with a simple majority"
+        );
+        claim_eq!(
+            logger.logs,
+            vec![test_utils::event(types::VotingEvent::Result {
+                results: vec![types::VotingResult {
+                    counts: vec![2, 2],
+                    outcome: types::VoteOutcome::Rejected,
+                }],
+            })],
+            "Result event should have been logged"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_outcome() {
+        let (_, mut vote_config, _) = test_utils::setup_test_config(6, Amount::from_micro_ccd(0));
+        let two_thirds = types::PassRule::Supermajority(types::Fraction {
+            numerator: 2,
+            denominator: 3,
+        });
+
+        // (pass rule, counts, outcome) for a yes/no vote without quorum
+        let cases = [
+            (types::PassRule::Majority, vec![3, 2], types::VoteOutcome::Passed),
+            (types::PassRule::Majority, vec![2, 2], types::VoteOutcome::Rejected),
+            (two_thirds.clone(), vec![4, 2], types::VoteOutcome::Passed),
+            (two_thirds.clone(), vec![3, 2], types::VoteOutcome::Rejected),
+            (types::PassRule::Unanimity, vec![5, 0], types::VoteOutcome::Passed),
+            (types::PassRule::Unanimity, vec![5, 1], types::VoteOutcome::Rejected),
+        ];
+        for (pass_rule, counts, outcome) in cases.iter() {
+            vote_config.pass_rule = pass_rule.clone();
+            claim_eq!(
+                vote_config.outcome(counts, counts.iter().sum()),
+                *outcome,
+                "Wrong outcome for {:?} with {:?}",
+                pass_rule,
+                counts
+            );
+        }
+
+        // Quorum of half the 6 eligible voters
+        vote_config.pass_rule = types::PassRule::Majority;
+        vote_config.quorum = types::Fraction {
+            numerator: 1,
+            denominator: 2,
+        };
+        claim_eq!(
+            vote_config.outcome(&[2, 0], 2),
+            types::VoteOutcome::QuorumNotMet,
+            "2 of 6 voters should not meet the quorum"
+        );
+        claim_eq!(
+            vote_config.outcome(&[2, 1], 3),
+            types::VoteOutcome::Passed,
+            "3 of 6 voters should meet the quorum"
+        );
+
+        // For candidate votes the rule applies to the leading candidate
+        vote_config.voting_options =
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        claim_eq!(
+            vote_config.outcome(&[1, 3, 0], 4),
+            types::VoteOutcome::Passed,
+            "Bob should have a majority"
+        );
+        claim_eq!(
+            vote_config.outcome(&[2, 2, 0], 4),
+            types::VoteOutcome::Rejected,
+            "No candidate should have a majority"
+        );
     }
 
     #[concordium_test]
     fn test_refund_deposits_all_honest() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
@@ -888,24 +1836,27 @@ mod tests {
         let (x2, g_x2) = off_chain::create_votingkey_pair();
         let (x3, g_x3) = off_chain::create_votingkey_pair();
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                voting_key: g_x1.to_bytes().to_vec(),
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                voting_key: g_x2.to_bytes().to_vec(),
+                voting_keys: vec![g_x2.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                voting_key: g_x3.to_bytes().to_vec(),
+                voting_keys: vec![g_x3.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
@@ -916,50 +1867,62 @@ mod tests {
         let g_y2 = util::compute_reconstructed_key(&list_of_voting_keys, 1);
         let g_y3 = util::compute_reconstructed_key(&list_of_voting_keys, 2);
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY),
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY)],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR)],
+                weight: 1,
                 ..Default::default()
             },
         );
 
         // Deposit is 1 and there are 3 accounts thus balance is 3
-        host.set_self_balance(Amount::from_micro_ccd(3));
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
 
-        let result = refund_deposits(accounts[0], &mut host);
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[0], &mut test_utils::election_mut(&mut host), &mut logger);
 
         claim!(
             result.is_ok(),
             "Contract receive failed, but should not have"
         );
 
+        for account in accounts.iter() {
+            claim_eq!(
+                test_utils::balance(&host, account),
+                Amount::from_micro_ccd(1),
+                "All deposits should have been refunded"
+            );
+        }
+
         claim_eq!(
-            host.self_balance(),
-            Amount::zero(),
-            "All deposits should have been refunded"
+            logger.logs.len(),
+            3,
+            "A refund event should have been logged for each voter"
         )
     }
 
     #[concordium_test]
     fn test_refund_deposits_no_honest() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
@@ -970,32 +1933,35 @@ mod tests {
             test_utils::setup_receive_context(None, accounts[0], state, state_builder);
 
         // Simulate that the 3 voters have registered, but not voted
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
+                voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
+                voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                voting_key: off_chain::create_votingkey_pair().1.to_bytes().to_vec(),
+                voting_keys: vec![off_chain::create_votingkey_pair().1.to_bytes().to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
 
         // Deposit is 1 and there are 3 accounts thus balance is 3
-        host.set_self_balance(Amount::from_micro_ccd(3));
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
 
-        let result = refund_deposits(accounts[2], &mut host);
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[2], &mut test_utils::election_mut(&mut host), &mut logger);
 
         claim!(
             result.is_ok(),
@@ -1003,14 +1969,15 @@ mod tests {
         );
 
         claim_eq!(
-            host.self_balance(),
-            Amount::from_micro_ccd(3),
+            test_utils::election(&host).balances.iter().count(),
+            0,
             "No deposits should be refunded"
         )
     }
 
     #[concordium_test]
     fn test_refund_deposits_one_dishonest() {
+        let mut logger = TestLogger::init();
         let (accounts, vote_config, _) =
             test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
 
@@ -1033,45 +2000,46 @@ mod tests {
         let g_y2 = util::compute_reconstructed_key(&list_of_voting_keys, 1);
         let g_y3 = util::compute_reconstructed_key(&list_of_voting_keys, 2);
 
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[0],
             Voter {
-                voting_key: g_x1.to_bytes().to_vec(),
-                reconstructed_key: g_y1.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY),
-                vote: ((g_y1.clone() * x1.clone()) + ProjectivePoint::IDENTITY)
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, ProjectivePoint::IDENTITY)],
+                votes: vec![((g_y1 * x1) + ProjectivePoint::IDENTITY)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[1],
             Voter {
-                voting_key: g_x2.to_bytes().to_vec(),
-                reconstructed_key: g_y2.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY),
-                vote: ((g_y2.clone() * x2.clone()) + ProjectivePoint::IDENTITY)
+                voting_keys: vec![g_x2.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y2.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x2, &g_y2, ProjectivePoint::IDENTITY)],
+                votes: vec![((g_y2 * x2) + ProjectivePoint::IDENTITY)
                     .to_bytes()
-                    .to_vec(),
+                    .to_vec()],
+                weight: 1,
                 ..Default::default()
             },
         );
         // This is the dishonest voter
-        host.state_mut().voters.insert(
+        test_utils::election_mut(&mut host).voters.insert(
             accounts[2],
             Voter {
-                voting_key: g_x3.to_bytes().to_vec(),
-                reconstructed_key: g_y3.to_bytes().to_vec(),
-                commitment: off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR),
+                voting_keys: vec![g_x3.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y3.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x3, &g_y3, ProjectivePoint::GENERATOR)],
+                weight: 1,
                 ..Default::default()
             },
         );
 
-        // Deposit is 1 and there are 3 accounts thus balance is 3. However we are in Vote phase so the 2 honest accounts has already received a refund
-        host.set_self_balance(Amount::from_micro_ccd(1));
-
-        let result = refund_deposits(accounts[1], &mut host);
+        // We are in Vote phase so the 2 honest accounts have already been refunded when voting
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[1], &mut test_utils::election_mut(&mut host), &mut logger);
 
         claim!(
             result.is_ok(),
@@ -1079,24 +2047,2060 @@ mod tests {
         );
 
         claim_eq!(
-            host.self_balance(),
-            Amount::from_micro_ccd(0),
+            test_utils::balance(&host, &accounts[1]),
+            Amount::from_micro_ccd(1),
             "Account[1] should get extra deposit for catching dishonest voter"
         );
+        claim_eq!(
+            test_utils::balance(&host, &accounts[0]),
+            Amount::zero(),
+            "Account[0] should not be refunded again"
+        );
 
         //------------------------------------ Run again where dishonest is sender ---------------------
 
-        // Same as above
-        host.set_self_balance(Amount::from_micro_ccd(1));
-
         // Dishonest voter is sender of refund request
-        let result = refund_deposits(accounts[2], &mut host);
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[2], &mut test_utils::election_mut(&mut host), &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        claim_eq!(test_utils::balance(&host, &accounts[2]), Amount::zero(), "Account[2] should not get deposit for catching dishonest voter, since they are dishonest")
+    }
+
+    #[concordium_test]
+    fn test_withdraw() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(6));
+
+        let phases = [
+            types::VotingPhase::Registration,
+            types::VotingPhase::Commit,
+            types::VotingPhase::Vote,
+            types::VotingPhase::Recovery,
+            types::VotingPhase::Result,
+            types::VotingPhase::Abort,
+        ];
+
+        // Balances can be claimed in every phase
+        for (i, phase) in phases.iter().enumerate() {
+            test_utils::election_mut(&mut host).voting_phase = phase.clone();
+
+            claim_eq!(
+                withdraw(&ctx, &mut host, &mut logger),
+                Err(types::WithdrawError::NothingToWithdraw),
+                "Should not withdraw without a balance"
+            );
+
+            // Simulate a refunded deposit
+            credit(&mut test_utils::election_mut(&mut host), accounts[0], Amount::from_micro_ccd(1));
+
+            claim_eq!(
+                withdraw(&ctx, &mut host, &mut logger),
+                Ok(Amount::from_micro_ccd(1)),
+                "Should withdraw the balance"
+            );
+            claim_eq!(
+                host.self_balance(),
+                Amount::from_micro_ccd(5 - i as u64),
+                "Balance should have been transferred"
+            );
+            claim_eq!(
+                test_utils::balance(&host, &accounts[0]),
+                Amount::zero(),
+                "Balance should have been cleared"
+            );
+            claim_eq!(
+                logger.logs.last(),
+                Some(&test_utils::event(types::VotingEvent::Withdraw {
+                    account: accounts[0],
+                    amount: Amount::from_micro_ccd(1),
+                })),
+                "Withdrawal should have been logged"
+            );
+
+            // A balance can only be claimed once
+            claim_eq!(
+                withdraw(&ctx, &mut host, &mut logger),
+                Err(types::WithdrawError::NothingToWithdraw),
+                "Should not withdraw the balance twice"
+            );
+        }
+
+        // Balances of the caller's reward and the refunds from an abort are claimed the same way
+        test_utils::election_mut(&mut host).voting_phase = types::VotingPhase::Commit;
+        for account in accounts.iter() {
+            test_utils::election_mut(&mut host).voters.insert(
+                *account,
+                Voter {
+                    commitments: vec![vec![1]],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+        test_utils::election_mut(&mut host).voters.insert(accounts[2], Default::default());
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
+
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[0], &mut test_utils::election_mut(&mut host), &mut logger);
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+
+        claim_eq!(
+            withdraw(&ctx, &mut host, &mut logger),
+            Ok(Amount::from_micro_ccd(2)),
+            "Caller should withdraw their refund and reward"
+        );
+        ctx.set_sender(Address::Account(accounts[1]));
+        claim_eq!(
+            withdraw(&ctx, &mut host, &mut logger),
+            Ok(Amount::from_micro_ccd(1)),
+            "Honest voter should withdraw their refund"
+        );
+        ctx.set_sender(Address::Account(accounts[2]));
+        claim_eq!(
+            withdraw(&ctx, &mut host, &mut logger),
+            Err(types::WithdrawError::NothingToWithdraw),
+            "Stalling voter should have nothing to withdraw"
+        );
+        claim_eq!(
+            host.self_balance(),
+            Amount::zero(),
+            "The deposit of the stalling voter should have paid the reward"
+        );
+
+        ctx.set_sender(Address::Contract(ContractAddress {
+            index: 1,
+            subindex: 0,
+        }));
+        claim_eq!(
+            withdraw(&ctx, &mut host, &mut logger),
+            Err(types::WithdrawError::ContractSender),
+            "Contracts should not withdraw"
+        );
+    }
+
+    #[concordium_test]
+    fn test_setup_invalid_voting_options() {
+        let mut logger = TestLogger::init();
+        let (_, mut vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        // A candidate vote with a single option is not a choice
+        vote_config.voting_options = vec!["Alice".to_string()];
+
+        let vote_config_bytes = to_bytes(&vote_config);
+        let (state, state_builder) = test_utils::setup_empty_state();
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_config_bytes),
+            test_utils::ORGANIZER,
+            state,
+            state_builder,
+        );
+
+        let result = create_election(&ctx, &mut host, &mut logger);
+
+        claim_eq!(
+            result.err(),
+            Some(types::SetupError::InvalidVotingOptions),
+            "Setup should fail with only one voting option"
+        );
+
+        // Approval and score ballots need candidates and bounds that allow a vote, and veto elections have no candidates
+        let three_options = vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        let score = |max_score, budget| types::BallotType::Score(types::ScoreRule { max_score, budget });
+        let invalid_ballots = [
+            (Vec::new(), types::BallotType::Approval(1)),
+            (three_options.clone(), types::BallotType::Approval(0)),
+            (three_options.clone(), score(0, 1)),
+            (three_options.clone(), score(2, 7)),
+            (three_options.clone(), types::BallotType::Veto),
+        ];
+        for (voting_options, ballot_type) in invalid_ballots.iter() {
+            let mut logger = TestLogger::init();
+            let (_, mut vote_config, _) =
+                test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+            vote_config.voting_options = voting_options.clone();
+            vote_config.ballot_type = ballot_type.clone();
+
+            let vote_config_bytes = to_bytes(&vote_config);
+            let (state, state_builder) = test_utils::setup_empty_state();
+            let (ctx, mut host) = test_utils::setup_receive_context(
+                Some(&vote_config_bytes),
+                test_utils::ORGANIZER,
+                state,
+                state_builder,
+            );
+
+            claim_eq!(
+                create_election(&ctx, &mut host, &mut logger).err(),
+                Some(types::SetupError::InvalidBallotType),
+                "Setup should fail with an invalid ballot type"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_setup_invalid_thresholds() {
+        let fraction = |numerator, denominator| types::Fraction {
+            numerator,
+            denominator,
+        };
+
+        // Invalid minimum number of registrants, quorum or supermajority
+        let invalid_configs = [
+            (2, fraction(0, 1), types::PassRule::Majority, types::SetupError::InvalidNumberOfVoters),
+            (4, fraction(0, 1), types::PassRule::Majority, types::SetupError::InvalidNumberOfVoters),
+            (3, fraction(1, 0), types::PassRule::Majority, types::SetupError::InvalidQuorum),
+            (3, fraction(3, 2), types::PassRule::Majority, types::SetupError::InvalidQuorum),
+            (3, fraction(0, 1), types::PassRule::Supermajority(fraction(1, 2)), types::SetupError::InvalidPassRule),
+            (3, fraction(0, 1), types::PassRule::Supermajority(fraction(4, 3)), types::SetupError::InvalidPassRule),
+        ];
+
+        for (min_registrants, quorum, pass_rule, error) in invalid_configs.iter() {
+            let mut logger = TestLogger::init();
+            let (_, mut vote_config, _) =
+                test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+            vote_config.min_registrants = *min_registrants;
+            vote_config.quorum = *quorum;
+            vote_config.pass_rule = pass_rule.clone();
+
+            let vote_config_bytes = to_bytes(&vote_config);
+            let (state, state_builder) = test_utils::setup_empty_state();
+            let (ctx, mut host) = test_utils::setup_receive_context(
+                Some(&vote_config_bytes),
+                test_utils::ORGANIZER,
+                state,
+                state_builder,
+            );
+
+            claim_eq!(
+                create_election(&ctx, &mut host, &mut logger).err().as_ref(),
+                Some(error),
+                "Setup should fail with invalid thresholds"
+            );
+        }
+
+        // Reporter share of more than all of the slashed deposits
+        let mut logger = TestLogger::init();
+        let (_, mut vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.slashing_policy = types::SlashingPolicy::Reporter(fraction(3, 2));
+
+        let vote_config_bytes = to_bytes(&vote_config);
+        let (state, state_builder) = test_utils::setup_empty_state();
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_config_bytes),
+            test_utils::ORGANIZER,
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            create_election(&ctx, &mut host, &mut logger).err(),
+            Some(types::SetupError::InvalidSlashingPolicy),
+            "Setup should fail with an invalid reporter share"
+        );
+
+        // Total weight of less than 1 per voter
+        let mut logger = TestLogger::init();
+        let (_, mut vote_config, _) = test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.total_weight = 2;
+
+        let vote_config_bytes = to_bytes(&vote_config);
+        let (state, state_builder) = test_utils::setup_empty_state();
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_config_bytes),
+            test_utils::ORGANIZER,
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            create_election(&ctx, &mut host, &mut logger).err(),
+            Some(types::SetupError::InvalidTotalWeight),
+            "Setup should fail with a total weight below the number of voters"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_candidates() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.voting_options =
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        let encodings = vote_config.candidate_encodings().unwrap();
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
+
+        let keys = vec![g_x1, g_x2, g_x3];
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+
+        // Voter 1 votes for Carol
+        let g_v = off_chain::encode_candidate(2, &encodings);
+        let vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + g_v).to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::OneInK(off_chain::create_one_in_k_zkp(
+                g_x1,
+                g_y1,
+                x1,
+                2,
+                &encodings,
+                &test_utils::proof_context(accounts[0]),
+            ))],
+        };
+        let vote_message_bytes = to_bytes(&vote_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&vote_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, g_v)],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        // A yes/no ZKP is not accepted for a candidate vote
+        let wrong_vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + ProjectivePoint::GENERATOR)
+                .to_bytes()
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_yes(
+                g_x1,
+                g_y1,
+                x1,
+                1,
+                &test_utils::proof_context(accounts[0]),
+            ))],
+        };
+        let wrong_vote_message_bytes = to_bytes(&wrong_vote_message);
+        ctx.set_parameter(&wrong_vote_message_bytes);
+
+        let result = vote(&ctx, &mut host, &mut logger);
+
+        claim_eq!(
+            result,
+            Err(types::VoteError::InvalidZKP),
+            "A one-in-two ZKP should be rejected for a candidate vote"
+        );
+
+        ctx.set_parameter(&vote_message_bytes);
+
+        let result = vote(&ctx, &mut host, &mut logger);
 
         claim!(
             result.is_ok(),
             "Contract receive failed, but should not have"
         );
+    }
+
+    #[concordium_test]
+    fn test_result_candidates() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+        vote_config.voting_options =
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        let encodings = vote_config.candidate_encodings().unwrap();
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
+
+        let result_message_bytes = test_utils::result_message(None);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&result_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        // Two votes for Alice, none for Bob and two for Carol
+        let choices = [0, 2, 0, 2];
+        for (i, (x, _)) in key_pairs.iter().enumerate() {
+            let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+            let g_v = off_chain::encode_candidate(choices[i], &encodings);
+
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                    commitments: vec![off_chain::commit_to_vote(x, &g_y, g_v)],
+                    votes: vec![((g_y * x) + g_v).to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        let result = result(&ctx, &mut host, &mut logger);
+
+        claim_eq!(
+            result,
+            Ok(vec![types::VotingResult {
+                counts: vec![2, 0, 2],
+                outcome: types::VoteOutcome::Rejected,
+            }]),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            vec![vec![2, 0, 2]],
+            test_utils::election(&host).voting_result,
+            "Wrong voting result"
+        )
+    }
+
+    #[concordium_test]
+    fn test_vote_scores() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.voting_options =
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        vote_config.ballot_type = types::BallotType::Score(types::ScoreRule {
+            max_score: 2,
+            budget: 3,
+        });
+        let encodings = vote_config.candidate_encodings().unwrap();
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
+
+        let keys = vec![g_x1, g_x2, g_x3];
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+        let context = test_utils::proof_context(accounts[0]);
+
+        // Voter 1 gives Alice 2 points and Carol 1 point
+        let scores = [2, 0, 1];
+        let g_v = off_chain::encode_scores(&scores, &encodings);
+        let vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + g_v).to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::Range(off_chain::create_range_zkp(
+                g_y1, x1, &scores, 2, 3, &encodings, 1, &context,
+            ))],
+        };
+        let vote_message_bytes = to_bytes(&vote_message);
+
+        // The proof of the scores does not hold for a vote with other scores
+        let other_scores_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + off_chain::encode_scores(&[2, 0, 2], &encodings))
+                .to_bytes()
+                .to_vec()],
+            vote_zkps: vote_message.vote_zkps.clone(),
+        };
+        let other_scores_message_bytes = to_bytes(&other_scores_message);
+
+        // A one-in-k ZKP is not accepted for a score ballot
+        let one_in_k_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + off_chain::encode_candidate(0, &encodings))
+                .to_bytes()
+                .to_vec()],
+            vote_zkps: vec![VoteZKP::OneInK(off_chain::create_one_in_k_zkp(
+                g_x1, g_y1, x1, 0, &encodings, &context,
+            ))],
+        };
+        let one_in_k_message_bytes = to_bytes(&one_in_k_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&other_scores_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, g_v)],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "The range ZKP should only hold for the scores it was made for"
+        );
+
+        ctx.set_parameter(&one_in_k_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "A one-in-k ZKP should be rejected for a score ballot"
+        );
+
+        ctx.set_parameter(&vote_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_approvals() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+        vote_config.voting_options =
+            vec!["Alice".to_string(), "Bob".to_string(), "Carol".to_string()];
+        vote_config.ballot_type = types::BallotType::Approval(2);
+        let encodings = vote_config.candidate_encodings().unwrap();
 
-        claim_eq!(host.self_balance(), Amount::from_micro_ccd(1), "Account[2] should not get deposit for catching dishonest voter, since they are dishonest")
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
+
+        let result_message_bytes = test_utils::result_message(None);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&result_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        // Alice is approved by three voters, Bob by one and Carol by two
+        let approvals = [[1, 0, 1], [1, 1, 0], [0, 0, 1], [1, 0, 0]];
+        for (i, (x, _)) in key_pairs.iter().enumerate() {
+            let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+            let g_v = off_chain::encode_scores(&approvals[i], &encodings);
+
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                    commitments: vec![off_chain::commit_to_vote(x, &g_y, g_v)],
+                    votes: vec![((g_y * x) + g_v).to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        let result = result(&ctx, &mut host, &mut logger);
+
+        claim_eq!(
+            result,
+            Ok(vec![types::VotingResult {
+                counts: vec![3, 1, 2],
+                outcome: types::VoteOutcome::Passed,
+            }]),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            off_chain::compute_score_result(
+                &test_utils::election(&host)
+                    .voters
+                    .iter()
+                    .map(|(_, voter)| voter.votes[0].clone())
+                    .collect::<Vec<_>>(),
+                &vec![Vec::new(); 4],
+                &[1; 4],
+                3,
+                1,
+                4
+            ),
+            Some(vec![3, 1, 2]),
+            "The off-chain tally should match"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_veto() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.ballot_type = types::BallotType::Veto;
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
+
+        let keys = vec![g_x1, g_x2, g_x3];
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+        let context = test_utils::proof_context(accounts[0]);
+
+        // Voter 1 vetoes with g^(r*y)
+        let (r, g_ry) = off_chain::create_veto_vote(&x1, &g_y1, true);
+        let vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![g_ry.to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::Veto(off_chain::create_veto_zkp(g_y1, g_ry, r, &context))],
+        };
+        let vote_message_bytes = to_bytes(&vote_message);
+
+        // A one-in-two ZKP is not accepted in a veto election
+        let one_in_two_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![(g_y1 * x1).to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_no(
+                g_x1, g_y1, x1, 1, &context,
+            ))],
+        };
+        let one_in_two_message_bytes = to_bytes(&one_in_two_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&one_in_two_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&r, &g_y1, ProjectivePoint::IDENTITY)],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "A one-in-two ZKP should be rejected in a veto election"
+        );
+
+        ctx.set_parameter(&vote_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_veto() {
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+        vote_config.ballot_type = types::BallotType::Veto;
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        // Nobody vetoes, then only the third voter vetoes, then all voters veto
+        let scenarios = [
+            ([false, false, false, false], types::VoteOutcome::Passed),
+            ([false, false, true, false], types::VoteOutcome::Vetoed),
+            ([true, true, true, true], types::VoteOutcome::Vetoed),
+        ];
+        for (vetoes, outcome) in scenarios.iter() {
+            let mut logger = TestLogger::init();
+            let (state, state_builder) =
+                test_utils::setup_state(&accounts, vote_config.clone(), types::VotingPhase::Result);
+
+            // A veto election has no counts to claim
+            let claimed_result_bytes = test_utils::result_message(Some(vec![vec![4, 0]]));
+            let result_message_bytes = test_utils::result_message(None);
+            let (mut ctx, mut host) = test_utils::setup_receive_context(
+                Some(&claimed_result_bytes),
+                accounts[0],
+                state,
+                state_builder,
+            );
+
+            for (i, (x, _)) in key_pairs.iter().enumerate() {
+                let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+                let (_, vote) = off_chain::create_veto_vote(x, &g_y, vetoes[i]);
+
+                test_utils::election_mut(&mut host).voters.insert(
+                    accounts[i],
+                    Voter {
+                        reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                        votes: vec![vote.to_bytes().to_vec()],
+                        weight: 1,
+                        ..Default::default()
+                    },
+                );
+            }
+
+            test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+            claim_eq!(
+                result(&ctx, &mut host, &mut logger),
+                Err(types::ResultError::WrongClaimedResult),
+                "Counts should not be accepted in a veto election"
+            );
+
+            ctx.set_parameter(&result_message_bytes);
+            claim_eq!(
+                result(&ctx, &mut host, &mut logger),
+                Ok(vec![types::VotingResult {
+                    counts: Vec::new(),
+                    outcome: outcome.clone(),
+                }]),
+                "The result should only tell whether anyone vetoed"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_register_weighted() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        // The weights are committed in the merkle tree along with the accounts
+        let leaves = vec![(accounts[0], 5), (accounts[1], 1), (accounts[2], 2)];
+        let merkle_tree = off_chain::create_merkle_tree(&leaves);
+        vote_config.merkle_root = merkle_tree.root_hex().unwrap();
+        vote_config.total_weight = 8;
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        let register_message_bytes = |i: usize, merkle_proof: util::MerkleProof| {
+            let (x, g_x) = off_chain::create_votingkey_pair();
+            to_bytes(&RegisterMessage {
+                election_id: test_utils::ELECTION_ID,
+                voting_keys: vec![g_x.to_bytes().to_vec()],
+                voting_key_zkps: vec![off_chain::create_schnorr_zkp(g_x, x, &test_utils::proof_context(accounts[i]))],
+                merkle_proof,
+            })
+        };
+
+        // Voter 1 claims a lower weight than the one in their leaf
+        let mut merkle_proof = off_chain::create_merkle_proof(accounts[0], 5, &merkle_tree);
+        merkle_proof.weight = 1;
+        let wrong_weight_message_bytes = register_message_bytes(0, merkle_proof);
+        let register_message_bytes1 =
+            register_message_bytes(0, off_chain::create_merkle_proof(accounts[0], 5, &merkle_tree));
+        let register_message_bytes3 =
+            register_message_bytes(2, off_chain::create_merkle_proof(accounts[2], 2, &merkle_tree));
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&wrong_weight_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Err(types::RegisterError::UnauthorizedVoter),
+            "The weight should be checked against the leaf"
+        );
+
+        ctx.set_parameter(&register_message_bytes1);
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Ok(Some(0)),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voters.get(&accounts[0]).map(|v| v.weight),
+            Some(5),
+            "Voter should have the weight of their leaf"
+        );
+        claim_eq!(
+            test_utils::election(&host).registered_weight,
+            5,
+            "Registered weight should be the weight of the voter"
+        );
+
+        // The registered weights can not add up to more than the total weight the tally is decoded with
+        test_utils::election_mut(&mut host).config.total_weight = 6;
+        ctx.set_sender(Address::Account(accounts[2]));
+        ctx.set_parameter(&register_message_bytes3);
+        claim_eq!(
+            register(&ctx, &mut host, Amount::from_micro_ccd(0), &mut logger),
+            Err(types::RegisterError::InvalidWeight),
+            "Registration should fail when it exceeds the total weight"
+        );
+    }
+
+    #[concordium_test]
+    fn test_vote_weighted() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        // Create pk, sk pair of g^x and x for accounts
+        let (x1, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+        let (_, g_x3) = off_chain::create_votingkey_pair();
+
+        let keys = vec![g_x1, g_x2, g_x3];
+        let g_y1 = util::compute_reconstructed_key(&keys, 0);
+        let context = test_utils::proof_context(accounts[0]);
+
+        // Voter 1 has weight 3, so a yes vote is g^xy*g^3
+        let g_w = ProjectivePoint::GENERATOR * k256::Scalar::from(3u64);
+        let vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + g_w).to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_yes(
+                g_x1, g_y1, x1, 3, &context,
+            ))],
+        };
+        let vote_message_bytes = to_bytes(&vote_message);
+
+        // A yes vote of weight 1 does not prove v is in {0, 3}
+        let unweighted_vote_message = VoteMessage {
+            election_id: test_utils::ELECTION_ID,
+            votes: vec![((g_y1 * x1) + ProjectivePoint::GENERATOR).to_bytes().to_vec()],
+            vote_zkps: vec![VoteZKP::OneInTwo(off_chain::create_one_in_two_zkp_yes(
+                g_x1, g_y1, x1, 1, &context,
+            ))],
+        };
+        let unweighted_vote_message_bytes = to_bytes(&unweighted_vote_message);
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&unweighted_vote_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                voting_keys: vec![g_x1.to_bytes().to_vec()],
+                reconstructed_keys: vec![g_y1.to_bytes().to_vec()],
+                commitments: vec![off_chain::commit_to_vote(&x1, &g_y1, g_w)],
+                weight: 3,
+                ..Default::default()
+            },
+        );
+
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "A vote not weighted by the voter's weight should be rejected"
+        );
+
+        ctx.set_parameter(&vote_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voted_weight,
+            3,
+            "Voted weight should be the weight of the voter"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_weighted() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+        vote_config.voting_options = vec!["a".to_string(), "b".to_string()];
+        vote_config.total_weight = 7;
+
+        // Voters of weight 3 and 1 vote for a, and voters of weight 1 and 2 vote for b
+        let weights = [3u32, 1, 1, 2];
+        let choices = [0, 0, 1, 1];
+        let encodings = util::candidate_encodings(2, 8).unwrap();
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
+        let result_message_bytes = test_utils::result_message(None);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&result_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        for (i, (x, _)) in key_pairs.iter().enumerate() {
+            let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+            let weighted_encodings = util::weighted_encodings(&encodings, weights[i] as u64);
+            let g_v = off_chain::encode_candidate(choices[i], &weighted_encodings);
+
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                    votes: vec![((g_y * x) + g_v).to_bytes().to_vec()],
+                    weight: weights[i],
+                    ..Default::default()
+                },
+            );
+        }
+
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        claim_eq!(
+            result(&ctx, &mut host, &mut logger),
+            Ok(vec![types::VotingResult {
+                counts: vec![4, 3],
+                outcome: types::VoteOutcome::Passed,
+            }]),
+            "The counts should be the sums of the weights"
+        );
+        claim_eq!(
+            off_chain::compute_result(
+                &test_utils::election(&host)
+                    .voters
+                    .iter()
+                    .map(|(_, voter)| voter.votes[0].clone())
+                    .collect::<Vec<_>>(),
+                &vec![Vec::new(); 4],
+                &weights,
+                2,
+                7
+            ),
+            Some(vec![4, 3]),
+            "The off-chain tally should match"
+        );
+    }
+
+    #[concordium_test]
+    fn test_multiple_questions() {
+        let mut logger = TestLogger::init();
+        let (accounts, mut vote_config, merkle_tree) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+        vote_config.voting_questions = vec!["Vote for x".to_string(), "Vote for y".to_string()];
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Registration);
+
+        // Every voter has a key per question, derived from their secret key
+        let keys: Vec<_> = (0..3)
+            .map(|_| off_chain::derive_question_keys(&off_chain::create_votingkey_pair().0, 2))
+            .collect();
+        let voting_keys = |question: usize| -> Vec<ProjectivePoint> {
+            keys.iter().map(|voter_keys| voter_keys[question].1).collect()
+        };
+        // Voters 1 and 2 vote yes to the first question, and only voter 3 votes yes to the second
+        let choices = [[true, false], [true, false], [false, true]];
+        let choice_to_point = |choice: bool| match choice {
+            true => ProjectivePoint::GENERATOR,
+            false => ProjectivePoint::IDENTITY,
+        };
+
+        let register_messages_bytes: Vec<_> = (0..3)
+            .map(|i| {
+                to_bytes(&RegisterMessage {
+                    election_id: test_utils::ELECTION_ID,
+                    voting_keys: keys[i].iter().map(|(_, g_x)| g_x.to_bytes().to_vec()).collect(),
+                    voting_key_zkps: keys[i]
+                        .iter()
+                        .map(|(x, g_x)| {
+                            off_chain::create_schnorr_zkp(*g_x, *x, &test_utils::proof_context(accounts[i]))
+                        })
+                        .collect(),
+                    merkle_proof: off_chain::create_merkle_proof(accounts[i], 1, &merkle_tree),
+                })
+            })
+            .collect();
+        let commit_messages_bytes: Vec<_> = (0..3)
+            .map(|i| {
+                let g_ys: Vec<_> = (0..2)
+                    .map(|q| util::compute_reconstructed_key(&voting_keys(q), i as i32))
+                    .collect();
+                to_bytes(&CommitMessage {
+                    election_id: test_utils::ELECTION_ID,
+                    reconstructed_keys: g_ys.iter().map(|g_y| g_y.to_bytes().to_vec()).collect(),
+                    commitments: (0..2)
+                        .map(|q| off_chain::commit_to_vote(&keys[i][q].0, &g_ys[q], choice_to_point(choices[i][q])))
+                        .collect(),
+                })
+            })
+            .collect();
+        let vote_messages_bytes: Vec<_> = (0..3)
+            .map(|i| {
+                let context = test_utils::proof_context(accounts[i]);
+                let mut votes = Vec::new();
+                let mut vote_zkps = Vec::new();
+                for q in 0..2 {
+                    let (x, g_x) = keys[i][q];
+                    let g_y = util::compute_reconstructed_key(&voting_keys(q), i as i32);
+                    votes.push(((g_y * x) + choice_to_point(choices[i][q])).to_bytes().to_vec());
+                    vote_zkps.push(VoteZKP::OneInTwo(match choices[i][q] {
+                        true => off_chain::create_one_in_two_zkp_yes(g_x, g_y, x, 1, &context),
+                        false => off_chain::create_one_in_two_zkp_no(g_x, g_y, x, 1, &context),
+                    }));
+                }
+                to_bytes(&VoteMessage {
+                    election_id: test_utils::ELECTION_ID,
+                    votes,
+                    vote_zkps,
+                })
+            })
+            .collect();
+
+        // A register message with the key of only one question is rejected
+        let partial_register_message_bytes = to_bytes(&RegisterMessage {
+            election_id: test_utils::ELECTION_ID,
+            voting_keys: vec![keys[0][0].1.to_bytes().to_vec()],
+            voting_key_zkps: vec![off_chain::create_schnorr_zkp(
+                keys[0][0].1,
+                keys[0][0].0,
+                &test_utils::proof_context(accounts[0]),
+            )],
+            merkle_proof: off_chain::create_merkle_proof(accounts[0], 1, &merkle_tree),
+        });
+        let result_message_bytes = test_utils::result_message(None);
+
+        let (mut ctx, mut host) = test_utils::setup_receive_context(
+            Some(&partial_register_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        claim_eq!(
+            register(&ctx, &mut host, Amount::zero(), &mut logger),
+            Err(types::RegisterError::InvalidVotingKey),
+            "Register message should hold a voting key for each question"
+        );
+
+        for i in 0..3 {
+            ctx.set_sender(Address::Account(accounts[i]));
+            ctx.set_parameter(&register_messages_bytes[i]);
+            claim_eq!(
+                register(&ctx, &mut host, Amount::zero(), &mut logger),
+                Ok(Some(i as i32)),
+                "Voter should be registered"
+            );
+        }
+        claim_eq!(
+            test_utils::election(&host).voting_keys,
+            vec![
+                voting_keys(0).iter().map(|g_x| g_x.to_bytes().to_vec()).collect::<Vec<_>>(),
+                voting_keys(1).iter().map(|g_x| g_x.to_bytes().to_vec()).collect::<Vec<_>>(),
+            ],
+            "Voting keys should be stored for each question"
+        );
+
+        for i in 0..3 {
+            ctx.set_sender(Address::Account(accounts[i]));
+            ctx.set_parameter(&commit_messages_bytes[i]);
+            claim_eq!(
+                commit(&ctx, &mut host, &mut logger),
+                Ok(()),
+                "Voter should have committed"
+            );
+        }
+
+        // The vote of one question can not be moved to the other
+        let swapped_vote_message_bytes = {
+            let mut vote_message: VoteMessage = from_bytes(&vote_messages_bytes[0]).unwrap();
+            vote_message.votes.swap(0, 1);
+            vote_message.vote_zkps.swap(0, 1);
+            to_bytes(&vote_message)
+        };
+        ctx.set_sender(Address::Account(accounts[0]));
+        ctx.set_parameter(&swapped_vote_message_bytes);
+        claim_eq!(
+            vote(&ctx, &mut host, &mut logger),
+            Err(types::VoteError::InvalidZKP),
+            "Votes of the questions should not be interchangeable"
+        );
+
+        for i in 0..3 {
+            ctx.set_sender(Address::Account(accounts[i]));
+            ctx.set_parameter(&vote_messages_bytes[i]);
+            claim_eq!(
+                vote(&ctx, &mut host, &mut logger),
+                Ok(()),
+                "Voter should have voted"
+            );
+        }
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Result,
+            "Should change to result phase since all voters voted"
+        );
+
+        ctx.set_parameter(&result_message_bytes);
+        claim_eq!(
+            result(&ctx, &mut host, &mut logger),
+            Ok(vec![
+                types::VotingResult {
+                    counts: vec![2, 1],
+                    outcome: types::VoteOutcome::Passed,
+                },
+                types::VotingResult {
+                    counts: vec![1, 2],
+                    outcome: types::VoteOutcome::Rejected,
+                },
+            ]),
+            "Each question should be tallied on its own"
+        );
+    }
+
+    #[concordium_test]
+    fn test_view_functions() {
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(0));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Commit);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        let (_, g_x1) = off_chain::create_votingkey_pair();
+        let (_, g_x2) = off_chain::create_votingkey_pair();
+
+        test_utils::election_mut(&mut host).voting_keys = vec![vec![g_x1.to_bytes().to_vec(), g_x2.to_bytes().to_vec()]];
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[1],
+            Voter {
+                voting_keys: vec![g_x2.to_bytes().to_vec()],
+                index: 1,
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        claim_eq!(
+            view_config(&ctx, &host).map(|c| c.voting_questions),
+            Ok(vec!["Vote for x".to_string()]),
+            "Should return the vote config"
+        );
+        claim_eq!(
+            view_phase(&ctx, &host),
+            Ok(types::VotingPhase::Commit),
+            "Should return the current phase"
+        );
+        claim_eq!(
+            view_voting_keys(&ctx, &host),
+            Ok(vec![vec![g_x1.to_bytes().to_vec(), g_x2.to_bytes().to_vec()]]),
+            "Should return the voting keys in order"
+        );
+        claim_eq!(
+            view_voters(&ctx, &host).map(|v| v.len()),
+            Ok(3),
+            "Should return all registered voters"
+        );
+        claim_eq!(
+            view_result(&ctx, &host),
+            Ok(Vec::new()),
+            "Should return an empty result, since voting is not done"
+        );
+
+        let parameter = to_bytes(&(test_utils::ELECTION_ID, accounts[1]));
+        ctx.set_parameter(&parameter);
+
+        match view_voter(&ctx, &host) {
+            Ok(v) => {
+                claim_eq!(v.index, 1, "Voter should have index 1");
+                claim_eq!(
+                    v.voting_keys,
+                    vec![g_x2.to_bytes().to_vec()],
+                    "Voter should have their registered voting key"
+                );
+            }
+            Err(e) => fail!("View voter failed: {:?}", e),
+        };
+
+        let parameter = to_bytes(&(test_utils::ELECTION_ID, AccountAddress([5u8; 32])));
+        ctx.set_parameter(&parameter);
+
+        claim_eq!(
+            view_voter(&ctx, &host).err(),
+            Some(types::ViewError::VoterNotFound),
+            "Should not find an unregistered voter"
+        );
+
+        let parameter = to_bytes(&(test_utils::ELECTION_ID + 1, accounts[1]));
+        ctx.set_parameter(&parameter);
+
+        claim_eq!(
+            view_voter(&ctx, &host).err(),
+            Some(types::ViewError::ElectionNotFound),
+            "Should not find a voter of an election that does not exist"
+        );
+    }
+
+    #[concordium_test]
+    fn test_recovery() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let result_message_bytes = test_utils::result_message(None);
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Voter 2 (index 1) stalls and never votes, voter 1 and 4 vote yes and voter 3 votes no
+        let votes = [
+            Some(ProjectivePoint::GENERATOR),
+            None,
+            Some(ProjectivePoint::IDENTITY),
+            Some(ProjectivePoint::GENERATOR),
+        ];
+        test_utils::election_mut(&mut host).voting_keys = vec![list_of_voting_keys
+            .iter()
+            .map(|g_x| g_x.to_bytes().to_vec())
+            .collect()];
+        for (i, (x, g_x)) in key_pairs.iter().enumerate() {
+            let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+            let g_v = votes[i].unwrap_or(ProjectivePoint::IDENTITY);
+
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    voting_keys: vec![g_x.to_bytes().to_vec()],
+                    reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                    commitments: vec![off_chain::commit_to_vote(x, &g_y, g_v)],
+                    votes: match votes[i] {
+                        Some(g_v) => vec![((g_y * x) + g_v).to_bytes().to_vec()],
+                        None => Vec::new(),
+                    },
+                    index: i as i32,
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        // Vote time is over, but enough voters have voted to recover
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(301));
+
+        let result = change_phase(&ctx, &mut host, &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Recovery,
+            "Should change to recovery phase since one voter did not vote"
+        );
+        claim_eq!(
+            test_utils::election(&host).missing_voters,
+            vec![1],
+            "Voter with index 1 should be missing"
+        );
+
+        // Voters who voted send their recovery keys
+        let recovery_messages_bytes: Vec<_> = key_pairs
+            .iter()
+            .enumerate()
+            .map(|(i, (x, g_x))| {
+                let h = util::compute_recovery_base(&list_of_voting_keys, i as i32, &[1]);
+                to_bytes(&RecoveryMessage {
+                    election_id: test_utils::ELECTION_ID,
+                    recovery_keys: vec![off_chain::create_recovery_key(x, &h).to_bytes().to_vec()],
+                    recovery_zkps: vec![off_chain::create_chaum_pedersen_zkp(
+                        *g_x,
+                        h,
+                        *x,
+                        &test_utils::proof_context(accounts[i]),
+                    )],
+                })
+            })
+            .collect();
+
+        // The stalling voter cannot take part in the recovery
+        ctx.set_parameter(&recovery_messages_bytes[0]);
+        ctx.set_sender(Address::Account(accounts[1]));
+
+        claim_eq!(
+            recover(&ctx, &mut host, &mut logger),
+            Err(types::RecoveryError::DidNotVote),
+            "Voter who did not vote should not be able to recover"
+        );
+
+        for i in [0, 2, 3] {
+            ctx.set_parameter(&recovery_messages_bytes[i]);
+            ctx.set_sender(Address::Account(accounts[i]));
+
+            let result = recover(&ctx, &mut host, &mut logger);
+
+            claim!(
+                result.is_ok(),
+                "Contract receive failed, but should not have"
+            );
+        }
+
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Result,
+            "Last recovery key should have moved the vote on to the result phase"
+        );
+
+        ctx.set_parameter(&result_message_bytes);
+
+        claim_eq!(
+            crate::result(&ctx, &mut host, &mut logger),
+            Ok(vec![types::VotingResult {
+                counts: vec![2, 1],
+                outcome: types::VoteOutcome::Passed,
+            }]),
+            "Tally should be computed from the votes that were cast"
+        );
+    }
+
+    #[concordium_test]
+    fn test_recovery_invalid_zkp() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Recovery);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        test_utils::election_mut(&mut host).voting_keys = vec![list_of_voting_keys
+            .iter()
+            .map(|g_x| g_x.to_bytes().to_vec())
+            .collect()];
+        test_utils::election_mut(&mut host).missing_voters = vec![3];
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                voting_keys: vec![key_pairs[0].1.to_bytes().to_vec()],
+                votes: vec![ProjectivePoint::GENERATOR.to_bytes().to_vec()],
+                index: 0,
+                weight: 1,
+                ..Default::default()
+            },
+        );
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        // Voter 1 sends a recovery key using a different secret than their voting key
+        let (other_x, _) = off_chain::create_votingkey_pair();
+        let h = util::compute_recovery_base(&list_of_voting_keys, 0, &[3]);
+        let recovery_message = RecoveryMessage {
+            election_id: test_utils::ELECTION_ID,
+            recovery_keys: vec![off_chain::create_recovery_key(&other_x, &h)
+                .to_bytes()
+                .to_vec()],
+            recovery_zkps: vec![off_chain::create_chaum_pedersen_zkp(
+                key_pairs[0].1,
+                h,
+                other_x,
+                &test_utils::proof_context(accounts[0]),
+            )],
+        };
+        let recovery_message_bytes = to_bytes(&recovery_message);
+        ctx.set_parameter(&recovery_message_bytes);
+
+        claim_eq!(
+            recover(&ctx, &mut host, &mut logger),
+            Err(types::RecoveryError::InvalidZKP),
+            "Recovery key should not be accepted with a different secret"
+        );
+    }
+
+    #[concordium_test]
+    fn test_recovery_timeout() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Recovery);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Voters 1 to 3 voted and voter 4 (index 3) did not
+        test_utils::election_mut(&mut host).voting_keys = vec![list_of_voting_keys
+            .iter()
+            .map(|g_x| g_x.to_bytes().to_vec())
+            .collect()];
+        test_utils::election_mut(&mut host).missing_voters = vec![3];
+        for (i, (_, g_x)) in key_pairs.iter().enumerate().take(3) {
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    voting_keys: vec![g_x.to_bytes().to_vec()],
+                    votes: vec![ProjectivePoint::GENERATOR.to_bytes().to_vec()],
+                    index: i as i32,
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        let recovery_messages_bytes: Vec<_> = key_pairs
+            .iter()
+            .enumerate()
+            .map(|(i, (x, g_x))| {
+                let h = util::compute_recovery_base(&list_of_voting_keys, i as i32, &[3]);
+                to_bytes(&RecoveryMessage {
+                    election_id: test_utils::ELECTION_ID,
+                    recovery_keys: vec![off_chain::create_recovery_key(x, &h).to_bytes().to_vec()],
+                    recovery_zkps: vec![off_chain::create_chaum_pedersen_zkp(
+                        *g_x,
+                        h,
+                        *x,
+                        &test_utils::proof_context(accounts[i]),
+                    )],
+                })
+            })
+            .collect();
+
+        // Voter 1 sends their recovery key in time
+        ctx.set_parameter(&recovery_messages_bytes[0]);
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(301));
+
+        claim_eq!(
+            recover(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Recovery,
+            "Should stay in the recovery phase until all voters who voted have recovered"
+        );
+
+        // Voter 2 sends their recovery key after the recovery timeout
+        ctx.set_parameter(&recovery_messages_bytes[1]);
+        ctx.set_sender(Address::Account(accounts[1]));
+        ctx.metadata_mut()
+            .set_slot_time(Timestamp::from_timestamp_millis(401));
+
+        claim_eq!(
+            recover(&ctx, &mut host, &mut logger),
+            Ok(()),
+            "Late recovery should only apply the phase change"
+        );
+        claim_eq!(
+            test_utils::election(&host).voting_phase,
+            types::VotingPhase::Abort,
+            "Recovery timeout should have aborted the vote"
+        );
+        claim_eq!(
+            test_utils::election(&host).voters.get(&accounts[1]).unwrap().recovery_keys,
+            Vec::<Vec<u8>>::new(),
+            "Late recovery key should not have been stored"
+        );
+        claim_eq!(
+            test_utils::election(&host).number_of_recoveries,
+            1,
+            "Only the recovery key sent in time should have been counted"
+        );
+        claim!(
+            logger.logs.contains(&test_utils::event(types::VotingEvent::ChangePhase {
+                from: types::VotingPhase::Recovery,
+                to: types::VotingPhase::Abort,
+                abort_reason: Some(types::AbortReason::NotAllRecovered),
+            })),
+            "Abort should have been logged"
+        );
+    }
+
+    #[concordium_test]
+    fn test_refund_deposits_recovery() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Recovery);
+
+        let (_ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Voter 1 and 2 voted and recovered, voter 3 voted but did not recover and voter 4 did not vote
+        let point = ProjectivePoint::GENERATOR.to_bytes().to_vec();
+        for account in accounts.iter().take(2) {
+            test_utils::election_mut(&mut host).voters.insert(
+                *account,
+                Voter {
+                    votes: vec![point.clone()],
+                    recovery_keys: vec![point.clone()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[2],
+            Voter {
+                votes: vec![point.clone()],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+
+        // Voter who did not recover is not slashed, since they were refunded when voting, but gets no reward for reporting
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[2], &mut test_utils::election_mut(&mut host), &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::balance(&host, &accounts[2]),
+            Amount::zero(),
+            "Account[2] should not get a reward, since they did not take part in the recovery"
+        );
+
+        // Recovery participant is honest and rewarded
+        let result = refund_deposits(test_utils::ELECTION_ID, accounts[1], &mut test_utils::election_mut(&mut host), &mut logger);
+
+        claim!(
+            result.is_ok(),
+            "Contract receive failed, but should not have"
+        );
+        claim_eq!(
+            test_utils::balance(&host, &accounts[1]),
+            Amount::from_micro_ccd(1),
+            "Account[1] should get the deposit of the stalling voter"
+        );
+    }
+
+    #[concordium_test]
+    fn test_slashing_policies() {
+        let sink = AccountAddress([100u8; 32]);
+        let half = types::Fraction {
+            numerator: 1,
+            denominator: 2,
+        };
+
+        // Voter 1 and 2 voted, voter 3, 4 and 5 stalled, slashing 3 deposits of 7.
+        // Cases are the policy, the caller of change_phase, the balances of voter 1, voter 2, voter 3 and the sink, and the amount left to sweep
+        let cases = [
+            (types::SlashingPolicy::Burn(sink), 0, [7, 7, 0, 21], 0),
+            (types::SlashingPolicy::SplitAmongHonest, 0, [17, 17, 0, 0], 1),
+            (types::SlashingPolicy::Reporter(half), 1, [7, 17, 0, 0], 11),
+            (types::SlashingPolicy::Reporter(half), 2, [7, 7, 0, 0], 21),
+        ];
+
+        for (policy, caller, balances, left) in cases {
+            let mut logger = TestLogger::init();
+            let (accounts, mut vote_config, _) =
+                test_utils::setup_test_config(5, Amount::from_micro_ccd(7));
+            vote_config.slashing_policy = policy;
+
+            let (state, state_builder) =
+                test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+            let (mut ctx, mut host) =
+                test_utils::setup_receive_context(None, accounts[caller], state, state_builder);
+
+            // The voters were refunded when voting, so their refunds are still owed besides the 3 stalled deposits
+            test_utils::set_funds(&mut host, Amount::from_micro_ccd(35));
+            for account in accounts.iter().take(2) {
+                test_utils::election_mut(&mut host).voters.insert(
+                    *account,
+                    Voter {
+                        votes: vec![ProjectivePoint::GENERATOR.to_bytes().to_vec()],
+                        weight: 1,
+                        ..Default::default()
+                    },
+                );
+                credit(&mut test_utils::election_mut(&mut host), *account, Amount::from_micro_ccd(7));
+            }
+            test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+            // Too few voted before the vote timeout
+            ctx.metadata_mut()
+                .set_slot_time(Timestamp::from_timestamp_millis(301));
+            claim_eq!(
+                change_phase(&ctx, &mut host, &mut logger),
+                Ok(()),
+                "Contract receive failed, but should not have"
+            );
+            claim_eq!(
+                test_utils::election(&host).voting_phase,
+                types::VotingPhase::Abort,
+                "Should abort since too few voted"
+            );
+
+            for (account, balance) in [accounts[0], accounts[1], accounts[2], sink]
+                .iter()
+                .zip(balances)
+            {
+                claim_eq!(
+                    test_utils::balance(&host, account),
+                    Amount::from_micro_ccd(balance),
+                    "Slashed deposits should have been paid out by the policy"
+                );
+            }
+
+            // The organizer sweeps what is not owed to anyone
+            ctx.set_sender(Address::Account(test_utils::ORGANIZER));
+            let swept = sweep(&ctx, &mut host, &mut logger);
+            if left == 0 {
+                claim_eq!(
+                    swept,
+                    Err(types::SweepError::NothingToSweep),
+                    "Everything should be owed"
+                );
+            } else {
+                claim_eq!(
+                    swept,
+                    Ok(Amount::from_micro_ccd(left)),
+                    "Leftover deposits should have been swept"
+                );
+            }
+            claim_eq!(
+                host.self_balance(),
+                test_utils::election(&host).total_balance,
+                "Only the owed balances should be left"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_slashing_policies_recovery() {
+        let sink = AccountAddress([100u8; 32]);
+        let half = types::Fraction {
+            numerator: 1,
+            denominator: 2,
+        };
+
+        // Voter 1 and 2 voted and recovered, voter 3 voted but did not recover, voter 4 and 5 did not vote, slashing 2 deposits of 7.
+        // Cases are the policy, the caller of change_phase, the balances of voter 1, voter 2, voter 3 and the sink, and the amount left to sweep
+        let cases = [
+            (types::SlashingPolicy::Burn(sink), 0, [7, 7, 7, 14], 0),
+            (types::SlashingPolicy::SplitAmongHonest, 0, [14, 14, 7, 0], 0),
+            (types::SlashingPolicy::Reporter(half), 1, [7, 14, 7, 0], 7),
+            (types::SlashingPolicy::Reporter(half), 2, [7, 7, 7, 0], 14),
+        ];
+
+        for (policy, caller, balances, left) in cases {
+            let mut logger = TestLogger::init();
+            let (accounts, mut vote_config, _) =
+                test_utils::setup_test_config(5, Amount::from_micro_ccd(7));
+            vote_config.slashing_policy = policy;
+
+            let (state, state_builder) =
+                test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Recovery);
+
+            let (mut ctx, mut host) =
+                test_utils::setup_receive_context(None, accounts[caller], state, state_builder);
+
+            // The voters were refunded when voting, so their refunds are still owed besides the 2 stalled deposits
+            test_utils::set_funds(&mut host, Amount::from_micro_ccd(35));
+            let point = ProjectivePoint::GENERATOR.to_bytes().to_vec();
+            for (i, account) in accounts.iter().take(3).enumerate() {
+                let recovery_keys = if i < 2 { vec![point.clone()] } else { Vec::new() };
+                test_utils::election_mut(&mut host).voters.insert(
+                    *account,
+                    Voter {
+                        votes: vec![point.clone()],
+                        recovery_keys,
+                        weight: 1,
+                        ..Default::default()
+                    },
+                );
+                credit(&mut test_utils::election_mut(&mut host), *account, Amount::from_micro_ccd(7));
+            }
+            test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+            // Not all voters recovered before the recovery timeout
+            ctx.metadata_mut()
+                .set_slot_time(Timestamp::from_timestamp_millis(401));
+            claim_eq!(
+                change_phase(&ctx, &mut host, &mut logger),
+                Ok(()),
+                "Contract receive failed, but should not have"
+            );
+            claim_eq!(
+                test_utils::election(&host).voting_phase,
+                types::VotingPhase::Abort,
+                "Should abort since not all voters recovered"
+            );
+
+            for (account, balance) in [accounts[0], accounts[1], accounts[2], sink]
+                .iter()
+                .zip(balances)
+            {
+                claim_eq!(
+                    test_utils::balance(&host, account),
+                    Amount::from_micro_ccd(balance),
+                    "Only the deposits of the voters who did not vote should have been slashed"
+                );
+            }
+
+            // The organizer sweeps what is not owed to anyone
+            ctx.set_sender(Address::Account(test_utils::ORGANIZER));
+            let swept = sweep(&ctx, &mut host, &mut logger);
+            if left == 0 {
+                claim_eq!(
+                    swept,
+                    Err(types::SweepError::NothingToSweep),
+                    "Everything should be owed"
+                );
+            } else {
+                claim_eq!(
+                    swept,
+                    Ok(Amount::from_micro_ccd(left)),
+                    "Leftover deposits should have been swept"
+                );
+            }
+            claim_eq!(
+                host.self_balance(),
+                test_utils::election(&host).total_balance,
+                "Only the owed balances should be left"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_reject_codes() {
+        // Clients rely on the reject codes, so new error variants go after the existing ones
+        let codes = [
+            (Reject::from(types::SetupError::InvalidNumberOfVoters), -7),
+            (Reject::from(types::RegisterError::InvalidVotingKey), -12),
+            (Reject::from(types::CommitError::InvalidReconstructedKey), -8),
+            (Reject::from(types::VoteError::InvalidZKP), -8),
+            (Reject::from(types::VoteError::AlreadyVoted), -10),
+            (Reject::from(types::ResultError::NotResultPhase), -2),
+            (Reject::from(types::ChangeError::TransferRefund), -3),
+        ];
+        for (reject, code) in codes {
+            claim_eq!(
+                reject.error_code.get(),
+                code,
+                "Reject code of an existing error should not have changed"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_sweep_not_allowed() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Vote);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+        test_utils::set_funds(&mut host, Amount::from_micro_ccd(3));
+
+        claim_eq!(
+            sweep(&ctx, &mut host, &mut logger),
+            Err(types::SweepError::NotOrganizer),
+            "Only the organizer should sweep"
+        );
+
+        ctx.set_sender(Address::Account(test_utils::ORGANIZER));
+        claim_eq!(
+            sweep(&ctx, &mut host, &mut logger),
+            Err(types::SweepError::ElectionNotEnded),
+            "Deposits should not be swept before the election has ended"
+        );
+        claim_eq!(
+            host.self_balance(),
+            Amount::from_micro_ccd(3),
+            "Nothing should have been transferred"
+        );
+    }
+
+    #[concordium_test]
+    fn test_try_compute_keys() {
+        let keys: Vec<ProjectivePoint> = (0..3).map(|_| off_chain::create_votingkey_pair().1).collect();
+
+        // The fallible functions agree with the trapping ones on valid input
+        claim_eq!(
+            util::try_compute_reconstructed_key(&keys, 1),
+            Ok(util::compute_reconstructed_key(&keys, 1)),
+            "Reconstructed keys should be equal"
+        );
+        claim_eq!(
+            util::try_compute_recovery_base(&keys, 1, &[0, 2]),
+            Ok(util::compute_recovery_base(&keys, 1, &[0, 2])),
+            "Recovery bases should be equal"
+        );
+
+        // Missing keys are an error rather than a trap
+        claim_eq!(
+            util::try_compute_reconstructed_key(&[], 0),
+            Err(util::UtilError::MissingValue),
+            "Reconstructed key of no keys should be missing"
+        );
+        claim_eq!(
+            util::try_compute_recovery_base(&keys, 1, &[3]),
+            Err(util::UtilError::MissingValue),
+            "Key of a missing voter outside the list should be missing"
+        );
+    }
+
+    #[concordium_test]
+    fn test_hash_to_scalar() {
+        // Test vectors: SHA-512 of the input, reduced modulo the secp256k1 group order
+        let vectors: [(Vec<u8>, &str); 3] = [
+            (
+                Vec::new(),
+                "2212ebe6b10493a445ef09e6931a18558e359eab4ef00c7432738cd8e18efd59",
+            ),
+            (
+                b"abc".to_vec(),
+                "f9726d8a91c103c2e7b921ea5462e94cac0a3de5849a441281b991d1fa2ffc3d",
+            ),
+            (
+                (0..64).collect(),
+                "86d5a3ec628da6bce7c6e66b472830c94e8c678c275690deac9a058c89e36fc0",
+            ),
+        ];
+        for (input, expected) in vectors.iter() {
+            claim_eq!(
+                hex::encode(util::hash_to_scalar(input.clone()).to_bytes()),
+                *expected,
+                "Hash to scalar does not match the test vector"
+            );
+        }
+
+        // Proofs created off-chain always verify, as no challenge can be out of range
+        let (accounts, _, _) = test_utils::setup_test_config(1, Amount::from_micro_ccd(0));
+        let context = test_utils::proof_context(accounts[0]);
+        for _ in 0..50 {
+            let (x, g_x) = off_chain::create_votingkey_pair();
+            claim_eq!(
+                crypto::verify_schnorr_zkp(
+                    g_x,
+                    off_chain::create_schnorr_zkp(g_x, x, &context),
+                    &context
+                ),
+                Ok(true),
+                "Honest proof should always verify"
+            );
+        }
+    }
+
+    #[concordium_test]
+    fn test_compute_tally() {
+        for t in [0u64, 1, 7, 99, 100] {
+            let vote_aggregate = ProjectivePoint::GENERATOR * k256::Scalar::from(t);
+            claim_eq!(
+                crypto::compute_tally(vote_aggregate, 100),
+                Some(t),
+                "Tally should be found when within the bound"
+            );
+        }
+
+        let vote_aggregate = ProjectivePoint::GENERATOR * k256::Scalar::from(101u64);
+        claim_eq!(
+            crypto::compute_tally(vote_aggregate, 100),
+            None,
+            "Tally should not be found when above the bound"
+        );
+
+        claim_eq!(
+            crypto::compute_tally(-ProjectivePoint::GENERATOR, 100),
+            None,
+            "Tally should not be found when not in range"
+        );
+
+        // The bound of a ballot with many candidates can be too large to search
+        claim_eq!(
+            crypto::compute_tally(ProjectivePoint::GENERATOR, u64::MAX),
+            None,
+            "Tally should not be searched for when the bound is too large"
+        );
+        let bound = util::MAX_BABY_STEPS * util::MAX_BABY_STEPS;
+        claim_eq!(
+            crypto::compute_tally(ProjectivePoint::GENERATOR, bound),
+            None,
+            "Tally should not be searched for when the bound needs too many baby steps"
+        );
+        claim_eq!(
+            crypto::compute_tally(ProjectivePoint::GENERATOR * k256::Scalar::from(bound - 1), bound - 1),
+            Some(bound - 1),
+            "Tally should be found at the largest bound that can be searched"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_tally_not_in_range() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(3, Amount::from_micro_ccd(1));
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
+
+        let result_message_bytes = test_utils::result_message(None);
+        let (ctx, mut host) = test_utils::setup_receive_context(
+            Some(&result_message_bytes),
+            accounts[0],
+            state,
+            state_builder,
+        );
+
+        // Votes whose sum is g^4, which is more than the number of voters
+        test_utils::election_mut(&mut host).voters.insert(
+            accounts[0],
+            Voter {
+                votes: vec![(ProjectivePoint::GENERATOR * k256::Scalar::from(4u64))
+                    .to_bytes()
+                    .to_vec()],
+                weight: 1,
+                ..Default::default()
+            },
+        );
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        claim_eq!(
+            result(&ctx, &mut host, &mut logger),
+            Err(types::ResultError::TallyNotInRange),
+            "Result should fail when the tally is not in range"
+        );
+    }
+
+    #[concordium_test]
+    fn test_result_claimed() {
+        let mut logger = TestLogger::init();
+        let (accounts, vote_config, _) =
+            test_utils::setup_test_config(4, Amount::from_micro_ccd(1));
+
+        let key_pairs: Vec<_> = (0..4).map(|_| off_chain::create_votingkey_pair()).collect();
+        let list_of_voting_keys: Vec<_> = key_pairs.iter().map(|(_, g_x)| *g_x).collect();
+
+        let (state, state_builder) =
+            test_utils::setup_state(&accounts, vote_config, types::VotingPhase::Result);
+
+        let (mut ctx, mut host) =
+            test_utils::setup_receive_context(None, accounts[0], state, state_builder);
+
+        // Voter 1 and 2 vote no, voter 3 and 4 vote yes
+        let votes = [
+            ProjectivePoint::IDENTITY,
+            ProjectivePoint::IDENTITY,
+            ProjectivePoint::GENERATOR,
+            ProjectivePoint::GENERATOR,
+        ];
+        for (i, (x, _)) in key_pairs.iter().enumerate() {
+            let g_y = util::compute_reconstructed_key(&list_of_voting_keys, i as i32);
+
+            test_utils::election_mut(&mut host).voters.insert(
+                accounts[i],
+                Voter {
+                    reconstructed_keys: vec![g_y.to_bytes().to_vec()],
+                    votes: vec![((g_y * x) + votes[i]).to_bytes().to_vec()],
+                    weight: 1,
+                    ..Default::default()
+                },
+            );
+        }
+
+        test_utils::aggregate_votes(&mut test_utils::election_mut(&mut host));
+
+        // Compute the result off-chain from the published votes
+        let (published_votes, recovery_keys): (Vec<_>, Vec<_>) = test_utils::election(&host)
+            .voters
+            .iter()
+            .map(|(_, v)| {
+                (
+                    v.votes.first().cloned().unwrap_or_default(),
+                    v.recovery_keys.first().cloned().unwrap_or_default(),
+                )
+            })
+            .unzip();
+        let claimed_result = off_chain::compute_result(&published_votes, &recovery_keys, &[1; 4], 0, 4);
+
+        claim_eq!(
+            claimed_result,
+            Some(vec![2, 2]),
+            "Off-chain result should be 2 yes and 2 no"
+        );
+
+        // Wrong claims are rejected
+        let wrong_results_bytes = [
+            test_utils::result_message(Some(vec![vec![3, 1]])),
+            test_utils::result_message(Some(vec![vec![2, 2, 0]])),
+            test_utils::result_message(Some(vec![vec![5, -1]])),
+        ];
+        for wrong_result_bytes in wrong_results_bytes.iter() {
+            ctx.set_parameter(wrong_result_bytes);
+
+            claim_eq!(
+                result(&ctx, &mut host, &mut logger),
+                Err(types::ResultError::WrongClaimedResult),
+                "Wrong claimed result should be rejected"
+            );
+        }
+
+        let claimed_result_bytes = test_utils::result_message(claimed_result.map(|r| vec![r]));
+        ctx.set_parameter(&claimed_result_bytes);
+
+        claim_eq!(
+            result(&ctx, &mut host, &mut logger),
+            Ok(vec![types::VotingResult {
+                counts: vec![2, 2],
+                outcome: types::VoteOutcome::Rejected,
+            }]),
+            "Correct claimed result should be accepted"
+        );
+        claim_eq!(
+            vec![vec![2, 2]],
+            test_utils::election(&host).voting_result,
+            "Wrong voting result"
+        )
     }
 }
@@ -1,10 +1,12 @@
 //! Rust file containing utility functions for unit tests.
 
-use crate::{types::VotingPhase, VoteConfig, VotingState};
+use crate::{types::VotingPhase, Election, VoteConfig, Voter, VotingState};
 use concordium_std::*;
+use group::GroupEncoding;
+use k256::ProjectivePoint;
 use test_infrastructure::*;
 
-/// Creates a list of voter accounts and a config for testing
+/// Creates a list of voter accounts and a config for testing, in which every voter has weight 1
 #[concordium_cfg_test]
 pub fn setup_test_config(
     number_of_accounts: i32,
@@ -19,78 +21,248 @@ pub fn setup_test_config(
         voters.push(AccountAddress([i as u8; 32]))
     }
 
-    let merkle_tree = off_chain::create_merkle_tree(&voters);
+    let leaves: Vec<_> = voters.iter().map(|voter| (*voter, 1)).collect();
+    let merkle_tree = off_chain::create_merkle_tree(&leaves);
 
     let vote_config = VoteConfig {
         merkle_root: merkle_tree.root_hex().unwrap(),
         merkle_leaf_count: number_of_accounts,
-        voting_question: "Vote for x".to_string(),
+        total_weight: number_of_accounts,
+        voting_questions: vec!["Vote for x".to_string()],
+        voting_options: Vec::new(),
+        ballot_type: crate::types::BallotType::Single,
         deposit,
         registration_timeout: Timestamp::from_timestamp_millis(100),
         commit_timeout: Timestamp::from_timestamp_millis(200),
         vote_timeout: Timestamp::from_timestamp_millis(300),
+        recovery_timeout: Timestamp::from_timestamp_millis(400),
+        min_registrants: 3,
+        quorum: crate::types::Fraction {
+            numerator: 0,
+            denominator: 1,
+        },
+        pass_rule: crate::types::PassRule::Majority,
+        slashing_policy: crate::types::SlashingPolicy::Reporter(crate::types::Fraction {
+            numerator: 1,
+            denominator: 1,
+        }),
     };
 
     (voters, vote_config, merkle_tree)
 }
 
-/// Creates a test init context with the given parameter
-pub fn setup_init_context(parameter: &Vec<u8>) -> TestInitContext {
+/// Account of the organizer, who creates the election in tests
+pub const ORGANIZER: AccountAddress = AccountAddress([255u8; 32]);
+
+/// Creates a test init context
+pub fn setup_init_context() -> TestInitContext<'static> {
     let mut ctx = TestInitContext::empty();
-    ctx.set_parameter(parameter);
+    ctx.set_init_origin(ORGANIZER);
     ctx.metadata_mut()
         .set_slot_time(Timestamp::from_timestamp_millis(1));
 
     ctx
 }
 
-/// Creates the test state and state builder from the list of accounts, config and desired voting phase
+/// Creates the test state of a contract without elections, and the state builder
+pub fn setup_empty_state() -> (VotingState<TestStateApi>, TestStateBuilder) {
+    let mut state_builder = TestStateBuilder::new();
+    let state = VotingState {
+        elections: state_builder.new_map(),
+        next_election_id: 0,
+    };
+
+    (state, state_builder)
+}
+
+/// Id of the election the test state holds
+pub const ELECTION_ID: u64 = 0;
+
+/// Creates the test state and state builder from the list of accounts, config and desired voting phase.
+/// The state holds a single election, with id ELECTION_ID
 pub fn setup_state(
-    accounts: &Vec<AccountAddress>,
+    accounts: &[AccountAddress],
     vote_config: VoteConfig,
     phase: VotingPhase,
 ) -> (VotingState<TestStateApi>, TestStateBuilder) {
     let mut state_builder = TestStateBuilder::new();
     let mut voters = state_builder.new_map();
 
-    // Add voters to starting state if we are not testing registration and instead one of the later phases with state
+    // Add voters with weight 1 to starting state if we are not testing registration and instead one of the later phases with state
+    let mut number_of_voters = 0;
+    let mut registered_weight = 0;
     if phase != VotingPhase::Registration {
-        for account in accounts.into_iter() {
-            voters.insert(*account, Default::default());
+        for account in accounts.iter() {
+            voters.insert(*account, Voter {
+                weight: 1,
+                ..Default::default()
+            });
+            number_of_voters += 1;
+            registered_weight += 1;
         }
     }
 
-    let state = VotingState {
+    let number_of_questions = vote_config.voting_questions.len();
+    let election = Election {
         config: vote_config,
+        organizer: ORGANIZER,
         voting_phase: phase,
-        voting_result: (-1, -1),
+        voting_result: Vec::new(),
+        voting_outcome: Vec::new(),
         voters,
-        voting_keys: Vec::new(),
+        voting_keys: vec![Vec::new(); number_of_questions],
+        missing_voters: Vec::new(),
+        vote_aggregates: vec![ProjectivePoint::IDENTITY.to_bytes().to_vec(); number_of_questions],
+        number_of_voters,
+        number_of_commitments: 0,
+        number_of_votes: 0,
+        registered_weight,
+        voted_weight: 0,
+        number_of_recoveries: 0,
+        balances: state_builder.new_map(),
+        total_balance: Amount::zero(),
+        funds: Amount::zero(),
+    };
+
+    let mut elections = state_builder.new_map();
+    elections.insert(ELECTION_ID, election);
+    let state = VotingState {
+        elections,
+        next_election_id: ELECTION_ID + 1,
     };
 
     (state, state_builder)
 }
 
+/// Sets the numbers of registered and committed voters from the voters in the state, as if they had called register and commit
+pub fn count_voters(state: &mut Election<TestStateApi>) {
+    state.number_of_voters = state.voters.iter().count() as i32;
+    state.number_of_commitments = state
+        .voters
+        .iter()
+        .filter(|(_, voter)| !voter.commitments.is_empty())
+        .count() as i32;
+}
+
+/// Sets the running aggregates of the votes from the voters in the state, as if they had called vote and recover
+pub fn aggregate_votes(state: &mut Election<TestStateApi>) {
+    let mut vote_aggregates = vec![ProjectivePoint::IDENTITY; state.config.voting_questions.len()];
+    let mut number_of_votes = 0;
+    let mut voted_weight = 0;
+    let mut number_of_recoveries = 0;
+
+    for (_, voter) in state.voters.iter() {
+        if !voter.votes.is_empty() {
+            for (vote_aggregate, vote) in vote_aggregates.iter_mut().zip(voter.votes.iter()) {
+                *vote_aggregate += util::convert_vec_to_point(vote);
+            }
+            number_of_votes += 1;
+            voted_weight += voter.weight as i32;
+        }
+        if !voter.recovery_keys.is_empty() {
+            for (vote_aggregate, recovery_key) in
+                vote_aggregates.iter_mut().zip(voter.recovery_keys.iter())
+            {
+                *vote_aggregate -= util::convert_vec_to_point(recovery_key);
+            }
+            number_of_recoveries += 1;
+        }
+    }
+
+    state.vote_aggregates = vote_aggregates
+        .iter()
+        .map(|vote_aggregate| vote_aggregate.to_bytes().to_vec())
+        .collect();
+    state.number_of_votes = number_of_votes;
+    state.voted_weight = voted_weight;
+    state.number_of_recoveries = number_of_recoveries;
+}
+
+/// The test election
+pub fn election(
+    host: &TestHost<VotingState<TestStateApi>>,
+) -> StateRef<'_, Election<TestStateApi>> {
+    host.state().elections.get(&ELECTION_ID).unwrap()
+}
+
+/// The test election, to be changed. Changes are stored when it is dropped
+pub fn election_mut(
+    host: &mut TestHost<VotingState<TestStateApi>>,
+) -> StateRefMut<'_, Election<TestStateApi>, TestStateApi> {
+    host.state_mut().elections.get_mut(&ELECTION_ID).unwrap()
+}
+
+/// Sets the CCD held by the contract, all of it for the test election
+pub fn set_funds(host: &mut TestHost<VotingState<TestStateApi>>, amount: Amount) {
+    host.set_self_balance(amount);
+    election_mut(host).funds = amount;
+}
+
+/// The event as logged for the test election
+pub fn event(event: crate::types::VotingEvent) -> Vec<u8> {
+    to_bytes(&crate::types::ElectionEvent {
+        election_id: ELECTION_ID,
+        event,
+    })
+}
+
+/// Amount the account can withdraw from the test election
+pub fn balance(host: &TestHost<VotingState<TestStateApi>>, account: &AccountAddress) -> Amount {
+    election(host)
+        .balances
+        .get(account)
+        .map(|balance| *balance)
+        .unwrap_or_else(Amount::zero)
+}
+
+/// Address of the contract instance in tests
+pub const CONTRACT_ADDRESS: ContractAddress = ContractAddress {
+    index: 0,
+    subindex: 0,
+};
+
+/// Creates the proof context of the sender for ZKPs sent to the contract in tests
+pub fn proof_context(sender: AccountAddress) -> util::ProofContext {
+    util::ProofContext {
+        contract_address: CONTRACT_ADDRESS,
+        election_id: ELECTION_ID,
+        sender,
+    }
+}
+
+/// Serialization of ELECTION_ID, as a parameter
+const ELECTION_ID_PARAMETER: [u8; 8] = ELECTION_ID.to_le_bytes();
+
+/// Creates the parameter of result for the test election, with the claimed result of each question if any
+pub fn result_message(claimed_result: Option<Vec<Vec<i32>>>) -> Vec<u8> {
+    to_bytes(&crate::ResultMessage {
+        election_id: ELECTION_ID,
+        claimed_result,
+    })
+}
+
 /// Creates a test receive context and a host with the parameter from the sender and with the given state
 pub fn setup_receive_context(
     parameter: Option<&Vec<u8>>,
     sender: AccountAddress,
     state: VotingState<TestStateApi>,
     state_builder: TestStateBuilder,
-) -> (TestReceiveContext, TestHost<VotingState<TestStateApi>>) {
+) -> (TestReceiveContext<'_>, TestHost<VotingState<TestStateApi>>) {
     let mut ctx = TestReceiveContext::empty();
     let mut host = TestHost::new(state, state_builder);
 
-    // Set parameter if it exists
+    // Set parameter if it exists, otherwise it is the id of the test election, like when calling a function taking only the election id
     match parameter {
         Some(p) => {
             ctx.set_parameter(p);
-            ()
         }
-        None => (),
+        None => {
+            ctx.set_parameter(&ELECTION_ID_PARAMETER);
+        }
     };
 
     ctx.set_sender(Address::Account(sender));
+    ctx.set_self_address(CONTRACT_ADDRESS);
     host.set_self_balance(Amount::from_micro_ccd(0));
     ctx.metadata_mut()
         .set_slot_time(Timestamp::from_timestamp_millis(1));
@@ -6,16 +6,167 @@ use concordium_std::*;
 pub type RegistrationTimeout = Timestamp;
 pub type CommitTimeout = Timestamp;
 pub type VoteTimeout = Timestamp;
+pub type RecoveryTimeout = Timestamp;
 
 /// Enums
 
-#[derive(Serialize, PartialEq, SchemaType, Debug)]
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
 pub enum VotingPhase {
     Registration,
     Commit,
     Vote,
+    Recovery,
     Result,
     Abort,
+    Cancelled,
+}
+
+/// A fraction numerator/denominator, e.g. 2/3
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone, Copy)]
+pub struct Fraction {
+    pub numerator: u32,
+    pub denominator: u32,
+}
+
+/// Rule for a vote to pass, on the share of the votes cast for yes (or for the leading candidate)
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub enum PassRule {
+    // More than half of the votes
+    Majority,
+    // At least the given fraction of the votes, which must be more than half (e.g. 2/3)
+    Supermajority(Fraction),
+    // All of the votes
+    Unanimity,
+}
+
+/// What happens to the deposits of stalling voters, which are slashed when the vote aborts or completes without them
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub enum SlashingPolicy {
+    // All slashed deposits go to the sink account set by the organizer
+    Burn(AccountAddress),
+    // Slashed deposits are split evenly among the honest voters
+    SplitAmongHonest,
+    // The given fraction of the slashed deposits goes to the account that reported the stall, by causing the phase change
+    Reporter(Fraction),
+}
+
+/// How a ballot is filled in. Approval and score ballots are votes between candidates (the voting options)
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub enum BallotType {
+    // A single yes/no vote, or a vote for exactly one of the candidates
+    Single,
+    // Approve any of the candidates, up to the given number of them
+    Approval(u32),
+    // Give every candidate a score, within the given bounds
+    Score(ScoreRule),
+    // Anonymous veto: the result only tells whether anyone vetoed, never how many did
+    Veto,
+}
+
+/// Bounds of a score ballot: every candidate gets 0..=max_score points, and at most budget points in total
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone, Copy)]
+pub struct ScoreRule {
+    pub max_score: u32,
+    pub budget: u32,
+}
+
+/// Outcome of a vote, stored and returned along with the counts
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub enum VoteOutcome {
+    Passed,
+    Rejected,
+    // Fewer voted than the quorum of the eligible voters
+    QuorumNotMet,
+    // Someone vetoed, in a veto election
+    Vetoed,
+}
+
+/// The counts of a question, (yes, no) for yes/no votes, the count of each candidate for single and approval ballots
+/// and the score sum of each candidate for score ballots, along with the outcome. Veto elections have no counts
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub struct VotingResult {
+    pub counts: Vec<i32>,
+    pub outcome: VoteOutcome,
+}
+
+/// Reasons for a vote to abort, logged along with the change to the abort phase
+#[derive(Serialize, PartialEq, SchemaType, Debug, Clone)]
+pub enum AbortReason {
+    // Fewer than the minimum number of registrants registered before the registration timeout
+    TooFewRegistered,
+    // Not all voters committed before the commit timeout
+    NotAllCommitted,
+    // Less than 3 voters voted before the vote timeout
+    TooFewVoted,
+    // Not all voters who voted sent their recovery key before the recovery timeout
+    NotAllRecovered,
+}
+
+/// Events logged by the contract, tagged with the election they belong to
+#[derive(Serialize, PartialEq, SchemaType, Debug)]
+pub struct ElectionEvent {
+    pub election_id: u64,
+    pub event: VotingEvent,
+}
+
+/// Events logged by the contract on every state transition of an election
+#[derive(Serialize, PartialEq, SchemaType, Debug)]
+pub enum VotingEvent {
+    Setup {
+        voting_questions: Vec<String>,
+        merkle_leaf_count: i32,
+        deposit: Amount,
+    },
+    Register {
+        account: AccountAddress,
+        index: i32,
+        voting_keys: Vec<Vec<u8>>,
+        weight: u32,
+    },
+    Commit {
+        account: AccountAddress,
+    },
+    Vote {
+        account: AccountAddress,
+    },
+    Recover {
+        account: AccountAddress,
+    },
+    ChangePhase {
+        from: VotingPhase,
+        to: VotingPhase,
+        abort_reason: Option<AbortReason>,
+    },
+    Refund {
+        account: AccountAddress,
+        amount: Amount,
+    },
+    Reward {
+        account: AccountAddress,
+        amount: Amount,
+    },
+    Result {
+        results: Vec<VotingResult>,
+    },
+    Withdraw {
+        account: AccountAddress,
+        amount: Amount,
+    },
+    Burn {
+        account: AccountAddress,
+        amount: Amount,
+    },
+    Sweep {
+        account: AccountAddress,
+        amount: Amount,
+    },
+    Cancel {
+        organizer: AccountAddress,
+    },
+    ExtendTimeout {
+        phase: VotingPhase,
+        timeout: Timestamp,
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -30,8 +181,29 @@ pub enum SetupError {
     InvalidVoteTimeout,
     // Deposits should be >=0
     NegativeDeposit,
-    // Must have atleast 3 voters
+    // Must have atleast 3 voters, and the minimum number of registrants must be between 3 and the number of eligible voters
     InvalidNumberOfVoters,
+    // Recovery timeout is not later than the vote timeout
+    InvalidRecoveryTimeout,
+    // Quorum must be a fraction of at most 1
+    InvalidQuorum,
+    // Supermajority must be a fraction of more than a half and at most 1
+    InvalidPassRule,
+    // Candidate votes need atleast 2 options and few enough options for the tally to be decoded
+    InvalidVotingOptions,
+    // Reporter share must be a fraction of at most 1
+    InvalidSlashingPolicy,
+    // Contracts cannot create elections
+    ContractSender,
+    // Must have atleast one question
+    InvalidVotingQuestions,
+    // Approval and score ballots need candidates, and bounds that allow a vote and fit a decodable tally. Veto elections have no candidates
+    InvalidBallotType,
+    // Total weight must be atleast the number of voters, as every voter has a weight of atleast 1
+    InvalidTotalWeight,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -59,8 +231,18 @@ pub enum RegisterError {
     AlreadyRegistered,
     // Invalid ZKP
     InvalidZKP,
-    // Invalid voting key (not valid ECC point)
+    // Invalid voting key (not valid ECC point), or not one key and ZKP per question
     InvalidVotingKey,
+    // No election with the given id
+    ElectionNotFound,
+    // Weight must be atleast 1, and the registered weights can not add up to more than the total weight
+    InvalidWeight,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+    // Failed applying a due phase change
+    #[from(ChangeError)]
+    ChangePhase,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -78,10 +260,18 @@ pub enum CommitError {
     PhaseEnded,
     // Voter was not found
     VoterNotFound,
-    // Something in CommitMessage is just an empty vector
+    // Something in CommitMessage is just an empty vector, or not one per question
     InvalidCommitMessage,
     // g^y is invalid
     InvalidReconstructedKey,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+    // Failed applying a due phase change
+    #[from(ChangeError)]
+    ChangePhase,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -89,8 +279,7 @@ pub enum VoteError {
     // Failed parsing the parameter
     #[from(ParseError)]
     ParseParams,
-    // Failed doing transfer
-    #[from(TransferError)]
+    // Unused since refunds are credited to balances, kept so the reject codes after it do not change
     DoTransfer,
     // Only allow authorized voters
     UnauthorizedVoter,
@@ -108,6 +297,49 @@ pub enum VoteError {
     VoteCommitmentMismatch,
     // Voter already voted
     AlreadyVoted,
+    // Invalid vote (not valid ECC point), or not one vote and ZKP per question
+    InvalidVote,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+    // Failed applying a due phase change
+    #[from(ChangeError)]
+    ChangePhase,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum RecoveryError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Only allow authorized voters
+    UnauthorizedVoter,
+    // Sender cannot be contract
+    ContractSender,
+    // Not in Recovery phase
+    NotRecoveryPhase,
+    // Recovery phase has ended
+    PhaseEnded,
+    // Voter was not found
+    VoterNotFound,
+    // Only voters who voted take part in the recovery
+    DidNotVote,
+    // Voter already sent their recovery key
+    AlreadyRecovered,
+    // Invalid recovery key (not valid ECC point), or not one key and ZKP per question
+    InvalidRecoveryKey,
+    // ZKP not correct
+    InvalidZKP,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+    // Failed applying a due phase change
+    #[from(ChangeError)]
+    ChangePhase,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -117,6 +349,15 @@ pub enum ResultError {
     ParseParams,
     // Not in result phase
     NotResultPhase,
+    // Sum of the votes is not a valid tally
+    TallyNotInRange,
+    // Claimed result does not match the sum of the votes
+    WrongClaimedResult,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
 }
 
 #[derive(Debug, PartialEq, Eq, Reject)]
@@ -126,6 +367,104 @@ pub enum ChangeError {
     ParseParams,
     // Contracts cannot change phase
     ContractSender,
-    #[from(TransferError)]
+    // Unused since refunds are credited to balances, kept so the reject codes after it do not change
     TransferRefund,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum WithdrawError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Failed doing transfer
+    #[from(TransferError)]
+    DoTransfer,
+    // Contracts cannot withdraw
+    ContractSender,
+    // The sender has no refunded deposit or reward to withdraw
+    NothingToWithdraw,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum SweepError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Failed doing transfer
+    #[from(TransferError)]
+    DoTransfer,
+    // Contracts cannot sweep
+    ContractSender,
+    // Only the organizer can sweep
+    NotOrganizer,
+    // The election has not reached the result, abort or cancelled phase
+    ElectionNotEnded,
+    // All of the balance is owed to voters
+    NothingToSweep,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum CancelError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Contracts cannot cancel
+    ContractSender,
+    // Only the organizer can cancel
+    NotOrganizer,
+    // Can only cancel during the registration phase, before its timeout
+    NotRegistrationPhase,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum ExtendError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Contracts cannot extend timeouts
+    ContractSender,
+    // Only the organizer can extend timeouts
+    NotOrganizer,
+    // The phase has ended (or has no timeout)
+    PhaseClosed,
+    // New timeout is not later than the current one
+    NotLater,
+    // New timeout is not earlier than the timeout of the next phase
+    InvalidTimeout,
+    // No election with the given id
+    ElectionNotFound,
+    // Failed logging an event
+    #[from(LogError)]
+    LogEvent,
+}
+
+#[derive(Debug, PartialEq, Eq, Reject)]
+pub enum ViewError {
+    // Failed parsing the parameter
+    #[from(ParseError)]
+    ParseParams,
+    // Voter was not found
+    VoterNotFound,
+    // No election with the given id
+    ElectionNotFound,
 }